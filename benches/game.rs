@@ -0,0 +1,131 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use tetris_rust::bitboard::PieceMasks;
+use tetris_rust::game::{GAME_HEIGHT, GAME_WIDTH, Field, FieldCell, GameState};
+
+const SEED: u64 = 42;
+const SIMULATED_PIECES: u64 = 10_000;
+
+/// bottom of the board filled from row 6, with 4 complete rows
+/// in between rows that have a single hole
+fn dense_field() -> Field {
+    let mut field = [[FieldCell::Empty; GAME_WIDTH]; GAME_HEIGHT];
+    for (y, row) in field.iter_mut().enumerate().skip(6) {
+        for (x, cell) in row.iter_mut().enumerate() {
+            let hole = y % 4 != 0 && x == y % GAME_WIDTH;
+            if !hole {
+                *cell = FieldCell::Garbage;
+            }
+        }
+    }
+    field
+}
+
+/// place a piece with random rotation and column, starting over on a loss
+fn play_random_piece(state: &mut GameState, rng: &mut StdRng) {
+    for _ in 0..rng.gen_range(0, 4) {
+        state.rotate();
+    }
+    let shift: i32 = rng.gen_range(-5, 6);
+    for _ in 0..shift.abs() {
+        if shift < 0 {
+            state.move_left();
+        } else {
+            state.move_right();
+        }
+    }
+    state.move_bottom();
+    if state.is_lost() {
+        *state = GameState::with_seed(rng.gen());
+    }
+}
+
+/// the previous implementation, which shifted every row above each
+/// cleared one, kept as a baseline
+fn eliminate_lines_shifting(field: &mut Field) -> Vec<usize> {
+    let mut cleared = Vec::new();
+    for y in 0..GAME_HEIGHT {
+        if field[y].contains(&FieldCell::Empty) {
+            continue;
+        }
+        cleared.push(y);
+        for h in (3..y + 1).rev() {
+            field[h] = field[h - 1];
+        }
+    }
+    cleared
+}
+
+fn eliminate_lines(c: &mut Criterion) {
+    let mut state = GameState::with_seed(SEED);
+    state.set_field(dense_field());
+    let mut group = c.benchmark_group("eliminate_lines four rows");
+    // the full rows are cleared when the falling piece locks over them
+    group.bench_function("single pass", |b| {
+        b.iter_batched_ref(|| state.clone(), |state| state.move_bottom(), BatchSize::SmallInput)
+    });
+    group.bench_function("shifting (old)", |b| {
+        b.iter_batched_ref(dense_field, eliminate_lines_shifting, BatchSize::SmallInput)
+    });
+    group.finish();
+}
+
+fn check_collision(c: &mut Criterion) {
+    let mut state = GameState::with_seed(SEED);
+    state.set_field(dense_field());
+    // every move checks the moved piece for collisions
+    c.bench_function("check_collision move left/right", |b| {
+        b.iter(|| {
+            state.move_left();
+            state.move_right();
+            black_box(state.piece_column())
+        })
+    });
+}
+
+fn bitboard_collision(c: &mut Criterion) {
+    let mut state = GameState::with_seed(SEED);
+    state.set_field(dense_field());
+    let board = state.bitboard();
+    let masks = PieceMasks::new(state.piece_set());
+    let piece = state.current_piece();
+    let position = piece.position();
+    c.bench_function("bitboard collision left/right", |b| {
+        b.iter(|| {
+            board.collides(&masks, piece.shape(), piece.rotation(), position.y, position.x - 1)
+                || board.collides(&masks, piece.shape(), piece.rotation(), position.y, position.x + 1)
+        })
+    });
+}
+
+fn hard_drop(c: &mut Criterion) {
+    let state = GameState::with_seed(SEED);
+    c.bench_function("hard drop cycle", |b| {
+        b.iter_batched(|| state.clone(), |mut state| {
+            state.move_bottom();
+            state
+        }, BatchSize::SmallInput)
+    });
+}
+
+fn simulated_game(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simulated game");
+    group.throughput(Throughput::Elements(SIMULATED_PIECES));
+    group.sample_size(10);
+    group.bench_function("10000 pieces", |b| {
+        b.iter(|| {
+            let mut rng = StdRng::seed_from_u64(SEED);
+            let mut state = GameState::with_seed(SEED);
+            for _ in 0..SIMULATED_PIECES {
+                play_random_piece(&mut state, &mut rng);
+            }
+            state.score
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, eliminate_lines, check_collision, bitboard_collision, hard_drop, simulated_game);
+criterion_main!(benches);
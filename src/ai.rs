@@ -0,0 +1,130 @@
+use crate::bitboard::{Bitboard, PieceMasks};
+use crate::game::{GAME_WIDTH, PIECE_SIZE, GameState, Piece, Point};
+
+// weights of the placement heuristic
+const HEIGHT_WEIGHT: f32 = -0.51;
+const LINES_WEIGHT: f32 = 0.76;
+const HOLES_WEIGHT: f32 = -0.36;
+const BUMPINESS_WEIGHT: f32 = -0.18;
+
+/// where to place the falling piece: rotate it `rotations` times,
+/// shift it to `column` and drop it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Move {
+    pub rotations: usize,
+    pub column: i32,
+    /// the piece where it lands after the move
+    pub landing: Piece,
+}
+
+/// find the best placement of the falling piece by trying every
+/// rotation and column and scoring the resulting board
+pub fn best_move(state: &GameState) -> Option<Move> {
+    let masks = PieceMasks::new(state.piece_set());
+    let board = state.bitboard();
+    let mut piece = state.current_piece();
+    let start = piece.position();
+    let mut best: Option<(f32, Move)> = None;
+    for rotations in 0..4 {
+        if rotations > 0 {
+            piece = state.rotation_system().turned(state.piece_set(), piece, true);
+        }
+        // once a rotation is blocked the following ones can't be reached either
+        if board.collides(&masks, piece.shape(), piece.rotation(), start.y, start.x) {
+            break;
+        }
+        for column in 1 - PIECE_SIZE as i32..GAME_WIDTH as i32 {
+            if let Some((value, landing)) = evaluate_placement(&board, &masks, piece, column) {
+                if best.is_none_or(|(best_value, _)| value > best_value) {
+                    best = Some((value, Move { rotations, column, landing }));
+                }
+            }
+        }
+    }
+    best.map(|(_, m)| m)
+}
+
+/// value of the board after shifting the piece to `column` and dropping it,
+/// and where the piece lands. `None` if the column can't be reached or the
+/// piece locks in the spawn rows
+fn evaluate_placement(board: &Bitboard, masks: &PieceMasks, piece: Piece, column: i32) -> Option<(f32, Piece)> {
+    let (shape, rotation) = (piece.shape(), piece.rotation());
+    let y = piece.position().y;
+    let mut x = piece.position().x;
+    while x != column {
+        x += if x < column { 1 } else { -1 };
+        if board.collides(masks, shape, rotation, y, x) {
+            return None;
+        }
+    }
+    let landing_row = board.drop_row(masks, shape, rotation, y, x);
+    let mut result = *board;
+    result.place(masks, shape, rotation, landing_row, x);
+    let lines = result.clear_lines();
+    if result.row(0) != 0 || result.row(1) != 0 {
+        return None;
+    }
+    Some((evaluate(&result, lines), piece.with_position(Point { x, y: landing_row })))
+}
+
+fn evaluate(board: &Bitboard, lines: usize) -> f32 {
+    let holes = board.count_holes();
+    let aggregate_height: usize = board.column_heights().iter().sum();
+    let bumpiness: u32 = board.surface_profile().iter().map(|step| step.unsigned_abs()).sum();
+
+    HEIGHT_WEIGHT * aggregate_height as f32
+        + LINES_WEIGHT * lines as f32
+        + HOLES_WEIGHT * holes as f32
+        + BUMPINESS_WEIGHT * bumpiness as f32
+}
+
+/// plays the game one input at a time, following `best_move`
+pub struct Autoplay {
+    target: Option<Move>,
+    rotations_done: usize,
+    pieces: Option<i32>,
+}
+
+impl Default for Autoplay {
+    fn default() -> Autoplay {
+        Autoplay::new()
+    }
+}
+
+impl Autoplay {
+    pub fn new() -> Autoplay {
+        Autoplay {
+            target: None,
+            rotations_done: 0,
+            pieces: None,
+        }
+    }
+
+    /// perform the next input towards the target placement
+    pub fn step(&mut self, state: &mut GameState) {
+        if self.pieces != Some(state.pieces_placed()) {
+            self.pieces = Some(state.pieces_placed());
+            self.target = best_move(state);
+            self.rotations_done = 0;
+        }
+        let target = match self.target {
+            Some(target) => target,
+            None => return state.move_bottom(),
+        };
+        if self.rotations_done < target.rotations {
+            state.rotate();
+            self.rotations_done += 1;
+            return;
+        }
+        let column = state.piece_column();
+        if column < target.column {
+            state.move_right();
+        } else if column > target.column {
+            state.move_left();
+        }
+        // drop when aligned, or when something blocks the way
+        if state.piece_column() == column {
+            state.move_bottom();
+        }
+    }
+}
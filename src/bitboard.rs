@@ -0,0 +1,200 @@
+//! Compact board representation where every row is a bit mask, with bit x
+//! set when column x is occupied. Collision tests and line detection become
+//! a few integer operations, which makes it suited to searches like the AI.
+//! It only knows whether cells are occupied, the `FieldCell` field stays the
+//! reference for rendering.
+
+use crate::game::{GAME_HEIGHT, GAME_WIDTH, PIECE_SIZE, Field, FieldCell, PieceRotation, PieceSet, PieceShape};
+
+/// a row with every column occupied
+pub const FULL_ROW: u16 = (1 << GAME_WIDTH) - 1;
+
+/// piece x positions go from -(PIECE_SIZE - 1) to GAME_WIDTH - 1
+const OFFSETS: usize = GAME_WIDTH + PIECE_SIZE - 1;
+
+const ROTATIONS: [PieceRotation; 4] = [
+    PieceRotation::NORMAL,
+    PieceRotation::LEFT,
+    PieceRotation::REVERSE,
+    PieceRotation::RIGHT,
+];
+
+/// the row masks of a piece at a given x position, `None` if some
+/// block would be outside of the board
+type OffsetMasks = [Option<[u16; PIECE_SIZE]>; OFFSETS];
+
+/// row masks of every shape, rotation and x position of a piece set
+#[derive(Clone, Debug)]
+pub struct PieceMasks {
+    masks: Vec<[OffsetMasks; 4]>,
+}
+
+impl PieceMasks {
+    pub fn new(shapes: &PieceSet) -> PieceMasks {
+        let mut masks = Vec::with_capacity(shapes.len());
+        for shape in 0..shapes.len() {
+            let mut rotations = [[None; OFFSETS]; 4];
+            for (r, &rotation) in ROTATIONS.iter().enumerate() {
+                for (offset, mask) in rotations[r].iter_mut().enumerate() {
+                    *mask = PieceMasks::shifted(shapes, PieceShape(shape), rotation, offset as i32 - PIECE_SIZE as i32 + 1);
+                }
+            }
+            masks.push(rotations);
+        }
+        PieceMasks { masks }
+    }
+
+    fn shifted(shapes: &PieceSet, shape: PieceShape, rotation: PieceRotation, x: i32) -> Option<[u16; PIECE_SIZE]> {
+        let mut rows = [0; PIECE_SIZE];
+        for (y, row) in rows.iter_mut().enumerate() {
+            for dx in 0..PIECE_SIZE {
+                if shapes.get(shape, rotation, y, dx) {
+                    let column = x + dx as i32;
+                    if column < 0 || column >= GAME_WIDTH as i32 {
+                        return None;
+                    }
+                    *row |= 1 << column;
+                }
+            }
+        }
+        Some(rows)
+    }
+
+    /// row masks of the piece with its top left corner at column x
+    pub fn get(&self, shape: PieceShape, rotation: PieceRotation, x: i32) -> Option<&[u16; PIECE_SIZE]> {
+        let offset = x + PIECE_SIZE as i32 - 1;
+        if offset < 0 || offset >= OFFSETS as i32 {
+            return None;
+        }
+        self.masks[shape.0][rotation as usize][offset as usize].as_ref()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bitboard {
+    rows: [u16; GAME_HEIGHT],
+}
+
+impl Bitboard {
+    pub fn from_field(field: &Field) -> Bitboard {
+        let mut rows = [0; GAME_HEIGHT];
+        for (mask, row) in rows.iter_mut().zip(field.iter()) {
+            for (x, &cell) in row.iter().enumerate() {
+                if cell != FieldCell::Empty {
+                    *mask |= 1 << x;
+                }
+            }
+        }
+        Bitboard { rows }
+    }
+
+    /// the board as a field, occupied cells become `FieldCell::Garbage`
+    /// since the bitboard doesn't know which piece they came from
+    pub fn to_field(&self) -> Field {
+        let mut field = [[FieldCell::Empty; GAME_WIDTH]; GAME_HEIGHT];
+        for (row, &mask) in field.iter_mut().zip(self.rows.iter()) {
+            for (x, cell) in row.iter_mut().enumerate() {
+                if mask & (1 << x) != 0 {
+                    *cell = FieldCell::Garbage;
+                }
+            }
+        }
+        field
+    }
+
+    pub fn row(&self, y: usize) -> u16 {
+        self.rows[y]
+    }
+
+    pub fn is_occupied(&self, y: usize, x: usize) -> bool {
+        self.rows[y] & (1 << x) != 0
+    }
+
+    /// height of each column, from the floor to its highest block even if
+    /// there are holes under it. Empty columns are 0
+    pub fn column_heights(&self) -> [usize; GAME_WIDTH] {
+        let mut heights = [0; GAME_WIDTH];
+        let mut seen = 0;
+        for (y, &row) in self.rows.iter().enumerate() {
+            let new = row & !seen;
+            for (x, height) in heights.iter_mut().enumerate() {
+                if new & (1 << x) != 0 {
+                    *height = GAME_HEIGHT - y;
+                }
+            }
+            seen |= row;
+        }
+        heights
+    }
+
+    /// empty cells with a block somewhere above them in their column
+    pub fn count_holes(&self) -> u32 {
+        let mut holes = 0;
+        let mut covered = 0;
+        for &row in self.rows.iter() {
+            holes += (covered & !row).count_ones();
+            covered |= row;
+        }
+        holes
+    }
+
+    /// the step from each column to the next one on the right, positive
+    /// when the surface goes up
+    pub fn surface_profile(&self) -> [i32; GAME_WIDTH - 1] {
+        let heights = self.column_heights();
+        let mut profile = [0; GAME_WIDTH - 1];
+        for (step, pair) in profile.iter_mut().zip(heights.windows(2)) {
+            *step = pair[1] as i32 - pair[0] as i32;
+        }
+        profile
+    }
+
+    /// whether the piece overlaps a block or goes outside the board
+    /// when its top left corner is at (y, x)
+    pub fn collides(&self, masks: &PieceMasks, shape: PieceShape, rotation: PieceRotation, y: i32, x: i32) -> bool {
+        let piece = match masks.get(shape, rotation, x) {
+            Some(piece) => piece,
+            None => return true,
+        };
+        piece.iter().enumerate().any(|(dy, &mask)| {
+            let row = y + dy as i32;
+            mask != 0 && (row < 0 || row >= GAME_HEIGHT as i32 || self.rows[row as usize] & mask != 0)
+        })
+    }
+
+    /// row where the piece stops if dropped from (y, x)
+    pub fn drop_row(&self, masks: &PieceMasks, shape: PieceShape, rotation: PieceRotation, mut y: i32, x: i32) -> i32 {
+        while !self.collides(masks, shape, rotation, y + 1, x) {
+            y += 1;
+        }
+        y
+    }
+
+    /// add the blocks of the piece with its top left corner at (y, x),
+    /// the position must not collide
+    pub fn place(&mut self, masks: &PieceMasks, shape: PieceShape, rotation: PieceRotation, y: i32, x: i32) {
+        if let Some(piece) = masks.get(shape, rotation, x) {
+            for (dy, &mask) in piece.iter().enumerate() {
+                if mask != 0 {
+                    self.rows[(y + dy as i32) as usize] |= mask;
+                }
+            }
+        }
+    }
+
+    /// remove the full rows, moving the ones above down, and return how many were removed
+    pub fn clear_lines(&mut self) -> usize {
+        let mut target = GAME_HEIGHT;
+        for y in (0..GAME_HEIGHT).rev() {
+            if self.rows[y] != FULL_ROW {
+                target -= 1;
+                self.rows[target] = self.rows[y];
+            }
+        }
+        // every removed row left an empty one at the top
+        for row in self.rows[..target].iter_mut() {
+            *row = 0;
+        }
+        target
+    }
+}
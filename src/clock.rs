@@ -0,0 +1,132 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+// Gravity, lock delay and entry delay count the ticks of `clock_tick`, so
+// they already run the same in tests. What depends on the real time, like
+// the game timer, reads it from a `Clock` instead of calling `Instant::now`,
+// which isn't available on wasm32.
+
+/// where the game reads the time, replaced in tests to control it. The
+/// time is counted from a point chosen by the clock
+pub trait Clock {
+    fn now(&self) -> Duration;
+
+    /// time passed since `earlier`, zero if it is in the future
+    fn since(&self, earlier: Duration) -> Duration {
+        self.now().saturating_sub(earlier)
+    }
+}
+
+/// the time of the system, from when the clock was made. On wasm32 there
+/// is no system time and it stays at zero, a front-end there sets its own
+/// clock
+#[derive(Clone, Copy, Debug)]
+pub struct SystemClock {
+    #[cfg(not(target_arch = "wasm32"))]
+    origin: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock {
+            #[cfg(not(target_arch = "wasm32"))]
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn now(&self) -> Duration {
+        Duration::from_secs(0)
+    }
+}
+
+/// a clock that only moves when told to, copies share the same time
+#[derive(Clone, Debug, Default)]
+pub struct TestClock {
+    now: Rc<Cell<Duration>>,
+}
+
+impl TestClock {
+    pub fn new() -> TestClock {
+        TestClock::default()
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+/// keeps a loop under a number of frames per second: at the end of each
+/// frame it tells how long to sleep for the frame to last its time
+#[derive(Clone, Copy, Debug)]
+pub struct FrameLimiter {
+    frame: Duration,
+    /// when the current frame started, None before the first one ends
+    start: Option<Duration>,
+}
+
+impl FrameLimiter {
+    /// at most `fps` frames per second, 0 for no limit
+    pub fn new(fps: u32) -> FrameLimiter {
+        let frame = if fps == 0 { Duration::from_secs(0) } else { Duration::from_secs(1) / fps };
+        FrameLimiter { frame, start: None }
+    }
+
+    /// time left of the frame ending at `now`, to sleep before the next
+    /// one. A frame that took longer doesn't make the next ones shorter
+    pub fn remaining(&mut self, now: Duration) -> Duration {
+        let sleep = match self.start {
+            Some(start) => (start + self.frame).saturating_sub(now),
+            None => Duration::from_secs(0),
+        };
+        self.start = Some(now + sleep);
+        sleep
+    }
+}
+
+/// lets an input through once when it comes again and again faster than
+/// a cooldown, like a key held down that the terminal repeats. Each repeat
+/// starts the cooldown again, so the input acts again once the key has
+/// been let go for that long
+#[derive(Clone, Copy, Debug)]
+pub struct RepeatGuard {
+    cooldown: Duration,
+    /// when the input last came, let through or not
+    last: Option<Duration>,
+}
+
+impl RepeatGuard {
+    /// a cooldown of `ms` milliseconds, 0 lets every input through
+    pub fn new(ms: u32) -> RepeatGuard {
+        RepeatGuard { cooldown: Duration::from_millis(ms as u64), last: None }
+    }
+
+    /// true if the input coming at `now` should act
+    pub fn allow(&mut self, now: Duration) -> bool {
+        let repeated = self.last.is_some_and(|last| now.saturating_sub(last) < self.cooldown);
+        self.last = Some(now);
+        !repeated
+    }
+}
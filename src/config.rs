@@ -0,0 +1,583 @@
+// Settings are read from ~/.config/tetris-rust/config, one `key = value`
+// per line. Lines starting with '#' are comments.
+
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::game::{GameConfig, LevelCap, PieceSet, RotationSystem};
+use crate::source::Randomizer;
+use crate::theme::Theme;
+
+/// highest level the games can start at
+pub const MAX_START_LEVEL: i32 = 20;
+
+/// what is drawn in the empty cells of the board
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Background {
+    #[default]
+    Space,
+    Dot,
+    Block,
+}
+
+impl std::str::FromStr for Background {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Background, ()> {
+        match s {
+            "space" => Ok(Background::Space),
+            "dot" => Ok(Background::Dot),
+            "block" => Ok(Background::Block),
+            _ => Err(()),
+        }
+    }
+}
+
+/// how the cells of the board are drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RenderStyle {
+    /// two characters side by side for each cell
+    #[default]
+    Double,
+    /// half a character for each cell, two rows of the board in a row of
+    /// the terminal. Needs UTF-8 and enough color pairs, the cells are
+    /// drawn `Double` otherwise
+    HalfBlock,
+}
+
+impl std::str::FromStr for RenderStyle {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<RenderStyle, ()> {
+        match s {
+            "double" => Ok(RenderStyle::Double),
+            "half_block" => Ok(RenderStyle::HalfBlock),
+            _ => Err(()),
+        }
+    }
+}
+
+/// something shown in the score window
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HudField {
+    Score,
+    Level,
+    Lines,
+    Time,
+    Combo,
+    Pps,
+    Next,
+    Hold,
+    Zone,
+    Drop,
+    Bag,
+    Rewinds,
+    Attack,
+}
+
+impl std::str::FromStr for HudField {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<HudField, ()> {
+        match s {
+            "score" => Ok(HudField::Score),
+            "level" => Ok(HudField::Level),
+            "lines" => Ok(HudField::Lines),
+            "time" => Ok(HudField::Time),
+            "combo" => Ok(HudField::Combo),
+            "pps" => Ok(HudField::Pps),
+            "next" => Ok(HudField::Next),
+            "hold" => Ok(HudField::Hold),
+            "zone" => Ok(HudField::Zone),
+            "drop" => Ok(HudField::Drop),
+            "bag" => Ok(HudField::Bag),
+            "rewinds" => Ok(HudField::Rewinds),
+            "attack" => Ok(HudField::Attack),
+            _ => Err(()),
+        }
+    }
+}
+
+/// defaults for the settings that aren't given
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Profile {
+    /// like the old games, without a ghost piece and hard drop
+    Classic,
+    #[default]
+    Modern,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 2] = [Profile::Classic, Profile::Modern];
+
+    /// name in the configuration file
+    pub fn name(self) -> &'static str {
+        match self {
+            Profile::Classic => "classic",
+            Profile::Modern => "modern",
+        }
+    }
+
+    pub fn show_ghost(self) -> bool {
+        self == Profile::Modern
+    }
+
+    pub fn hard_drop(self) -> bool {
+        self == Profile::Modern
+    }
+}
+
+impl std::str::FromStr for Profile {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Profile, ()> {
+        Profile::ALL.iter().copied().find(|profile| profile.name() == s).ok_or(())
+    }
+}
+
+/// kind of game, picked on the command line or with `mode` in the file,
+/// and saved as `last_mode` to offer it again at the next start. Written
+/// as the name and the number it takes, like `sprint 40`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Mode {
+    #[default]
+    Marathon,
+    /// rows of garbage to dig
+    Cheese(usize),
+    /// lines to clear
+    Sprint(i32),
+    /// length in seconds
+    Ultra(i32),
+}
+
+impl Mode {
+    /// name saved with the high scores
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Marathon => "marathon",
+            Mode::Cheese(_) => "cheese",
+            Mode::Sprint(_) => "sprint",
+            Mode::Ultra(_) => "ultra",
+        }
+    }
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Mode::Marathon => write!(f, "marathon"),
+            Mode::Cheese(rows) => write!(f, "cheese {}", rows),
+            Mode::Sprint(lines) => write!(f, "sprint {}", lines),
+            Mode::Ultra(seconds) => write!(f, "ultra {}", seconds),
+        }
+    }
+}
+
+impl std::str::FromStr for Mode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Mode, ()> {
+        let mut words = s.split_whitespace();
+        let mode = match (words.next(), words.next()) {
+            (Some("marathon"), None) => Mode::Marathon,
+            (Some("cheese"), Some(rows)) => Mode::Cheese(rows.parse().map_err(|_| ())?),
+            (Some("sprint"), Some(lines)) => Mode::Sprint(lines.parse().map_err(|_| ())?),
+            (Some("ultra"), Some(seconds)) => Mode::Ultra(seconds.parse().map_err(|_| ())?),
+            _ => return Err(()),
+        };
+        match words.next() {
+            None => Ok(mode),
+            Some(_) => Err(()),
+        }
+    }
+}
+
+/// the two characters drawn in a cell of the board, printable ASCII as
+/// they are drawn as single byte characters
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CellChars(pub char, pub char);
+
+impl Default for CellChars {
+    fn default() -> CellChars {
+        CellChars('[', ']')
+    }
+}
+
+impl std::str::FromStr for CellChars {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<CellChars, ()> {
+        let printable = |c: char| c.is_ascii_graphic() || c == ' ';
+        let mut chars = s.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(left), Some(right), None) if printable(left) && printable(right) => Ok(CellChars(left, right)),
+            _ => Err(()),
+        }
+    }
+}
+
+/// fields of the score window from top to bottom, given as a comma
+/// separated list. Next and hold side by side share the same rows
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hud(pub Vec<HudField>);
+
+impl Default for Hud {
+    fn default() -> Hud {
+        Hud(vec![HudField::Level, HudField::Score, HudField::Next, HudField::Hold])
+    }
+}
+
+impl std::str::FromStr for Hud {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Hud, ()> {
+        s.split(',').map(|field| field.trim().parse()).collect::<Result<_, _>>().map(Hud)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// defaults of the settings below that aren't given
+    pub profile: Profile,
+    /// draw a dotted grid in the empty cells of the board
+    pub show_grid: bool,
+    /// mark the columns of the falling piece on the top border of the board
+    pub column_markers: bool,
+    /// show the board flipped left to right, with the left and right keys
+    /// and the rotations swapped to match
+    pub mirror: bool,
+    /// draw the board upside down, with the up and down keys swapped
+    pub flip: bool,
+    /// each piece gets a random color instead of the one of its shape
+    pub rainbow: bool,
+    /// outline where the falling piece lands, the profile decides if not set
+    pub show_ghost: Option<bool>,
+    /// SPACE drops the piece to the bottom, the profile decides if not set
+    pub hard_drop_enabled: Option<bool>,
+    /// list the keys in the score window, shown if not set
+    pub show_controls: Option<bool>,
+    /// most frames drawn in a second, 60 if not set and 0 for no limit
+    pub max_fps: Option<u32>,
+    /// the number keys move the piece to a column
+    pub column_keys: bool,
+    /// rotate keys coming again within this many milliseconds are a key
+    /// held down and ignored, 0 to rotate on each one
+    pub rotate_cooldown_ms: u32,
+    /// characters of the ghost piece cells
+    pub ghost_chars: CellChars,
+    /// color of the ghost piece, the color of the piece if not set
+    pub ghost_color: Option<i16>,
+    /// color of the window borders, the terminal color if not set
+    pub border_color: Option<i16>,
+    /// color of the border of the board while it has focus, i.e. while
+    /// playing and not in a menu
+    pub focus_color: Option<i16>,
+    /// rows at the top of the board where the stack turns the border red,
+    /// 4 if not set and 0 to turn the warning off
+    pub danger_rows: Option<usize>,
+    /// beep when the stack gets into the danger rows
+    pub danger_beep: bool,
+    /// colors of the pieces
+    pub theme: Theme,
+    /// character of the empty cells when the grid is off
+    pub background: Background,
+    /// characters the cells of the board are drawn with
+    pub render_style: RenderStyle,
+    /// show pieces and frames per second in the score window,
+    /// even if `pps` is not in the `hud` fields
+    pub show_pps: bool,
+    /// what the score window shows and in which order
+    pub hud: Hud,
+    /// pause when the terminal loses focus, if it can report it
+    pub pause_on_focus_loss: bool,
+    /// start games with the next piece preview hidden
+    pub hide_next: bool,
+    /// entry delay in milliseconds between a piece locking and the next one
+    pub are_ms: i32,
+    /// time in milliseconds the cleared lines are shown before they go
+    pub clear_animation_ms: i32,
+    /// time in milliseconds the gap of the cleared lines is shown before
+    /// the stack falls
+    pub collapse_animation_ms: i32,
+    /// after a clear, blocks left hanging fall and can clear more lines
+    pub cascade: bool,
+    /// points lost for each hole a piece makes, 0 for none
+    pub hole_penalty: i32,
+    /// pieces sometimes lock with an item block
+    pub items: bool,
+    /// times a game can be rewound a few pieces after topping out
+    pub rewinds: u32,
+    /// rows of random garbage games start with, outside the cheese drill
+    pub garbage_rows: usize,
+    /// part of the cells of `garbage_rows` that are filled, 0.5 if not set
+    pub garbage_density: Option<f32>,
+    /// pieces come back from hold in the rotation they were held in
+    pub hold_keeps_rotation: bool,
+    /// level the games start at, 1 if not set
+    pub start_level: Option<i32>,
+    /// level where the gravity stops getting faster, no limit if not set
+    pub max_level: Option<i32>,
+    /// whether the level number stops or wraps around at `max_level`
+    pub level_cap: LevelCap,
+    /// wall kicks and spawn orientations of the pieces
+    pub rotation_system: RotationSystem,
+    /// how the shapes of the pieces are picked
+    pub randomizer: Randomizer,
+    /// kind of game played last, offered at the start when the command
+    /// line doesn't pick one, saved on exit
+    pub last_mode: Option<Mode>,
+    /// the zone was on in the last game, saved on exit
+    pub last_zone: bool,
+    /// name saved with high scores, the login name if not set
+    pub name: Option<String>,
+    /// show the last keys received and print what was detected about the
+    /// terminal on exit, set from the command line
+    pub debug: bool,
+    /// draw with plain characters and no colors, set from the command line
+    pub safe: bool,
+    /// name of the snapshot to save and retry, set from the command line
+    pub snapshot: Option<String>,
+    /// rows of garbage for the dig practice drill, set from the command line
+    pub cheese_rows: Option<usize>,
+    /// lines to clear in a sprint, set from the command line
+    pub sprint_lines: Option<i32>,
+    /// length of an ultra game in seconds, set from the command line
+    pub ultra_seconds: Option<i32>,
+    /// the zone can be charged and started, set from the command line
+    pub zone: bool,
+    /// gravity steps to run without a terminal, set from the command line
+    pub headless_steps: Option<usize>,
+    /// seed of the games, set from the command line
+    pub seed: Option<u64>,
+    /// file where the board and the stats are written when a game ends,
+    /// set from the command line
+    pub export_on_loss: Option<String>,
+    /// file where the share string of the replay is written when a game
+    /// ends, set from the command line
+    pub export_replay: Option<String>,
+    /// share string of a replay to watch before playing, set from the
+    /// command line
+    pub watch_replay: Option<String>,
+    /// shapes of the pieces, loaded from the file given as `piece_set`
+    pub piece_set: Rc<PieceSet>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        let base = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        };
+        Some(base.join("tetris-rust").join("config"))
+    }
+
+    /// load the configuration file, falling back to the defaults if it doesn't exist
+    pub fn load() -> Result<Config, String> {
+        let path = match Config::path() {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Config::parse(&content)
+                .map_err(|err| format!("{}: {}", path.display(), err)),
+            Err(_) => Ok(Config::default()),
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Config, String> {
+        let mut config = Config::default();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.find('=') {
+                Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+                None => return Err(format!("line {}: expected `key = value`", number + 1)),
+            };
+            config.set(key, value).map_err(|err| format!("line {}: {}", number + 1, err))?;
+        }
+        Ok(config)
+    }
+
+    pub fn show_ghost(&self) -> bool {
+        self.show_ghost.unwrap_or_else(|| self.profile.show_ghost())
+    }
+
+    pub fn hard_drop_enabled(&self) -> bool {
+        self.hard_drop_enabled.unwrap_or_else(|| self.profile.hard_drop())
+    }
+
+    pub fn show_controls(&self) -> bool {
+        self.show_controls.unwrap_or(true)
+    }
+
+    pub fn max_fps(&self) -> u32 {
+        self.max_fps.unwrap_or(60)
+    }
+
+    pub fn danger_rows(&self) -> usize {
+        self.danger_rows.unwrap_or(4)
+    }
+
+    pub fn garbage_density(&self) -> f32 {
+        self.garbage_density.unwrap_or(0.5)
+    }
+
+    /// kind of game asked for on the command line or in the file
+    pub fn mode(&self) -> Mode {
+        if let Some(rows) = self.cheese_rows {
+            Mode::Cheese(rows)
+        } else if let Some(lines) = self.sprint_lines {
+            Mode::Sprint(lines)
+        } else if let Some(seconds) = self.ultra_seconds {
+            Mode::Ultra(seconds)
+        } else {
+            Mode::Marathon
+        }
+    }
+
+    /// play `mode` from the next game, as if it was on the command line
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.cheese_rows = None;
+        self.sprint_lines = None;
+        self.ultra_seconds = None;
+        match mode {
+            Mode::Marathon => {},
+            Mode::Cheese(rows) => self.cheese_rows = Some(rows),
+            Mode::Sprint(lines) => self.sprint_lines = Some(lines),
+            Mode::Ultra(seconds) => self.ultra_seconds = Some(seconds),
+        }
+    }
+
+    /// the rules of the games played with this configuration
+    pub fn game_config(&self) -> GameConfig {
+        GameConfig {
+            piece_set: self.piece_set.clone(),
+            randomizer: self.randomizer,
+            rotation_system: self.rotation_system,
+            entry_delay_ms: self.are_ms,
+            clear_animation_ms: self.clear_animation_ms,
+            collapse_animation_ms: self.collapse_animation_ms,
+            hold_resets_orientation: !self.hold_keeps_rotation,
+            start_level: self.start_level.unwrap_or(1),
+            max_level: self.max_level,
+            level_cap: self.level_cap,
+            line_goal: self.sprint_lines,
+            time_limit_ms: self.ultra_seconds.map(|seconds| seconds * 1000),
+            zone: self.zone,
+            cascade: self.cascade,
+            hole_penalty: self.hole_penalty,
+            items: self.items,
+            rainbow: self.rainbow,
+            next_hidden: self.hide_next,
+            rewinds: self.rewinds,
+            ..GameConfig::default()
+        }
+    }
+
+    /// write a setting to the configuration file, replacing the line
+    /// that sets it if there is one and keeping everything else
+    pub fn save_setting(key: &str, value: &str) -> Result<(), String> {
+        let path = Config::path().ok_or("no home directory to save the configuration")?;
+        let content = std::fs::read_to_string(&path).unwrap_or_default();
+        let setting = format!("{} = {}", key, value);
+        let mut found = false;
+        let mut lines: Vec<String> = content.lines()
+            .map(|line| match line.find('=') {
+                Some(i) if !found && line[..i].trim() == key => {
+                    found = true;
+                    setting.clone()
+                },
+                _ => line.to_string(),
+            })
+            .collect();
+        if !found {
+            lines.push(setting);
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+        }
+        std::fs::write(&path, lines.join("\n") + "\n").map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    /// change a setting as if `key = value` was in the configuration file
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "profile" => self.profile = parse_value(key, value)?,
+            "show_grid" => self.show_grid = parse_value(key, value)?,
+            "column_markers" => self.column_markers = parse_value(key, value)?,
+            "mirror" => self.mirror = parse_value(key, value)?,
+            "flip" => self.flip = parse_value(key, value)?,
+            "rainbow" => self.rainbow = parse_value(key, value)?,
+            "show_ghost" => self.show_ghost = Some(parse_value(key, value)?),
+            "show_controls" => self.show_controls = Some(parse_value(key, value)?),
+            "max_fps" => self.max_fps = Some(parse_value(key, value)?),
+            "column_keys" => self.column_keys = parse_value(key, value)?,
+            "rotate_cooldown_ms" => self.rotate_cooldown_ms = parse_value(key, value)?,
+            "hard_drop_enabled" => self.hard_drop_enabled = Some(parse_value(key, value)?),
+            "ghost_chars" => self.ghost_chars = parse_value(key, value)?,
+            "ghost_color" => self.ghost_color = Some(parse_value(key, value)?),
+            "border_color" => self.border_color = Some(parse_value(key, value)?),
+            "focus_color" => self.focus_color = Some(parse_value(key, value)?),
+            "danger_rows" => self.danger_rows = Some(parse_value(key, value)?),
+            "danger_beep" => self.danger_beep = parse_value(key, value)?,
+            "theme" => self.theme = parse_value(key, value)?,
+            "background" => self.background = parse_value(key, value)?,
+            "render_style" => self.render_style = parse_value(key, value)?,
+            "show_pps" => self.show_pps = parse_value(key, value)?,
+            "hud" => self.hud = parse_value(key, value)?,
+            "pause_on_focus_loss" => self.pause_on_focus_loss = parse_value(key, value)?,
+            "hide_next" => self.hide_next = parse_value(key, value)?,
+            "are_ms" => self.are_ms = parse_value(key, value)?,
+            "clear_animation_ms" => self.clear_animation_ms = parse_value(key, value)?,
+            "collapse_animation_ms" => self.collapse_animation_ms = parse_value(key, value)?,
+            "cascade" => self.cascade = parse_value(key, value)?,
+            "hole_penalty" => self.hole_penalty = parse_value(key, value)?,
+            "items" => self.items = parse_value(key, value)?,
+            "rewinds" => self.rewinds = parse_value(key, value)?,
+            "garbage_rows" => self.garbage_rows = parse_value(key, value)?,
+            "garbage_density" => {
+                let density: f32 = parse_value(key, value)?;
+                if !(0.0..=1.0).contains(&density) {
+                    return Err(format!("`garbage_density` must be between 0 and 1, not {}", value));
+                }
+                self.garbage_density = Some(density);
+            },
+            "hold_keeps_rotation" => self.hold_keeps_rotation = parse_value(key, value)?,
+            "start_level" => {
+                let level: i32 = parse_value(key, value)?;
+                if !(1..=MAX_START_LEVEL).contains(&level) {
+                    return Err(format!("`start_level` must be from 1 to {}, not {}", MAX_START_LEVEL, value));
+                }
+                self.start_level = Some(level);
+            },
+            "max_level" => {
+                let level: i32 = parse_value(key, value)?;
+                if level < 1 {
+                    return Err(format!("`max_level` must be at least 1, not {}", value));
+                }
+                self.max_level = Some(level);
+            },
+            "level_cap" => self.level_cap = parse_value(key, value)?,
+            "rotation_system" => self.rotation_system = parse_value(key, value)?,
+            "randomizer" => self.randomizer = parse_value(key, value)?,
+            "mode" => self.set_mode(parse_value(key, value)?),
+            // saved by the game itself, a value it can't read, edited by
+            // hand or from another version, is forgotten instead of failing
+            "last_mode" => self.last_mode = value.parse().ok(),
+            "last_zone" => self.last_zone = value.parse().unwrap_or(false),
+            "name" => self.name = Some(value.to_string()),
+            "piece_set" => {
+                let shapes = PieceSet::load(Path::new(value)).map_err(|err| format!("{}: {}", value, err))?;
+                self.piece_set = Rc::new(shapes);
+            },
+            _ => return Err(format!("unknown setting `{}`", key)),
+        }
+        Ok(())
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value `{}` for `{}`", value, key))
+}
@@ -0,0 +1,93 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+use crate::game::{GAME_HEIGHT, GAME_WIDTH, Piece, PieceSet, PieceShape, Point, RotationSystem};
+
+/// fewest inputs (moves and rotations) needed to put each shape in each
+/// place with a rotation system, found with a breadth first search from
+/// the spawn position on an empty board. Places are the cells of the
+/// piece moved to the top row, so rotations that cover the same cells
+/// count as the same place
+#[derive(Clone, Debug)]
+pub struct FinesseTable {
+    inputs: HashMap<(PieceShape, Vec<Point>), u32>,
+}
+
+impl FinesseTable {
+    pub fn new(shapes: &PieceSet, system: RotationSystem) -> FinesseTable {
+        let mut inputs = HashMap::new();
+        for shape in (0..shapes.len()).map(PieceShape) {
+            let start = system.spawned(shapes, shape);
+            let mut seen = vec![start];
+            let mut queue = VecDeque::from(vec![(start, 0)]);
+            while let Some((piece, count)) = queue.pop_front() {
+                inputs.entry((shape, place(&piece.cells(shapes)))).or_insert(count);
+                let moves = [
+                    Some(piece.translated(-1, 0)),
+                    Some(piece.translated(1, 0)),
+                    rotated(shapes, system, piece),
+                ];
+                for next in moves.iter().flatten() {
+                    if fits(shapes, *next) && !seen.contains(next) {
+                        seen.push(*next);
+                        queue.push_back((*next, count + 1));
+                    }
+                }
+            }
+        }
+        FinesseTable { inputs }
+    }
+
+    /// the table of `new`, built once per rotation system for the
+    /// standard piece set and shared by all the games of the thread, as
+    /// the search is too slow to run at every new game. Other piece sets
+    /// get a table of their own
+    pub fn shared(shapes: &PieceSet, system: RotationSystem) -> Rc<FinesseTable> {
+        if !shapes.is_standard() {
+            return Rc::new(FinesseTable::new(shapes, system));
+        }
+        STANDARD_TABLES.with(|tables| {
+            let mut tables = tables.borrow_mut();
+            if let Some((_, table)) = tables.iter().find(|(built, _)| *built == system) {
+                return Rc::clone(table);
+            }
+            let table = Rc::new(FinesseTable::new(shapes, system));
+            tables.push((system, Rc::clone(&table)));
+            table
+        })
+    }
+
+    /// fewest inputs to lock the shape on `cells`, None if the place can't
+    /// be reached by moving and rotating from the top, e.g. after a tuck
+    pub fn min_inputs(&self, shape: PieceShape, cells: &[Point]) -> Option<u32> {
+        self.inputs.get(&(shape, place(cells))).copied()
+    }
+}
+
+thread_local! {
+    static STANDARD_TABLES: RefCell<Vec<(RotationSystem, Rc<FinesseTable>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// the cells moved up to the top row, in order
+fn place(cells: &[Point]) -> Vec<Point> {
+    let top = cells.iter().map(|cell| cell.y).min().unwrap_or(0);
+    let mut place: Vec<Point> = cells.iter().map(|cell| Point { x: cell.x, y: cell.y - top }).collect();
+    place.sort_by_key(|cell| (cell.y, cell.x));
+    place
+}
+
+fn fits(shapes: &PieceSet, piece: Piece) -> bool {
+    piece.absolute_cells(shapes).all(|cell| {
+        (0..GAME_WIDTH as i32).contains(&cell.x) && (0..GAME_HEIGHT as i32).contains(&cell.y)
+    })
+}
+
+/// the piece rotated clockwise with the first kick that fits
+fn rotated(shapes: &PieceSet, system: RotationSystem, piece: Piece) -> Option<Piece> {
+    let rotated = system.turned(shapes, piece, true);
+    system.kicks(shapes, piece.shape(), piece.rotation(), rotated.rotation())
+        .iter()
+        .map(|&(dx, dy)| rotated.translated(dx, dy))
+        .find(|&kicked| fits(shapes, kicked))
+}
@@ -1,249 +1,2498 @@
 
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::Duration;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+
+use crate::bitboard::Bitboard;
+use crate::clock::{Clock, SystemClock};
+use crate::finesse::FinesseTable;
+use crate::source::{PieceSource, Randomizer};
+use crate::theme::PIECE_COLORS;
+
 pub const GAME_WIDTH: usize = 10;
 pub const GAME_HEIGHT: usize = 22;
 const PIECE_SPAWN_POSITION: Point = Point { y: 0, x: GAME_WIDTH as i32 / 2 - 2 };
+pub const POINTS_PER_LEVEL: i32 = 700;
+pub const PIECE_SIZE: usize = 5;
+/// time between two calls of `clock_tick`, the game clock resolution
+pub const TICK_MS: i32 = 50;
+/// gravity never gets faster than one row per tick
+pub const MIN_GRAVITY_DELAY_MS: i32 = TICK_MS;
+/// time a piece resting on the stack waits before gravity locks it
+pub const LOCK_DELAY_MS: i32 = 500;
+/// rows at the top of the board where pieces spawn, a piece locking
+/// entirely inside them ends the game
+pub const SPAWN_ROWS: usize = 2;
+/// lines to clear to fill the zone meter
+pub const ZONE_CHARGE_LINES: i32 = 8;
+/// how long a zone stops the gravity
+pub const ZONE_MS: i32 = 10_000;
+/// pieces a rewind after a top out goes back
+pub const REWIND_PIECES: usize = 3;
+/// mixed into the seed of `with_seed` for the garbage, so the holes don't
+/// follow the pieces
+const GARBAGE_SEED_MIX: u64 = 0x9e37_79b9_7f4a_7c15;
+/// mixed into the seed of `with_seed` for the items, for the same reason
+const ITEM_SEED_MIX: u64 = 0xc2b2_ae3d_27d4_eb4f;
+/// mixed into the seed of `with_seed` for the rainbow colors, for the same
+/// reason
+const COLOR_SEED_MIX: u64 = 0x1656_67b1_9e37_79f9;
+/// in items mode, one piece in this many locks with an item block
+const ITEM_CHANCE: u32 = 8;
+/// letters of the tetrominoes, in the order of the standard piece set
+const SHAPE_LETTERS: [char; 7] = ['I', 'O', 'L', 'J', 'T', 'S', 'Z'];
+
+/// time the falling piece takes to move down by one row at `level`,
+/// 800ms made 10% shorter at every level and rounded to the clock tick
+pub fn gravity_delay_ms(level: i32) -> i32 {
+    let delay = 800.0 * 0.9f32.powi(level);
+    let ticks = (delay / TICK_MS as f32).round() as i32;
+    (ticks * TICK_MS).max(MIN_GRAVITY_DELAY_MS)
+}
+
+/// garbage lines sent for each clear in a row after the first one, the
+/// last value for longer combos
+const COMBO_ATTACK: [usize; 11] = [0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
 
+/// a line clear as the attack table sees it
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub enum PieceShape {
-    I = 0, 
-    O = 1, 
-    L = 2, 
-    J = 3, 
-    T = 4, 
-    S = 5, 
-    Z = 6,
-}
-
-#[derive(Clone, Copy, Debug)]
-enum PieceRotation {
+pub enum ClearType {
+    /// lines cleared without a T-spin
+    Lines(usize),
+    /// lines cleared, or none, with a mini T-spin
+    TSpinMini(usize),
+    /// lines cleared, or none, with a full T-spin
+    TSpin(usize),
+}
+
+impl ClearType {
+    pub fn new(lines: usize, spin: Spin) -> ClearType {
+        match spin {
+            Spin::None => ClearType::Lines(lines),
+            Spin::Mini => ClearType::TSpinMini(lines),
+            Spin::Full => ClearType::TSpin(lines),
+        }
+    }
+
+    /// tetrises and T-spins, the clears that keep a back to back going
+    pub fn is_difficult(self) -> bool {
+        match self {
+            ClearType::Lines(lines) => lines >= 4,
+            ClearType::TSpinMini(_) | ClearType::TSpin(_) => true,
+        }
+    }
+}
+
+/// garbage lines a clear sends to the other player in versus: 0, 1, 2
+/// and 4 for one to four lines, twice the lines for a T-spin and a line
+/// for a mini T-spin double. `combo` counts the clears in a row before
+/// this one, and `b2b` adds a line to a difficult clear that follows
+/// another one. Clears that don't clear lines send nothing
+pub fn attack_lines(clear_type: ClearType, combo: usize, b2b: bool) -> usize {
+    let (lines, base) = match clear_type {
+        ClearType::Lines(lines) => (lines, [0, 0, 1, 2, 4][lines.min(4)]),
+        ClearType::TSpinMini(lines) => (lines, [0, 0, 1][lines.min(2)]),
+        ClearType::TSpin(lines) => (lines, 2 * lines.min(3)),
+    };
+    if lines == 0 {
+        return 0;
+    }
+    let b2b = if b2b && clear_type.is_difficult() { 1 } else { 0 };
+    base + b2b + COMBO_ATTACK[combo.min(COMBO_ATTACK.len() - 1)]
+}
+
+/// index of a shape in the piece set in use, the standard set
+/// is made of the seven tetrominoes in the order I, O, L, J, T, S, Z
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PieceShape(pub usize);
+
+impl PieceShape {
+    pub const I: PieceShape = PieceShape(0);
+    pub const O: PieceShape = PieceShape(1);
+    pub const L: PieceShape = PieceShape(2);
+    pub const J: PieceShape = PieceShape(3);
+    pub const T: PieceShape = PieceShape(4);
+    pub const S: PieceShape = PieceShape(5);
+    pub const Z: PieceShape = PieceShape(6);
+
+    /// index of the color of the shape in `Theme::colors`, custom shapes
+    /// after the seventh take the colors again from the first
+    pub fn color_index(self) -> usize {
+        self.0 % PIECE_COLORS
+    }
+
+    /// letter of the shape, custom shapes take the letters of the
+    /// tetrominoes again like the colors
+    pub fn letter(self) -> char {
+        SHAPE_LETTERS[self.0 % SHAPE_LETTERS.len()]
+    }
+}
+
+type PieceGrid = [[bool; PIECE_SIZE]; PIECE_SIZE];
+
+/// the shapes pieces can take, each with its four rotations in the
+/// order normal, left, reverse, right
+#[derive(Clone, Debug, PartialEq)]
+pub struct PieceSet {
+    shapes: Vec<[PieceGrid; 4]>,
+    standard: bool,
+}
+
+impl Default for PieceSet {
+    fn default() -> PieceSet {
+        PieceSet::standard()
+    }
+}
+
+impl PieceSet {
+    /// the seven tetrominoes
+    pub fn standard() -> PieceSet {
+        let mut shapes = Vec::new();
+        for shape in TETRIS.iter() {
+            let mut rotations = [[[false; PIECE_SIZE]; PIECE_SIZE]; 4];
+            for (r, rotation) in shape.iter().enumerate() {
+                for (y, row) in rotation.iter().enumerate() {
+                    for (x, &cell) in row.iter().enumerate() {
+                        rotations[r][y][x] = cell != 0;
+                    }
+                }
+            }
+            shapes.push(rotations);
+        }
+        PieceSet { shapes, standard: true }
+    }
+
+    pub(crate) fn is_standard(&self) -> bool {
+        self.standard
+    }
+
+    pub fn load(path: &std::path::Path) -> Result<PieceSet, GameError> {
+        let content = std::fs::read_to_string(path).map_err(|err| GameError::Io(err.to_string()))?;
+        PieceSet::parse(&content)
+    }
+
+    /// parse a piece set: pieces are separated by blank lines, every row of
+    /// a piece lists its four rotations side by side, '#' is a block and
+    /// '.' an empty cell
+    ///
+    /// ```text
+    /// .#. #.. ### .#.
+    /// ### ##. .#. ##.
+    /// ... #.. ... .#.
+    /// ```
+    pub fn parse(content: &str) -> Result<PieceSet, GameError> {
+        let mut shapes = Vec::new();
+        let mut rows: Vec<(usize, &str)> = Vec::new();
+        for (number, line) in content.lines().chain(std::iter::once("")).enumerate() {
+            if !line.trim().is_empty() {
+                rows.push((number + 1, line));
+            } else if !rows.is_empty() {
+                shapes.push(PieceSet::parse_shape(&rows)?);
+                rows.clear();
+            }
+        }
+        if shapes.is_empty() {
+            return Err(GameError::NoPieces);
+        }
+        Ok(PieceSet { shapes, standard: false })
+    }
+
+    fn parse_shape(rows: &[(usize, &str)]) -> Result<[PieceGrid; 4], GameError> {
+        let invalid = |line, problem| Err(GameError::InvalidPiece { line, problem });
+        let mut rotations = [[[false; PIECE_SIZE]; PIECE_SIZE]; 4];
+        if rows.len() > PIECE_SIZE {
+            return invalid(rows[0].0, PieceProblem::TooTall);
+        }
+        for (y, &(number, line)) in rows.iter().enumerate() {
+            let grids: Vec<&str> = line.split_whitespace().collect();
+            if grids.len() != 4 {
+                return invalid(number, PieceProblem::Rotations(grids.len()));
+            }
+            for (r, grid) in grids.iter().enumerate() {
+                if grid.chars().count() > PIECE_SIZE {
+                    return invalid(number, PieceProblem::TooWide);
+                }
+                for (x, c) in grid.chars().enumerate() {
+                    rotations[r][y][x] = match c {
+                        '#' => true,
+                        '.' => false,
+                        _ => return invalid(number, PieceProblem::Character(c)),
+                    };
+                }
+            }
+        }
+        if !PieceSet::consistent_rotations(&rotations) {
+            return invalid(rows[0].0, PieceProblem::DifferentShapes);
+        }
+        Ok(rotations)
+    }
+
+    /// every rotation must be the first one turned by some multiple of 90 degrees
+    fn consistent_rotations(rotations: &[PieceGrid; 4]) -> bool {
+        let cells = |grid: &PieceGrid| -> Vec<(i32, i32)> {
+            let mut cells = Vec::new();
+            for (y, row) in grid.iter().enumerate() {
+                for (x, &cell) in row.iter().enumerate() {
+                    if cell {
+                        cells.push((y as i32, x as i32));
+                    }
+                }
+            }
+            cells
+        };
+        let normalized = |mut cells: Vec<(i32, i32)>| -> Vec<(i32, i32)> {
+            let min_y = cells.iter().map(|c| c.0).min().unwrap_or(0);
+            let min_x = cells.iter().map(|c| c.1).min().unwrap_or(0);
+            for cell in cells.iter_mut() {
+                *cell = (cell.0 - min_y, cell.1 - min_x);
+            }
+            cells.sort_unstable();
+            cells
+        };
+        let mut turns = Vec::new();
+        let mut turned = cells(&rotations[0]);
+        if turned.is_empty() {
+            return false;
+        }
+        for _ in 0..4 {
+            turns.push(normalized(turned.clone()));
+            turned = turned.iter().map(|&(y, x)| (x, -y)).collect();
+        }
+        rotations.iter().all(|grid| turns.contains(&normalized(cells(grid))))
+    }
+
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    /// wall kicks to try when rotating the shape, custom pieces
+    /// use the same kicks as J, L, S, T and Z
+    pub(crate) fn kicks(&self, shape: PieceShape, from: PieceRotation, to: PieceRotation) -> &'static [(i32, i32)] {
+        use PieceRotation::*;
+        let transition = match (from, to) {
+            (NORMAL, RIGHT) => 0,
+            (RIGHT, NORMAL) => 1,
+            (RIGHT, REVERSE) => 2,
+            (REVERSE, RIGHT) => 3,
+            (REVERSE, LEFT) => 4,
+            (LEFT, REVERSE) => 5,
+            (LEFT, NORMAL) => 6,
+            (NORMAL, LEFT) => 7,
+            _ => return &[(0, 0)],
+        };
+        match shape {
+            PieceShape::O if self.standard => &[(0, 0)],
+            PieceShape::I if self.standard => &I_KICKS[transition],
+            _ => &JLSTZ_KICKS[transition],
+        }
+    }
+
+    /// whether the shape is the T tetromino, the only one that can spin
+    fn is_t(&self, shape: PieceShape) -> bool {
+        self.standard && shape == PieceShape::T
+    }
+
+    /// whether (y, x) is a block of the shape in its spawn orientation
+    pub fn preview(&self, shape: PieceShape, y: usize, x: usize) -> bool {
+        self.get(shape, PieceRotation::NORMAL, y, x)
+    }
+
+    /// whether (y, x) is a block of the shape in the given rotation
+    pub fn get(&self, shape: PieceShape, rotation: PieceRotation, y: usize, x: usize) -> bool {
+        let grid = self.shapes.get(shape.0).map(|shape| &shape[rotation as usize]);
+        grid.and_then(|grid| grid.get(y)).and_then(|row| row.get(x)).copied().unwrap_or(false)
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PieceRotation {
     NORMAL = 0, 
     LEFT = 1, 
     REVERSE = 2,
     RIGHT = 3, 
 }
 
-#[derive(Clone, Copy, Debug)]
-enum Direction {
-    DOWN, LEFT, RIGHT,
-}
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    DOWN, LEFT, RIGHT,
+}
+
+/// an input of the player, for driving a game from a script or a replay
+/// with `GameState::apply`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveDown,
+    /// drop to one row above the stack, without locking
+    DropToRest,
+    HardDrop,
+    RotateCw,
+    RotateCcw,
+    Hold,
+    StartZone,
+    /// go back a few pieces after topping out, see `GameState::rewind`
+    Rewind,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveDown,
+        Action::DropToRest,
+        Action::HardDrop,
+        Action::RotateCw,
+        Action::RotateCcw,
+        Action::Hold,
+        Action::StartZone,
+        Action::Rewind,
+    ];
+
+    /// short name of the action, what `str::parse` reads back
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "left",
+            Action::MoveRight => "right",
+            Action::MoveDown => "down",
+            Action::DropToRest => "rest",
+            Action::HardDrop => "drop",
+            Action::RotateCw => "cw",
+            Action::RotateCcw => "ccw",
+            Action::Hold => "hold",
+            Action::StartZone => "zone",
+            Action::Rewind => "rewind",
+        }
+    }
+
+    /// the action that looks like this one on a board drawn `mirrored`
+    /// left to right or `flipped` upside down: a mirror swaps left and
+    /// right, and either of the two turns a rotation the other way, both
+    /// together turn it back
+    pub fn as_drawn(self, mirrored: bool, flipped: bool) -> Action {
+        match self {
+            Action::MoveLeft if mirrored => Action::MoveRight,
+            Action::MoveRight if mirrored => Action::MoveLeft,
+            Action::RotateCw if mirrored != flipped => Action::RotateCcw,
+            Action::RotateCcw if mirrored != flipped => Action::RotateCw,
+            action => action,
+        }
+    }
+}
+
+impl std::str::FromStr for Action {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Action, ()> {
+        Action::ALL.iter().copied().find(|action| action.name() == s).ok_or(())
+    }
+}
+
+/// rules for rotating pieces: the wall kicks tried and the orientation
+/// pieces spawn in
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum RotationSystem {
+    /// the Super Rotation System of modern games
+    #[default]
+    Srs,
+    /// the Arika system of Sega and TGM, flat side up and kicking one
+    /// cell right then left, except the I
+    Sega,
+    /// the original Nintendo games, flat side up and no kicks, with only
+    /// two orientations for the I, S and Z
+    Nintendo,
+}
+
+impl RotationSystem {
+    pub const ALL: [RotationSystem; 3] = [RotationSystem::Srs, RotationSystem::Sega, RotationSystem::Nintendo];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RotationSystem::Srs => "srs",
+            RotationSystem::Sega => "sega",
+            RotationSystem::Nintendo => "nintendo",
+        }
+    }
+
+    /// offsets to try in order when rotating the shape
+    pub(crate) fn kicks(self, shapes: &PieceSet, shape: PieceShape, from: PieceRotation, to: PieceRotation) -> &'static [(i32, i32)] {
+        match self {
+            RotationSystem::Srs => shapes.kicks(shape, from, to),
+            RotationSystem::Sega if !(shapes.standard && shape == PieceShape::I) => &[(0, 0), (1, 0), (-1, 0)],
+            RotationSystem::Sega | RotationSystem::Nintendo => &[(0, 0)],
+        }
+    }
+
+    /// the shape at the spawn position. SRS spawns every piece pointing up,
+    /// the older systems spawn L, J and T with their flat side up, and the
+    /// Nintendo one also lays the I, S and Z on the same row as them
+    pub(crate) fn spawned(self, shapes: &PieceSet, shape: PieceShape) -> Piece {
+        let piece = Piece::spawned(shape);
+        let flat_side_up = shapes.standard && [PieceShape::L, PieceShape::J, PieceShape::T].contains(&shape);
+        if (self != RotationSystem::Srs && flat_side_up) || self.has_two_orientations(shapes, shape) {
+            piece.with_rotation(PieceRotation::REVERSE)
+        } else {
+            piece
+        }
+    }
+
+    /// `piece` turned a quarter, clockwise or not. The Nintendo system
+    /// turns the I, S and Z either way between lying down and standing on
+    /// the right of their grid, so they don't move around when spun
+    pub(crate) fn turned(self, shapes: &PieceSet, piece: Piece, clockwise: bool) -> Piece {
+        if self.has_two_orientations(shapes, piece.shape) {
+            let rotation = if piece.rotation == PieceRotation::RIGHT { PieceRotation::REVERSE } else { PieceRotation::RIGHT };
+            piece.with_rotation(rotation)
+        } else if clockwise {
+            piece.rotated_right()
+        } else {
+            piece.rotated_left()
+        }
+    }
+
+    fn has_two_orientations(self, shapes: &PieceSet, shape: PieceShape) -> bool {
+        self == RotationSystem::Nintendo && shapes.standard && [PieceShape::I, PieceShape::S, PieceShape::Z].contains(&shape)
+    }
+}
+
+impl std::str::FromStr for RotationSystem {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<RotationSystem, ()> {
+        RotationSystem::ALL.iter().copied().find(|system| system.name() == s).ok_or(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// a T-spin, detected with the three corner rule
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Spin {
+    None,
+    Mini,
+    Full,
+}
+
+/// outcome of a successful rotation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rotation {
+    /// wall kick offset that made the rotated piece fit
+    pub kick: Point,
+    pub spin: Spin,
+}
+
+impl Point {
+    /// the point moved by `dx` columns and `dy` rows
+    pub fn translated(&self, dx: i32, dy: i32) -> Point {
+        Point { x: self.x + dx, y: self.y + dy }
+    }
+
+    fn moved(&self, direction: Direction) -> Point {
+        use Direction::*;
+        match direction {
+            DOWN => self.translated(0, 1),
+            LEFT => self.translated(-1, 0),
+            RIGHT => self.translated(1, 0),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Piece {
+    shape: PieceShape,
+    rotation: PieceRotation,
+    position: Point,
+    /// index in `Theme::colors` of a color of its own, in rainbow mode
+    #[serde(default)]
+    color: Option<u8>,
+}
+
+impl Piece {
+    pub fn shape(&self) -> PieceShape {
+        self.shape
+    }
+
+    pub fn rotation(&self) -> PieceRotation {
+        self.rotation
+    }
+
+    /// position of the top left corner of the piece grid on the board
+    pub fn position(&self) -> Point {
+        self.position
+    }
+
+    /// index of the color of the piece in `Theme::colors`, the one of its
+    /// shape unless it was given its own
+    pub fn color_index(&self) -> usize {
+        match self.color {
+            Some(color) => color as usize % PIECE_COLORS,
+            None => self.shape.color_index(),
+        }
+    }
+
+    /// what the cells of the piece become when it locks
+    fn cell(&self) -> FieldCell {
+        match self.color {
+            Some(color) => FieldCell::Painted(self.shape, color),
+            None => FieldCell::Occupied(self.shape),
+        }
+    }
+
+    fn random(shapes: &PieceSet, system: RotationSystem, source: &mut dyn PieceSource) -> Piece {
+        system.spawned(shapes, source.next_shape(shapes.len()))
+    }
+
+    pub(crate) fn spawned(shape: PieceShape) -> Piece {
+        Piece {
+            rotation: PieceRotation::NORMAL,
+            shape,
+            position: PIECE_SPAWN_POSITION,
+            color: None,
+        }
+    }
+
+    fn with_rotation(&self, rotation: PieceRotation) -> Piece {
+        Piece { rotation, ..*self }
+    }
+
+    /// the same piece moved so its grid starts at `position`
+    pub fn with_position(&self, position: Point) -> Piece {
+        Piece { position, ..*self }
+    }
+
+    fn moved(&self, direction: Direction) -> Piece {
+        self.with_position(self.position.moved(direction))
+    }
+
+    pub fn rotated_right(&self) ->  Piece {
+        use PieceRotation::*;
+        self.with_rotation(match self.rotation {
+            NORMAL => RIGHT,
+            RIGHT => REVERSE,
+            REVERSE => LEFT,
+            LEFT => NORMAL,
+        })
+    }
+
+    pub fn rotated_left(&self) -> Piece {
+        use PieceRotation::*;
+        self.with_rotation(match self.rotation {
+            NORMAL => LEFT,
+            LEFT => REVERSE,
+            REVERSE => RIGHT,
+            RIGHT => NORMAL,
+        })
+    }
+
+    fn get(&self, shapes: &PieceSet, y: usize, x: usize) -> bool {
+        shapes.get(self.shape, self.rotation, y, x)
+    }
+
+    /// whether a block of the piece covers the board cell at `point`
+    pub fn contains(&self, shapes: &PieceSet, point: Point) -> bool {
+        let (dy, dx) = (point.y - self.position.y, point.x - self.position.x);
+        let size = PIECE_SIZE as i32;
+        (0..size).contains(&dy) && (0..size).contains(&dx) && self.get(shapes, dy as usize, dx as usize)
+    }
+
+    /// board coordinates of the blocks of the piece, row by row
+    pub fn absolute_cells<'a>(&self, shapes: &'a PieceSet) -> impl Iterator<Item = Point> + 'a {
+        let piece = *self;
+        (0..PIECE_SIZE)
+            .flat_map(|y| (0..PIECE_SIZE).map(move |x| (y, x)))
+            .filter(move |&(y, x)| piece.get(shapes, y, x))
+            .map(move |(y, x)| piece.position.translated(x as i32, y as i32))
+    }
+
+    /// the board coordinates of `absolute_cells` collected
+    pub fn cells(&self, shapes: &PieceSet) -> Vec<Point> {
+        self.absolute_cells(shapes).collect()
+    }
+
+    pub(crate) fn translated(&self, dx: i32, dy: i32) -> Piece {
+        self.with_position(self.position.translated(dx, dy))
+    }
+
+    /// false if the piece overlaps a block or goes outside the field
+    fn check_collision(&self, state: &GameState) -> bool {
+        self.absolute_cells(&state.shapes).all(|cell| !state.is_blocked(cell.y, cell.x))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FieldCell {
+    Empty, 
+    Occupied(PieceShape),
+    /// a block of a piece with a color of its own, in rainbow mode: the
+    /// index in `Theme::colors`
+    Painted(PieceShape, u8),
+    Garbage,
+    /// a block of a piece holding an item, in items mode
+    Item(PieceShape, Item),
+}
+
+/// what an item block does when a line with it is cleared
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Item {
+    /// the bottom row goes too, full or not
+    ClearBottom,
+    /// its whole column is emptied
+    ClearColumn,
+    /// the blocks around it, 3x3, are blown away
+    Bomb,
+}
+
+impl Item {
+    pub const ALL: [Item; 3] = [Item::ClearBottom, Item::ClearColumn, Item::Bomb];
+}
+
+/// the settled board, indexed as `field[y][x]` with y = 0 at the top
+pub type Field = [[FieldCell; GAME_WIDTH]; GAME_HEIGHT];
+
+/// why an operation on a game or a piece set failed
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameError {
+    /// a file couldn't be read
+    Io(String),
+    /// a piece set file without pieces
+    NoPieces,
+    /// a piece of a piece set file, starting at `line`, is wrong
+    InvalidPiece { line: usize, problem: PieceProblem },
+    /// a cell that isn't on the board
+    OutsideBoard { y: usize, x: usize },
+    /// a cell covered by the falling piece
+    CoveredByPiece { y: usize, x: usize },
+    /// a snapshot with a piece, numbered from 1, that the piece set doesn't have
+    UnknownPiece { piece: usize, pieces: usize },
+    /// a replay share string that can't be read
+    InvalidReplay(ReplayProblem),
+}
+
+/// what is wrong with a piece of a piece set file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PieceProblem {
+    TooTall,
+    TooWide,
+    /// the rows don't have 4 rotations but this many
+    Rotations(usize),
+    Character(char),
+    /// the rotations aren't the same shape turned
+    DifferentShapes,
+}
+
+/// what is wrong with a replay share string
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReplayProblem {
+    /// not made of the version, the seed and the steps
+    Format,
+    Seed,
+    Character(char),
+    /// a number of ticks too large
+    Ticks,
+    /// more steps than a game can take
+    Steps,
+}
+
+impl std::fmt::Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            GameError::Io(ref err) => write!(f, "{}", err),
+            GameError::NoPieces => write!(f, "no pieces defined"),
+            GameError::InvalidPiece { line, problem } => write!(f, "line {}: {}", line, problem),
+            GameError::OutsideBoard { y, x } => write!(f, "cell ({}, {}) is outside the board", y, x),
+            GameError::CoveredByPiece { y, x } => write!(f, "cell ({}, {}) is covered by the falling piece", y, x),
+            GameError::UnknownPiece { piece, pieces } => {
+                write!(f, "the snapshot has piece {} but the piece set has {}", piece, pieces)
+            },
+            GameError::InvalidReplay(problem) => write!(f, "invalid replay: {}", problem),
+        }
+    }
+}
+
+impl std::fmt::Display for PieceProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            PieceProblem::TooTall => write!(f, "pieces can be at most {} rows tall", PIECE_SIZE),
+            PieceProblem::TooWide => write!(f, "pieces can be at most {} cells wide", PIECE_SIZE),
+            PieceProblem::Rotations(found) => write!(f, "expected 4 rotations, found {}", found),
+            PieceProblem::Character(c) => write!(f, "invalid character '{}'", c),
+            PieceProblem::DifferentShapes => write!(f, "the rotations are not the same shape"),
+        }
+    }
+}
+
+impl std::fmt::Display for ReplayProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            ReplayProblem::Format => write!(f, "not a replay of this version"),
+            ReplayProblem::Seed => write!(f, "invalid seed"),
+            ReplayProblem::Character(c) => write!(f, "invalid character '{}'", c),
+            ReplayProblem::Ticks => write!(f, "too many ticks in a row"),
+            ReplayProblem::Steps => write!(f, "too long"),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+/// why a game ended
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameOverReason {
+    /// a new piece overlapped the stack where it spawned, or the falling
+    /// piece was left inside a board filled under it
+    BlockOut,
+    /// a piece locked entirely inside the spawn rows
+    LockOut,
+    /// the time limit ran out (ultra)
+    TimeUp,
+    /// the goal was reached: the lines of a sprint or the cheese cleared
+    Completed,
+}
+
+/// something that happened in the game that the front-end may want to show
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// a piece was locked on the field, covering `cells`. `field` is the
+    /// board with the piece on it, before the rows it completed are cleared
+    PieceLocked { cells: Vec<Point>, field: Box<Field> },
+    /// lines were cleared or a T-spin was locked, scoring these points
+    Scored(ScoreBreakdown),
+    /// a new piece came in: the next one at the start of the entry delay,
+    /// the first one after a reset, or the next one taken by a hold with
+    /// nothing held. If it overlapped the stack the game is over
+    PieceSpawned { shape: PieceShape, topped_out: bool },
+}
+
+/// called by the game with each new piece, see `GameState::set_spawn_hook`
+pub type SpawnHook = Rc<dyn Fn(&GameEvent)>;
+
+/// part of the line clear animation that is running
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClearPhase {
+    /// the full rows are still on the board
+    Flash,
+    /// the full rows are gone, the stack above them hasn't fallen yet
+    Collapse,
+}
+
+/// the line clear being shown, the next piece waits for it to end
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClearAnimation {
+    pub phase: ClearPhase,
+    /// the cleared rows, from top to bottom
+    pub rows: Vec<usize>,
+    /// the board before the rows were removed
+    pub field: Field,
+}
+
+/// a stage of a cascade, with the board as it is after it
+#[derive(Clone, Debug, PartialEq)]
+pub enum CascadeStep {
+    /// these rows were cleared, the rows above moved down over them
+    Clear { rows: Vec<usize>, field: Field },
+    /// the groups of blocks left hanging fell onto the stack
+    Fall { field: Field },
+}
+
+/// which moves of a piece resting on the stack restart its lock delay
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LockDelayPolicy {
+    /// every move and rotation, the piece can be kept up forever
+    Infinite,
+    /// only moving down by a row
+    StepReset,
+    /// moves and rotations, at most this many times per piece
+    MoveReset(u32),
+}
+
+impl Default for LockDelayPolicy {
+    fn default() -> LockDelayPolicy {
+        LockDelayPolicy::MoveReset(15)
+    }
+}
+
+/// what the level does past the maximum set with `GameState::set_max_level`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LevelCap {
+    /// the level stays at the maximum
+    #[default]
+    Stop,
+    /// the level number starts again from 1, the speed stays the fastest
+    Wrap,
+}
+
+impl std::str::FromStr for LevelCap {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<LevelCap, ()> {
+        match s {
+            "stop" => Ok(LevelCap::Stop),
+            "wrap" => Ok(LevelCap::Wrap),
+            _ => Err(()),
+        }
+    }
+}
+
+/// points scored by a piece, split by where they come from
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScoreBreakdown {
+    pub lines: usize,
+    /// points for the cleared lines
+    pub base: i32,
+    /// consecutive pieces that cleared lines
+    pub combo: i32,
+    /// difficult clears (tetrises and T-spins) one after the other
+    pub b2b: i32,
+    pub tspin: i32,
+    /// the clear left the board empty
+    pub perfect: i32,
+    /// bonus for the lines cleared at the end of a zone
+    pub zone: i32,
+    /// points lost for the holes the piece made, zero or less
+    pub holes: i32,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> i32 {
+        self.base + self.combo + self.b2b + self.tspin + self.perfect + self.zone + self.holes
+    }
+}
+
+/// a position to practice again, saved with `GameState::snapshot`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    field: Field,
+    current_piece: Piece,
+    next_piece: Piece,
+    held: Option<PieceShape>,
+    hold_used: bool,
+    score: i32,
+    #[serde(default)]
+    peak_score: i32,
+    lines: i32,
+    combo: i32,
+    back_to_back: bool,
+}
+
+/// everything a game is set up with, for `GameState::with_config`. Set
+/// the fields that matter and take the others from the default:
+/// `GameConfig { seed: Some(7), ..GameConfig::default() }`
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameConfig {
+    /// shapes of the pieces, the tetrominoes by default
+    pub piece_set: Rc<PieceSet>,
+    /// seed of the pieces, None (the default) for a seed from the system
+    pub seed: Option<u64>,
+    /// seed of the holes of `GameState::add_garbage`, made from `seed` if
+    /// None (the default), from the system if that is None too
+    pub garbage_seed: Option<u64>,
+    /// how the shapes are picked, `Random` by default
+    pub randomizer: Randomizer,
+    /// kicks and spawn orientations, SRS by default
+    pub rotation_system: RotationSystem,
+    /// moves that restart the lock delay, up to 15 moves by default
+    pub lock_policy: LockDelayPolicy,
+    /// entry delay after a piece locks in milliseconds, 0 by default
+    pub entry_delay_ms: i32,
+    /// time the cleared lines are shown, 0 (the default) for none
+    pub clear_animation_ms: i32,
+    /// time the gap of the cleared lines is shown, 0 by default
+    pub collapse_animation_ms: i32,
+    /// held pieces come back in their spawn orientation, true by default
+    pub hold_resets_orientation: bool,
+    /// level the games start at, 1 by default
+    pub start_level: i32,
+    /// level where the gravity stops getting faster, None by default
+    pub max_level: Option<i32>,
+    /// what the level does past `max_level`, `Stop` by default
+    pub level_cap: LevelCap,
+    /// lines that complete a sprint, None (the default) for no goal
+    pub line_goal: Option<i32>,
+    /// game time of an ultra in milliseconds, None (the default) for none
+    pub time_limit_ms: Option<i32>,
+    /// allow zones, off by default
+    pub zone: bool,
+    /// hanging blocks fall after clears, off by default
+    pub cascade: bool,
+    /// points lost for each hole a piece makes, 0 (the default) for none
+    pub hole_penalty: i32,
+    /// random colors for the pieces, off by default, drawn from `seed` if it
+    /// is set
+    pub rainbow: bool,
+    /// pieces sometimes lock with an item block, off by default
+    pub items: bool,
+    /// hide the next piece, off by default
+    pub next_hidden: bool,
+    /// rewinds allowed after a top out in each game, 0 (the default) for
+    /// none
+    pub rewinds: u32,
+}
+
+impl Default for GameConfig {
+    fn default() -> GameConfig {
+        GameConfig {
+            piece_set: Rc::new(PieceSet::standard()),
+            seed: None,
+            garbage_seed: None,
+            randomizer: Randomizer::default(),
+            rotation_system: RotationSystem::default(),
+            lock_policy: LockDelayPolicy::default(),
+            entry_delay_ms: 0,
+            clear_animation_ms: 0,
+            collapse_animation_ms: 0,
+            hold_resets_orientation: true,
+            start_level: 1,
+            max_level: None,
+            level_cap: LevelCap::default(),
+            line_goal: None,
+            time_limit_ms: None,
+            zone: false,
+            cascade: false,
+            hole_penalty: 0,
+            rainbow: false,
+            items: false,
+            next_hidden: false,
+            rewinds: 0,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct GameState {
+    pub score: i32,
+    pub level: i32,
+    lines: i32,
+    game_over: Option<GameOverReason>,
+    /// set while a menu or dialog is open, stops the gravity and the timer
+    paused: bool,
+    clock: Rc<dyn Clock>,
+    started: Duration,
+    paused_at: Option<Duration>,
+    /// time spent paused since the game started
+    paused_for: Duration,
+    topped_out: Option<Piece>,
+    delay: i32,
+    pieces: i32,
+    cheese: bool,
+    /// lines to clear to complete the game, for sprints
+    line_goal: Option<i32>,
+    /// game time for ultra, and what's left of it
+    time_limit: Option<i32>,
+    time_left: Option<i32>,
+    field: Field,
+    current_piece: Piece,
+    next_piece: Piece,
+    next_hidden: bool,
+    held: Option<PieceShape>,
+    /// rotation the held piece had, it comes back with it unless
+    /// `hold_resets_orientation` is set
+    held_rotation: PieceRotation,
+    /// color the held piece had in rainbow mode, it comes back with it
+    held_color: Option<u8>,
+    hold_resets_orientation: bool,
+    hold_used: bool,
+    holds: i32,
+    /// moves and rotations of the falling piece so far
+    inputs: u32,
+    finesse_faults: i32,
+    finesse: Rc<FinesseTable>,
+    /// entry delay in ms between a piece locking and the next one moving
+    are: i32,
+    entry_delay: i32,
+    /// length in ms of the two parts of the line clear animation
+    clear_ms: i32,
+    collapse_ms: i32,
+    clear_animation: Option<ClearAnimation>,
+    lock_policy: LockDelayPolicy,
+    /// level before the first points are scored
+    start_level: i32,
+    /// highest score of the game, the level comes from it so that the
+    /// hole penalty doesn't take levels away
+    peak_score: i32,
+    /// highest level, where the gravity stops getting faster
+    max_level: Option<i32>,
+    level_cap: LevelCap,
+    /// time left before the piece locks, counted while it rests on the stack
+    lock_delay: i32,
+    lock_resets: u32,
+    rotation_system: RotationSystem,
+    /// T-spin of the last rotation, if the piece didn't move after it
+    last_spin: Spin,
+    /// pieces in a row that cleared lines
+    combo: i32,
+    back_to_back: bool,
+    zone_enabled: bool,
+    /// lines cleared towards the next zone, up to `ZONE_CHARGE_LINES`
+    zone_charge: i32,
+    /// time left in the running zone
+    zone_left: Option<i32>,
+    /// full rows kept at the bottom of the board during the zone
+    zone_lines: usize,
+    /// gives each new piece a random color, in rainbow mode
+    colors: Option<StdRng>,
+    /// places the item blocks on the pieces that lock, in items mode
+    items: Option<StdRng>,
+    /// points lost for each hole a piece makes, 0 turns the penalty off
+    hole_penalty: i32,
+    /// holes made by the piece that just locked
+    holes_made: i32,
+    /// after a clear, blocks fall in connected groups and may clear more lines
+    cascade: bool,
+    /// stages of the last cascade
+    cascade_steps: Vec<CascadeStep>,
+    /// rewinds allowed in each game and left in this one
+    rewinds: u32,
+    rewinds_left: u32,
+    /// the game at the start of the last `REWIND_PIECES` pieces, oldest
+    /// first, kept when rewinds are allowed
+    rewind_history: VecDeque<Snapshot>,
+    /// garbage lines the clears would send in versus, and lines of
+    /// garbage added with `add_garbage`
+    lines_sent: usize,
+    lines_received: usize,
+    /// the board was replaced with `set_field` or edited with `set_cell`
+    /// since the falling piece came in, it can have full rows or be over
+    /// the piece until it locks
+    field_set: bool,
+    events: Vec<GameEvent>,
+    /// told about each new piece before it can be moved
+    spawn_hook: Option<SpawnHook>,
+    shapes: Rc<PieceSet>,
+    source: Box<dyn PieceSource>,
+    /// picks the holes of `add_garbage`, apart from the pieces
+    garbage: StdRng,
+}
+
+impl Default for GameState {
+    fn default() -> GameState {
+        GameState::new()
+    }
+}
+
+impl GameState {
+    pub fn new() -> GameState {
+        GameState::with_config(GameConfig::default())
+    }
+
+    /// a new game set up as `config` says, the other constructors are
+    /// shortcuts for the common cases
+    pub fn with_config(config: GameConfig) -> GameState {
+        let source = match config.seed {
+            Some(seed) => config.randomizer.seeded(seed),
+            None => config.randomizer.source(),
+        };
+        let mut game = GameState::build(config.piece_set, source);
+        if let Some(seed) = config.garbage_seed.or(config.seed.map(|seed| seed ^ GARBAGE_SEED_MIX)) {
+            game.garbage = StdRng::seed_from_u64(seed);
+        }
+        game.set_rotation_system(config.rotation_system);
+        game.set_lock_delay_policy(config.lock_policy);
+        game.set_entry_delay(config.entry_delay_ms);
+        game.set_clear_animation(config.clear_animation_ms, config.collapse_animation_ms);
+        game.set_hold_resets_orientation(config.hold_resets_orientation);
+        game.set_max_level(config.max_level, config.level_cap);
+        game.set_start_level(config.start_level);
+        game.set_line_goal(config.line_goal);
+        game.set_time_limit(config.time_limit_ms);
+        game.set_zone_enabled(config.zone);
+        game.set_cascade(config.cascade);
+        game.set_hole_penalty(config.hole_penalty);
+        game.set_rainbow(config.rainbow);
+        if let (true, Some(seed)) = (config.rainbow, config.seed) {
+            game.colors = Some(StdRng::seed_from_u64(seed ^ COLOR_SEED_MIX));
+        }
+        if config.rainbow {
+            // the first two pieces came in before the colors were set
+            game.current_piece.color = game.new_color();
+            game.next_piece.color = game.new_color();
+        }
+        game.set_items(config.items);
+        if let (true, Some(seed)) = (config.items, config.seed) {
+            game.items = Some(StdRng::seed_from_u64(seed ^ ITEM_SEED_MIX));
+        }
+        game.set_next_hidden(config.next_hidden);
+        game.set_rewinds(config.rewinds);
+        game
+    }
+
+    /// a new game using the given shapes instead of the tetrominoes
+    pub fn with_piece_set(shapes: Rc<PieceSet>) -> GameState {
+        GameState::with_config(GameConfig { piece_set: shapes, ..GameConfig::default() })
+    }
+
+    /// a new game where the sequence of pieces only depends on `seed`, and
+    /// the holes of the garbage on a seed made from it
+    pub fn with_seed(seed: u64) -> GameState {
+        GameState::with_config(GameConfig { seed: Some(seed), ..GameConfig::default() })
+    }
+
+    /// a new game where the pieces only depend on `piece_seed` and the
+    /// holes of the garbage added with `add_garbage` on `garbage_seed`
+    pub fn with_seeds(piece_seed: u64, garbage_seed: u64) -> GameState {
+        GameState::with_config(GameConfig {
+            seed: Some(piece_seed),
+            garbage_seed: Some(garbage_seed),
+            ..GameConfig::default()
+        })
+    }
+
+    /// a new game with the tetrominoes drawn from `source`, e.g. a
+    /// `FixedSource` to script the pieces
+    pub fn with_source<S: PieceSource + 'static>(source: S) -> GameState {
+        GameState::build(Rc::new(PieceSet::standard()), Box::new(source))
+    }
+
+    fn build(shapes: Rc<PieceSet>, source: Box<dyn PieceSource>) -> GameState {
+        let clock: Rc<dyn Clock> = Rc::new(SystemClock::new());
+        let mut game = GameState {
+            field: [[FieldCell::Empty; GAME_WIDTH]; GAME_HEIGHT],
+            score: 0,
+            peak_score: 0,
+            level: 1,
+            lines: 0,
+            delay: 0,
+            pieces: 0,
+            cheese: false,
+            line_goal: None,
+            time_limit: None,
+            time_left: None,
+            // replaced by the first pieces drawn in `reset`
+            current_piece: Piece::spawned(PieceShape(0)),
+            next_piece: Piece::spawned(PieceShape(0)),
+            next_hidden: false,
+            held: None,
+            held_rotation: PieceRotation::NORMAL,
+            held_color: None,
+            hold_resets_orientation: true,
+            hold_used: false,
+            holds: 0,
+            inputs: 0,
+            finesse_faults: 0,
+            finesse: FinesseTable::shared(&shapes, RotationSystem::default()),
+            are: 0,
+            entry_delay: 0,
+            clear_ms: 0,
+            collapse_ms: 0,
+            clear_animation: None,
+            lock_policy: LockDelayPolicy::default(),
+            start_level: 1,
+            max_level: None,
+            level_cap: LevelCap::default(),
+            lock_delay: LOCK_DELAY_MS,
+            lock_resets: 0,
+            rotation_system: RotationSystem::default(),
+            game_over: None,
+            paused: false,
+            started: clock.now(),
+            clock,
+            paused_at: None,
+            paused_for: Duration::from_secs(0),
+            topped_out: None,
+            last_spin: Spin::None,
+            combo: 0,
+            back_to_back: false,
+            zone_enabled: false,
+            zone_charge: 0,
+            zone_left: None,
+            zone_lines: 0,
+            colors: None,
+            items: None,
+            hole_penalty: 0,
+            holes_made: 0,
+            cascade: false,
+            cascade_steps: Vec::new(),
+            rewinds: 0,
+            rewinds_left: 0,
+            rewind_history: VecDeque::new(),
+            events: Vec::new(),
+            lines_sent: 0,
+            lines_received: 0,
+            field_set: false,
+            spawn_hook: None,
+            shapes,
+            source,
+            garbage: StdRng::from_entropy(),
+        };
+        game.reset();
+        // nothing can be listening yet, the stream starts empty
+        game.events.clear();
+        game
+    }
+
+    /// start a new game reusing this one, without allocating. The piece
+    /// set, the entry delay, the rotation system, the levels, the goals and
+    /// whether the next piece is hidden are kept, and the pieces keep
+    /// coming from the same source
+    pub fn reset(&mut self) {
+        self.clear_field();
+        self.score = 0;
+        self.peak_score = 0;
+        self.update_level();
+        self.lines = 0;
+        self.lines_sent = 0;
+        self.lines_received = 0;
+        self.pieces = 0;
+        self.cheese = false;
+        self.current_piece = self.new_piece();
+        self.next_piece = self.new_piece();
+        self.held = None;
+        self.held_color = None;
+        self.hold_used = false;
+        self.holds = 0;
+        self.inputs = 0;
+        self.finesse_faults = 0;
+        self.entry_delay = 0;
+        self.clear_animation = None;
+        self.cascade_steps.clear();
+        self.lock_delay = LOCK_DELAY_MS;
+        self.lock_resets = 0;
+        self.game_over = None;
+        self.time_left = self.time_limit;
+        self.paused = false;
+        self.reset_timer();
+        self.topped_out = None;
+        self.last_spin = Spin::None;
+        self.combo = 0;
+        self.back_to_back = false;
+        self.zone_charge = 0;
+        self.zone_left = None;
+        self.events.clear();
+        self.timer_reset();
+        self.rewinds_left = self.rewinds;
+        self.rewind_history.clear();
+        self.remember_for_rewind();
+        self.piece_spawned();
+    }
+
+    /// cell at (y, x) with the falling piece drawn over the settled board,
+    /// use `field_cell` to get only the settled blocks
+    pub fn get(&self, y: usize, x: usize) -> FieldCell {
+        if self.current_piece.contains(&self.shapes, Point { y: y as i32, x: x as i32 }) {
+            return self.current_piece.cell();
+        }
+        self.field_cell(y, x)
+    }
+
+    /// replace the whole settled board, e.g. to set up a position
+    pub fn set_field(&mut self, field: Field) {
+        self.field = field;
+        self.field_set = true;
+    }
+
+    /// change one cell of the settled board, for board editors and test
+    /// fixtures. Fails outside the board and on the cells of the falling
+    /// piece, which would be left overlapping the stack
+    pub fn set_cell(&mut self, y: usize, x: usize, cell: FieldCell) -> Result<(), GameError> {
+        if y >= GAME_HEIGHT || x >= GAME_WIDTH {
+            return Err(GameError::OutsideBoard { y, x });
+        }
+        let point = Point { x: x as i32, y: y as i32 };
+        if cell != FieldCell::Empty && self.current_piece.contains(&self.shapes, point) {
+            return Err(GameError::CoveredByPiece { y, x });
+        }
+        self.field[y][x] = cell;
+        self.field_set = true;
+        Ok(())
+    }
+
+    /// empty the settled board, the falling piece stays where it is
+    pub fn clear_field(&mut self) {
+        for row in self.field.iter_mut() {
+            *row = [FieldCell::Empty; GAME_WIDTH];
+        }
+        self.zone_lines = 0;
+        self.field_set = false;
+    }
+
+    /// copy of the position that `restore` can go back to
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            field: self.field,
+            current_piece: self.current_piece,
+            next_piece: self.next_piece,
+            held: self.held,
+            hold_used: self.hold_used,
+            score: self.score,
+            peak_score: self.peak_score,
+            lines: self.lines,
+            combo: self.combo,
+            back_to_back: self.back_to_back,
+        }
+    }
+
+    /// go back to a saved position, fails if it has pieces that are
+    /// not in the piece set of this game
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), GameError> {
+        let cells = snapshot.field.iter().flatten().filter_map(|cell| match cell {
+            FieldCell::Occupied(shape) | FieldCell::Painted(shape, _) | FieldCell::Item(shape, _) => Some(*shape),
+            _ => None,
+        });
+        let pieces = [snapshot.current_piece.shape, snapshot.next_piece.shape];
+        for shape in pieces.iter().copied().chain(snapshot.held).chain(cells) {
+            if shape.0 >= self.shapes.len() {
+                return Err(GameError::UnknownPiece { piece: shape.0 + 1, pieces: self.shapes.len() });
+            }
+        }
+        self.field = snapshot.field;
+        self.current_piece = snapshot.current_piece;
+        self.next_piece = snapshot.next_piece;
+        self.held = snapshot.held;
+        self.held_rotation = snapshot.held.map_or(PieceRotation::NORMAL, |shape| {
+            self.rotation_system.spawned(&self.shapes, shape).rotation
+        });
+        self.hold_used = snapshot.hold_used;
+        self.score = snapshot.score;
+        self.peak_score = snapshot.peak_score;
+        self.lines = snapshot.lines;
+        self.combo = snapshot.combo;
+        self.back_to_back = snapshot.back_to_back;
+        self.update_level();
+        self.zone_left = None;
+        self.zone_lines = 0;
+        self.game_over = None;
+        self.topped_out = None;
+        self.last_spin = Spin::None;
+        self.inputs = 0;
+        self.entry_delay = 0;
+        self.clear_animation = None;
+        self.cascade_steps.clear();
+        self.timer_reset();
+        self.check_top_out();
+        self.rewind_history.clear();
+        self.remember_for_rewind();
+        Ok(())
+    }
+
+    /// let the player go back `REWIND_PIECES` pieces after topping out,
+    /// `count` times in a game. Kept by `reset`, which gives them back
+    pub fn set_rewinds(&mut self, count: u32) {
+        self.rewinds = count;
+        self.rewinds_left = count;
+        self.rewind_history.clear();
+        self.remember_for_rewind();
+    }
+
+    /// rewinds allowed in each game
+    pub fn rewinds(&self) -> u32 {
+        self.rewinds
+    }
+
+    pub fn rewinds_left(&self) -> u32 {
+        self.rewinds_left
+    }
+
+    /// true if the game topped out and `rewind` can take it back
+    pub fn can_rewind(&self) -> bool {
+        let topped_out = matches!(self.game_over, Some(GameOverReason::BlockOut) | Some(GameOverReason::LockOut));
+        topped_out && self.rewinds_left > 0 && !self.rewind_history.is_empty()
+    }
+
+    /// after a top out, go back to the start of the piece that fell
+    /// `REWIND_PIECES` pieces ago, or of the first one, and play on. Uses
+    /// one of the rewinds left, false if there are none or the game
+    /// didn't top out
+    pub fn rewind(&mut self) -> bool {
+        if !self.can_rewind() {
+            return false;
+        }
+        let snapshot = self.rewind_history.pop_front().expect("can_rewind checks the history");
+        self.restore(&snapshot).expect("the snapshots of a game fit its piece set");
+        self.rewinds_left -= 1;
+        self.check_invariants();
+        true
+    }
+
+    /// keep the game at the start of the falling piece for `rewind`
+    fn remember_for_rewind(&mut self) {
+        if self.rewinds == 0 {
+            return;
+        }
+        if self.rewind_history.len() == REWIND_PIECES {
+            self.rewind_history.pop_front();
+        }
+        self.rewind_history.push_back(self.snapshot());
+    }
+
+    /// cell at (y, x) of the settled board, ignoring the falling piece.
+    /// Empty outside the board
+    pub fn field_cell(&self, y: usize, x: usize) -> FieldCell {
+        self.field.get(y).and_then(|row| row.get(x)).copied().unwrap_or(FieldCell::Empty)
+    }
+
+    /// height of the settled stack in each column, from the floor to the
+    /// highest block even if there are holes under it
+    pub fn column_heights(&self) -> [usize; GAME_WIDTH] {
+        self.bitboard().column_heights()
+    }
+
+    /// empty cells of the settled stack with a block above them
+    pub fn count_holes(&self) -> u32 {
+        self.bitboard().count_holes()
+    }
+
+    /// the step in height from each column of the settled stack to the
+    /// next one on the right, the shape of its surface
+    pub fn surface_profile(&self) -> [i32; GAME_WIDTH - 1] {
+        self.bitboard().surface_profile()
+    }
+
+    /// true if a column of the settled stack reaches into the top `rows`
+    /// rows of the board
+    pub fn stack_reaches(&self, rows: usize) -> bool {
+        let limit = GAME_HEIGHT.saturating_sub(rows);
+        rows > 0 && self.column_heights().iter().any(|&height| height > limit)
+    }
+
+    /// copy of the settled board without the falling piece, as rows from
+    /// the top down, so a cell is `snapshot[y][x]` like in `Field`. Without
+    /// `spawn_rows` the top `SPAWN_ROWS` rows are left out and the first
+    /// row of the copy is board row `SPAWN_ROWS`
+    pub fn field_snapshot(&self, spawn_rows: bool) -> Vec<Vec<FieldCell>> {
+        let skip = if spawn_rows { 0 } else { SPAWN_ROWS };
+        self.field[skip..].iter().map(|row| row.to_vec()).collect()
+    }
+
+    pub fn move_left(&mut self) {
+        self.shift(Direction::LEFT);
+        self.check_invariants();
+    }
+
+    pub fn move_right(&mut self) {
+        self.shift(Direction::RIGHT);
+        self.check_invariants();
+    }
+
+    /// shift the falling piece until its leftmost block is in `column`, or
+    /// as close as it gets before something blocks it, for players who
+    /// pick a column instead of pressing the arrows. Returns the columns
+    /// moved, negative to the left
+    pub fn move_to_column(&mut self, column: i32) -> i32 {
+        let mut moved = 0;
+        loop {
+            let left = self.current_piece.absolute_cells(&self.shapes).map(|cell| cell.x).min().unwrap_or(column);
+            let (direction, step) = match left.cmp(&column) {
+                std::cmp::Ordering::Less => (Direction::RIGHT, 1),
+                std::cmp::Ordering::Greater => (Direction::LEFT, -1),
+                std::cmp::Ordering::Equal => break,
+            };
+            if !self.can_move(direction) {
+                break;
+            }
+            self.shift(direction);
+            moved += step;
+        }
+        self.check_invariants();
+        moved
+    }
+
+    fn shift(&mut self, direction: Direction) {
+        if self.is_entering() {
+            return;
+        }
+        self.inputs += 1;
+        if self.can_move(direction) {
+            let grounded = self.is_grounded();
+            self.current_piece = self.current_piece.moved(direction);
+            self.last_spin = Spin::None;
+            self.lock_delay_move(grounded);
+        }
+    }
+
+    /// whether the falling piece rests on the stack or the floor
+    fn is_grounded(&self) -> bool {
+        !self.current_piece.moved(Direction::DOWN).check_collision(self)
+    }
+
+    /// restart the lock delay after a move or rotation, if the policy allows it
+    fn lock_delay_move(&mut self, grounded: bool) {
+        match self.lock_policy {
+            LockDelayPolicy::Infinite => self.lock_delay = LOCK_DELAY_MS,
+            LockDelayPolicy::StepReset => {},
+            LockDelayPolicy::MoveReset(max) => {
+                if grounded && self.lock_resets < max {
+                    self.lock_resets += 1;
+                    self.lock_delay = LOCK_DELAY_MS;
+                }
+            },
+        }
+    }
+
+        pub fn move_bottom(&mut self) {
+        if self.is_entering() {
+            return;
+        }
+        if self.drop_distance() > 0 {
+            self.current_piece = self.ghost_piece();
+            self.last_spin = Spin::None;
+        }
+        self.piece_bottom();
+        self.check_invariants();
+    }
+
+    /// rows the falling piece can move down before it hits the stack or the floor
+    pub fn drop_distance(&self) -> usize {
+        let mut distance = 0;
+        while self.current_piece.translated(0, distance as i32 + 1).check_collision(self) {
+            distance += 1;
+        }
+        distance
+    }
+
+    /// where the falling piece would land if dropped now
+    pub fn ghost_piece(&self) -> Piece {
+        self.current_piece.translated(0, self.drop_distance() as i32)
+    }
+
+    /// move the falling piece down to one row above where it would rest,
+    /// unlike a sonic drop it isn't on the stack yet, so it can still be
+    /// lined up before gravity brings it down
+    pub fn drop_to_rest_no_lock(&mut self) {
+        if self.is_entering() {
+            return;
+        }
+        let distance = self.drop_distance().saturating_sub(1);
+        if distance > 0 {
+            self.current_piece = self.current_piece.translated(0, distance as i32);
+            self.last_spin = Spin::None;
+        }
+        self.check_invariants();
+    }
+
+    pub fn move_down(&mut self) {
+        if !self.is_entering() && !self.step_down() {
+            self.piece_bottom()
+        }
+        self.check_invariants();
+    }
+
+    /// rotate the falling piece clockwise, same as `rotate_cw`
+    pub fn rotate(&mut self) -> Option<Rotation> {
+        self.rotate_cw()
+    }
+
+    /// rotate the falling piece clockwise, trying the wall kicks in order.
+    /// Returns the kick that was used, or None if the piece can't rotate
+    pub fn rotate_cw(&mut self) -> Option<Rotation> {
+        let rotated = self.rotation_system.turned(&self.shapes, self.current_piece, true);
+        let rotation = self.rotate_to(rotated);
+        self.check_invariants();
+        rotation
+    }
+
+    /// rotate the falling piece counterclockwise, with the kicks of that
+    /// direction, which aren't the clockwise ones mirrored
+    pub fn rotate_ccw(&mut self) -> Option<Rotation> {
+        let rotated = self.rotation_system.turned(&self.shapes, self.current_piece, false);
+        let rotation = self.rotate_to(rotated);
+        self.check_invariants();
+        rotation
+    }
+
+    fn rotate_to(&mut self, rotated: Piece) -> Option<Rotation> {
+        if self.is_entering() {
+            return None;
+        }
+        self.inputs += 1;
+        let (kicked, kick, last_kick) = self.kicked_rotation(rotated)?;
+        let grounded = self.is_grounded();
+        self.current_piece = kicked;
+        self.last_spin = self.t_spin(last_kick);
+        self.lock_delay_move(grounded);
+        Some(Rotation {
+            kick,
+            spin: self.last_spin,
+        })
+    }
+
+    /// the falling piece turned to `rotated` with the first kick that
+    /// fits, the kick offset and whether it was the last one of the table
+    fn kicked_rotation(&self, rotated: Piece) -> Option<(Piece, Point, bool)> {
+        let kicks = self.rotation_system.kicks(&self.shapes, rotated.shape, self.current_piece.rotation, rotated.rotation);
+        kicks.iter().enumerate().find_map(|(i, &(dx, dy))| {
+            let kicked = rotated.translated(dx, dy);
+            if kicked.check_collision(self) {
+                Some((kicked, Point { x: dx, y: dy }, i > 0 && i == kicks.len() - 1))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// true if the falling piece can move one cell in the direction
+    pub fn can_move(&self, direction: Direction) -> bool {
+        !self.is_entering() && self.current_piece.moved(direction).check_collision(self)
+    }
+
+    /// true if `rotate` would succeed, with or without a wall kick
+    pub fn can_rotate(&self) -> bool {
+        !self.is_entering() && self.kicked_rotation(self.rotation_system.turned(&self.shapes, self.current_piece, true)).is_some()
+    }
+
+    /// true if `rotate_ccw` would succeed, with or without a wall kick
+    pub fn can_rotate_ccw(&self) -> bool {
+        !self.is_entering() && self.kicked_rotation(self.rotation_system.turned(&self.shapes, self.current_piece, false)).is_some()
+    }
+
+    /// true if `hold` would swap the falling piece
+    pub fn can_hold(&self) -> bool {
+        !self.is_entering() && !self.hold_used
+    }
+
+    /// false once hold was used for the falling piece, until it locks.
+    /// Unlike `can_hold` it stays true during the entry delay
+    pub fn hold_available(&self) -> bool {
+        !self.hold_used
+    }
+
+    /// set the entry delay (ARE) after each piece locks, in milliseconds
+    pub fn set_entry_delay(&mut self, ms: i32) {
+        self.are = ms;
+    }
+
+    /// show line clears for `clear_ms` and then leave the gap for
+    /// `collapse_ms` before the stack falls, in milliseconds. The game is
+    /// stopped meanwhile, on top of the entry delay. Zero for both clears
+    /// lines at once
+    pub fn set_clear_animation(&mut self, clear_ms: i32, collapse_ms: i32) {
+        self.clear_ms = clear_ms.max(0);
+        self.collapse_ms = collapse_ms.max(0);
+    }
+
+    /// the line clear animation that is running, if any
+    pub fn clear_animation(&self) -> Option<&ClearAnimation> {
+        self.clear_animation.as_ref()
+    }
+
+    /// true during the entry delay, when the new piece can't be controlled
+    pub fn is_entering(&self) -> bool {
+        self.entry_delay > 0
+    }
+
+    /// classify the falling piece position with the three corner rule: a T
+    /// with three of the corners around its center occupied spins, fully if
+    /// both the corners it points to are occupied or it used the last kick
+    fn t_spin(&self, last_kick: bool) -> Spin {
+        use PieceRotation::*;
+        let piece = self.current_piece;
+        if !self.shapes.is_t(piece.shape) {
+            return Spin::None;
+        }
+        let (y, x) = (piece.position.y + 1, piece.position.x + 1);
+        let top_left = self.is_blocked(y - 1, x - 1);
+        let top_right = self.is_blocked(y - 1, x + 1);
+        let bottom_left = self.is_blocked(y + 1, x - 1);
+        let bottom_right = self.is_blocked(y + 1, x + 1);
+        let corners = [top_left, top_right, bottom_left, bottom_right].iter().filter(|&&c| c).count();
+        if corners < 3 {
+            return Spin::None;
+        }
+        let front = match piece.rotation {
+            NORMAL => top_left && top_right,
+            RIGHT => top_right && bottom_right,
+            REVERSE => bottom_left && bottom_right,
+            LEFT => top_left && bottom_left,
+        };
+        if front || last_kick {
+            Spin::Full
+        } else {
+            Spin::Mini
+        }
+    }
+
+    /// do what the key bound to `action` does
+    pub fn apply(&mut self, action: Action) {
+        match action {
+            Action::MoveLeft => self.move_left(),
+            Action::MoveRight => self.move_right(),
+            Action::MoveDown => self.move_down(),
+            Action::DropToRest => self.drop_to_rest_no_lock(),
+            Action::HardDrop => self.move_bottom(),
+            Action::RotateCw => {
+                self.rotate_cw();
+            },
+            Action::RotateCcw => {
+                self.rotate_ccw();
+            },
+            Action::Hold => self.hold(),
+            Action::StartZone => {
+                self.start_zone();
+            },
+            Action::Rewind => {
+                self.rewind();
+            },
+        }
+    }
+
+    /// swap the falling piece with the held one, or with the next piece if
+    /// nothing is held yet. Only allowed once per piece
+    pub fn hold(&mut self) {
+        if !self.can_hold() {
+            return;
+        }
+        let swapped = match self.held {
+            Some(shape) if self.hold_resets_orientation => self.rotation_system.spawned(&self.shapes, shape),
+            Some(shape) => Piece::spawned(shape).with_rotation(self.held_rotation),
+            None => {
+                let next = self.next_piece;
+                self.next_piece = self.new_piece();
+                next
+            }
+        };
+        let swapped = Piece { color: self.held_color, ..swapped };
+        let from_next = self.held.is_none();
+        self.held = Some(self.current_piece.shape);
+        self.held_rotation = self.current_piece.rotation;
+        self.held_color = self.current_piece.color;
+        self.current_piece = swapped;
+        self.hold_used = true;
+        self.holds += 1;
+        self.inputs = 0;
+        self.lock_delay = LOCK_DELAY_MS;
+        self.lock_resets = 0;
+        self.check_top_out();
+        if from_next {
+            self.piece_spawned();
+        }
+        self.check_invariants();
+    }
+
+    /// whether a piece coming back from hold spawns in its spawn
+    /// orientation, the default, or in the rotation it was held in. Either
+    /// way it comes back at the spawn position
+    pub fn set_hold_resets_orientation(&mut self, resets: bool) {
+        self.hold_resets_orientation = resets;
+    }
+
+    /// shape in the hold box
+    pub fn held_shape(&self) -> Option<PieceShape> {
+        self.held
+    }
+
+    /// the held piece as it comes back, with its color
+    pub fn held_piece(&self) -> Option<Piece> {
+        self.held.map(|shape| Piece { color: self.held_color, ..Piece::spawned(shape) })
+    }
+
+    /// number of times hold was used in this game
+    pub fn hold_count(&self) -> i32 {
+        self.holds
+    }
+
+    /// clears in a row after the first one
+    pub fn combo(&self) -> i32 {
+        (self.combo - 1).max(0)
+    }
+
+    /// pieces placed with more inputs than needed
+    pub fn finesse_faults(&self) -> i32 {
+        self.finesse_faults
+    }
+
+    pub fn clock_tick(&mut self) {
+        self.tick();
+        self.check_invariants();
+    }
+
+    fn tick(&mut self) {
+        if self.paused || self.is_lost() {
+            return;
+        }
+        if let Some(left) = self.time_left.as_mut() {
+            *left -= TICK_MS;
+            if *left <= 0 {
+                self.game_over = Some(GameOverReason::TimeUp);
+                return;
+            }
+        }
+        if let Some(left) = self.zone_left.as_mut() {
+            *left -= TICK_MS;
+            if *left <= 0 {
+                self.end_zone();
+                if self.is_lost() {
+                    return;
+                }
+            }
+        }
+        if self.is_entering() {
+            self.entry_delay -= TICK_MS;
+            self.update_clear_animation();
+            return;
+        }
+        if self.is_zone_active() {
+            // the time is stopped, the piece only locks when dropped
+            return;
+        }
+        if self.is_grounded() {
+            self.lock_delay -= TICK_MS;
+            if self.lock_delay <= 0 {
+                self.piece_bottom();
+            }
+            return;
+        }
+        // a delay that isn't a whole number of ticks would go past zero
+        self.delay -= TICK_MS;
+        if self.delay <= 0 {
+            self.timer_reset();
+            self.step_down();
+        }
+    }
+
+    /// time before gravity moves the falling piece down by a row
+    pub fn time_to_next_drop(&self) -> Duration {
+        Duration::from_millis(self.delay.max(0) as u64)
+    }
+
+    /// choose which moves restart the lock delay
+    pub fn set_lock_delay_policy(&mut self, policy: LockDelayPolicy) {
+        self.lock_policy = policy;
+    }
+
+    /// choose the kicks and spawn orientations, the falling and next
+    /// pieces are spawned again so it's meant for the start of a game
+    pub fn set_rotation_system(&mut self, system: RotationSystem) {
+        self.rotation_system = system;
+        self.current_piece = Piece { color: self.current_piece.color, ..system.spawned(&self.shapes, self.current_piece.shape) };
+        self.next_piece = Piece { color: self.next_piece.color, ..system.spawned(&self.shapes, self.next_piece.shape) };
+        self.finesse = FinesseTable::shared(&self.shapes, system);
+        self.check_top_out();
+    }
+
+    pub fn rotation_system(&self) -> RotationSystem {
+        self.rotation_system
+    }
+
+    /// true once the game is over, see `game_over_reason` for why
+    pub fn is_lost(&self) -> bool {
+        self.game_over.is_some()
+    }
+
+    pub fn game_over_reason(&self) -> Option<GameOverReason> {
+        self.game_over
+    }
+
+    /// end the game with `Completed` once `lines` lines are cleared, for
+    /// sprints. Kept by `reset`
+    pub fn set_line_goal(&mut self, lines: Option<i32>) {
+        self.line_goal = lines;
+    }
+
+    /// end the game with `TimeUp` after `ms` milliseconds of game clock,
+    /// for ultra. Kept by `reset`
+    pub fn set_time_limit(&mut self, ms: Option<i32>) {
+        self.time_limit = ms;
+        self.time_left = ms;
+    }
+
+    /// game time left before the time limit runs out
+    pub fn time_left(&self) -> Option<Duration> {
+        self.time_left.map(|ms| Duration::from_millis(ms.max(0) as u64))
+    }
+
+    /// stop the level at `max`, or wrap it around with `LevelCap::Wrap`.
+    /// From there on the gravity stays at its fastest, a row every tick.
+    /// Kept by `reset`
+    pub fn set_max_level(&mut self, max: Option<i32>, cap: LevelCap) {
+        self.max_level = max.map(|max| max.max(1));
+        self.level_cap = cap;
+        self.update_level();
+    }
 
-#[derive(Clone, Copy, Debug)]
-struct Point {
-    x: i32,
-    y: i32,
-}
+    /// start the games at `level` instead of 1, the points go on from
+    /// there. Kept by `reset`
+    pub fn set_start_level(&mut self, level: i32) {
+        self.start_level = level.max(1);
+        self.update_level();
+        self.timer_reset();
+    }
 
-impl Point {
-    fn moved(&self, direction: Direction) -> Point {
-        use Direction::*;
-        match direction {
-            DOWN => Point { x: self.x, y: self.y + 1 },
-            LEFT => Point { x: self.x - 1, y: self.y },
-            RIGHT => Point { x: self.x + 1, y: self.y },
-        }
+    /// true once the score reached the maximum level
+    pub fn is_max_level(&self) -> bool {
+        self.max_level.is_some_and(|max| self.uncapped_level() >= max)
     }
-}
 
-#[derive(Copy, Clone, Debug)]
-struct Piece {
-    shape: PieceShape,
-    rotation: PieceRotation,
-    position: Point,
-}
+    /// the level the highest score gives, without the maximum
+    fn uncapped_level(&self) -> i32 {
+        self.start_level + self.score.max(self.peak_score) / POINTS_PER_LEVEL
+    }
 
-impl Piece {
-    fn random() -> Piece {
-        use PieceShape::*;
-        let random_shape = match rand::prelude::random::<u64>() % 7 {
-            0 => I,
-            1 => O,
-            2 => L,
-            3 => J,
-            4 => T,
-            5 => S,
-            6 => Z,
-            _ => unreachable!(),
-        };
-        Piece {
-            rotation: PieceRotation::NORMAL,
-            shape: random_shape,
-            position: PIECE_SPAWN_POSITION,
+    /// `level` stopped or wrapped around at the maximum
+    fn capped_level(&self, level: i32) -> i32 {
+        match (self.max_level, self.level_cap) {
+            (Some(max), LevelCap::Stop) => level.min(max),
+            (Some(max), LevelCap::Wrap) => (level - 1) % max + 1,
+            (None, _) => level,
         }
     }
 
-    fn with_rotation(&self, rotation: PieceRotation) -> Piece {
-        Piece {
-            shape: self.shape,
-            position: self.position,
-            rotation,
-        }
+    fn update_level(&mut self) {
+        self.peak_score = self.peak_score.max(self.score);
+        self.level = self.capped_level(self.uncapped_level());
     }
 
-    fn with_position(&self, position: Point) -> Piece {
-        Piece {
-            shape: self.shape,
-            rotation: self.rotation,
-            position,
-        }
+    /// take `points` off the score for each hole a piece makes when it
+    /// locks, the score doesn't go under zero and the level doesn't go
+    /// down. 0 turns the penalty off. Kept by `reset`
+    pub fn set_hole_penalty(&mut self, points: i32) {
+        self.hole_penalty = points.max(0);
     }
 
-    fn moved(&self, direction: Direction) -> Piece {
-        self.with_position(self.position.moved(direction))
+    /// let hanging blocks fall after a clear, in groups of blocks touching
+    /// each other, clearing the lines they complete as a chain. Kept by `reset`
+    pub fn set_cascade(&mut self, cascade: bool) {
+        self.cascade = cascade;
     }
 
-    fn rotated_right(&self) ->  Piece {
-        use PieceRotation::*;
-        self.with_rotation(match self.rotation {
-            NORMAL => RIGHT,
-            RIGHT => REVERSE,
-            REVERSE => LEFT,
-            LEFT => NORMAL,
-        })
+    pub fn is_cascade(&self) -> bool {
+        self.cascade
     }
 
-    fn get(&self, y: usize, x: usize) -> bool {
-        TETRIS[self.shape as usize][self.rotation as usize][y][x] != 0
+    /// the clears and falls of the last piece locked in cascade mode, in
+    /// order. Empty if it didn't clear lines
+    pub fn cascade_steps(&self) -> std::slice::Iter<'_, CascadeStep> {
+        self.cascade_steps.iter()
     }
 
-    fn check_limits(&self) -> bool {
-        self.position.x >= 0 
-            && self.position.y >= 0
-            && self.position.x < GAME_WIDTH as i32
-            && self.position.y < GAME_HEIGHT as i32
+    /// let the player start a zone once the meter is charged. Kept by `reset`
+    pub fn set_zone_enabled(&mut self, enabled: bool) {
+        self.zone_enabled = enabled;
     }
 
-    fn check_collision(&self, state: &GameState) -> bool {
-        if !self.check_limits() {
+    pub fn is_zone_enabled(&self) -> bool {
+        self.zone_enabled
+    }
+
+    /// lines cleared towards the next zone, the meter is full at
+    /// `ZONE_CHARGE_LINES`
+    pub fn zone_charge(&self) -> i32 {
+        self.zone_charge
+    }
+
+    pub fn is_zone_active(&self) -> bool {
+        self.zone_left.is_some()
+    }
+
+    /// time left in the running zone
+    pub fn zone_time_left(&self) -> Option<Duration> {
+        self.zone_left.map(|ms| Duration::from_millis(ms.max(0) as u64))
+    }
+
+    /// lines waiting at the bottom of the board to be cleared when the
+    /// zone ends
+    pub fn zone_lines(&self) -> usize {
+        self.zone_lines
+    }
+
+    /// stop the gravity for `ZONE_MS`, using the full meter. The lines
+    /// completed meanwhile pile up at the bottom of the board and are
+    /// all cleared together at the end. Returns false if the meter isn't
+    /// full or a zone is already running
+    pub fn start_zone(&mut self) -> bool {
+        if !self.zone_enabled || self.zone_charge < ZONE_CHARGE_LINES || self.is_zone_active() || self.is_lost() {
             return false;
         }
-        for y in 0..4 {
-            for x in 0..4 {
-                if self.get(y, x) && (
-                    self.position.x as usize + x >= GAME_WIDTH
-                    || self.position.y as usize + y >= GAME_HEIGHT
-                    || state.is_occupied(self.position.y as usize + y, self.position.x as usize + x)
-                ) {
-                    return false;
-                } 
-            }
-        }
+        self.zone_charge = 0;
+        self.zone_left = Some(ZONE_MS);
+        self.check_invariants();
         true
     }
-}
-
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum FieldCell {
-    Empty, 
-    Occupied(PieceShape),
-}
 
-pub struct GameState {
-    pub score: i32,
-    pub level: i32,
-    lost: bool,
-    delay: i32,
-    field: [[FieldCell; GAME_WIDTH]; GAME_HEIGHT],
-    current_piece: Piece,
-}
+    /// clear the lines piled up by the zone and score them, more lines
+    /// at once get a bigger bonus
+    fn end_zone(&mut self) {
+        self.zone_left = None;
+        let lines = std::mem::take(&mut self.zone_lines);
+        if lines == 0 {
+            return;
+        }
+        self.field.copy_within(..GAME_HEIGHT - lines, lines);
+        for row in self.field[..lines].iter_mut() {
+            *row = [FieldCell::Empty; GAME_WIDTH];
+        }
+        // the stack came down, so the falling piece can be inside it
+        // if it was under an overhang: bring it down too
+        let moved = self.current_piece.translated(0, lines as i32);
+        if !self.current_piece.check_collision(self) && moved.check_collision(self) {
+            self.current_piece = moved;
+        }
 
-impl GameState {
-    pub fn new() -> GameState {
-        let mut game = GameState {
-            field: [[FieldCell::Empty; GAME_WIDTH]; GAME_HEIGHT],
-            score: 0,
-            level: 1,
-            delay: 0,
-            current_piece: Piece::random(),
-            lost: false,
+        let n = lines as i32;
+        let breakdown = ScoreBreakdown {
+            lines,
+            base: 100 * n,
+            zone: 50 * n * n,
+            ..ScoreBreakdown::default()
         };
-        game.timer_reset();
-        game
+        self.events.push(GameEvent::Scored(breakdown));
+        self.lines += n;
+        self.score += breakdown.total();
+        self.update_level();
+        if self.is_cheese_cleared() || self.line_goal.is_some_and(|goal| self.lines >= goal) {
+            self.game_over = Some(GameOverReason::Completed);
+        } else {
+            self.check_top_out();
+        }
     }
 
-    pub fn get(&self, y: usize, x: usize) -> FieldCell {
-        let p = self.current_piece.position;
-        if p.y <= y as i32 && (y as i32) < p.y + 4 && p.x <= x as i32 && (x as i32) < p.x + 4 {
-            if self.current_piece.get(y - p.y as usize, x - p.x as usize) {
-                return FieldCell::Occupied(self.current_piece.shape);
-            }
+    /// move the rows completed during a zone down to the ones already
+    /// piled at the bottom, pushing the stack up instead of clearing them.
+    /// Returns the indices the completed rows had
+    fn pile_zone_lines(&mut self) -> Vec<usize> {
+        let bottom = GAME_HEIGHT - self.zone_lines;
+        let (full, rest): (Vec<usize>, Vec<usize>) = (0..bottom)
+            .partition(|&y| !self.field[y].contains(&FieldCell::Empty));
+        if !full.is_empty() {
+            let rows: Vec<_> = rest.iter().chain(&full).map(|&y| self.field[y]).collect();
+            self.field[..bottom].copy_from_slice(&rows);
+            self.zone_lines += full.len();
         }
-        self.field[y][x]
+        full
     }
 
-    pub fn move_left(&mut self) {
-        let moved = self.current_piece.moved(Direction::LEFT);
-        if moved.check_collision(self) {
-            self.current_piece = moved;
+    pub fn set_paused(&mut self, paused: bool) {
+        if paused && self.paused_at.is_none() {
+            self.paused_at = Some(self.clock.now());
+        } else if !paused {
+            if let Some(at) = self.paused_at.take() {
+                self.paused_for += self.clock.since(at);
+            }
         }
+        self.paused = paused;
     }
 
-    pub fn move_right(&mut self) {
-        let moved = self.current_piece.moved(Direction::RIGHT);
-        if moved.check_collision(self) {
-            self.current_piece = moved;
+    /// time played since the game started, without the time spent paused
+    pub fn elapsed(&self) -> Duration {
+        let played = match self.paused_at {
+            Some(at) => at.saturating_sub(self.started),
+            None => self.clock.since(self.started),
+        };
+        played.saturating_sub(self.paused_for)
+    }
+
+    /// start the timer again from zero, e.g. when the game is shown after
+    /// waiting on a start screen
+    pub fn reset_timer(&mut self) {
+        self.started = self.clock.now();
+        self.paused_at = if self.paused { Some(self.started) } else { None };
+        self.paused_for = Duration::from_secs(0);
+    }
+
+    /// read the time from `clock` from now on, restarting the timer
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        self.clock = clock;
+        self.reset_timer();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// the piece that couldn't enter the board and ended the game
+    pub fn topped_out_piece(&self) -> Option<Piece> {
+        self.topped_out
+    }
+
+    /// shape of the piece that comes after the falling one,
+    /// unless the preview is hidden
+    pub fn next_shape(&self) -> Option<PieceShape> {
+        self.next_piece().map(|piece| piece.shape)
+    }
+
+    /// the next piece, None while the preview is hidden
+    pub fn next_piece(&self) -> Option<Piece> {
+        if self.next_hidden {
+            None
+        } else {
+            Some(self.next_piece)
         }
     }
 
-        pub fn move_bottom(&mut self) {
-        while self.step_down() {}
-        self.piece_bottom()
+    /// draw the pieces from `source` from now on, the falling and next
+    /// pieces are drawn again so it's meant for the start of a game
+    pub fn set_source(&mut self, source: Box<dyn PieceSource>) {
+        self.source = source;
+        self.current_piece = self.new_piece();
+        self.next_piece = self.new_piece();
+        self.check_top_out();
     }
 
-    pub fn move_down(&mut self) {
-        if !self.step_down() {
-            self.piece_bottom()
+    /// shapes left in the bag of a bag source, which are coming after
+    /// the next piece. Empty for other sources
+    pub fn remaining_bag(&self) -> &[PieceShape] {
+        self.source.remaining_bag()
+    }
+
+    /// give each new piece a random color of the shape colors instead of
+    /// the one of its shape. The pieces already drawn keep their color
+    pub fn set_rainbow(&mut self, rainbow: bool) {
+        self.colors = if rainbow { Some(StdRng::from_entropy()) } else { None };
+    }
+
+    pub fn is_rainbow(&self) -> bool {
+        self.colors.is_some()
+    }
+
+    /// now and then make a block of a piece that locks an item, set off
+    /// when its line is cleared. Kept by `reset`
+    pub fn set_items(&mut self, items: bool) {
+        self.items = if items { Some(StdRng::from_entropy()) } else { None };
+    }
+
+    pub fn has_items(&self) -> bool {
+        self.items.is_some()
+    }
+
+    /// the next piece from the source, with a color of its own in
+    /// rainbow mode
+    fn new_piece(&mut self) -> Piece {
+        let piece = Piece::random(&self.shapes, self.rotation_system, self.source.as_mut());
+        Piece { color: self.new_color(), ..piece }
+    }
+
+    /// a random color for a new piece in rainbow mode, None otherwise
+    fn new_color(&mut self) -> Option<u8> {
+        self.colors.as_mut().map(|rng| rng.gen_range(0, PIECE_COLORS) as u8)
+    }
+
+    /// hide the next piece preview for an extra challenge
+    pub fn set_next_hidden(&mut self, hidden: bool) {
+        self.next_hidden = hidden;
+    }
+
+    pub fn is_next_hidden(&self) -> bool {
+        self.next_hidden
+    }
+
+    pub fn piece_set(&self) -> &PieceSet {
+        &self.shapes
+    }
+
+    /// the falling piece
+    pub fn current_piece(&self) -> Piece {
+        self.current_piece
+    }
+
+    /// the settled board as bit masks, for fast searches
+    pub fn bitboard(&self) -> Bitboard {
+        Bitboard::from_field(&self.field)
+    }
+
+    /// events that happened since the last call
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// call `hook` with the `PieceSpawned` event of each new piece as soon
+    /// as it comes in, before the entry delay and before the event can be
+    /// taken with `take_events`. None removes it
+    pub fn set_spawn_hook(&mut self, hook: Option<SpawnHook>) {
+        self.spawn_hook = hook;
+    }
+
+    /// number of lines cleared in this game
+    pub fn lines_cleared(&self) -> i32 {
+        self.lines
+    }
+
+    /// garbage lines the clears of this game send, by `attack_lines`
+    pub fn lines_sent(&self) -> usize {
+        self.lines_sent
+    }
+
+    /// garbage lines added to this game with `add_garbage`
+    pub fn lines_received(&self) -> usize {
+        self.lines_received
+    }
+
+    /// column of the falling piece
+    pub fn piece_column(&self) -> i32 {
+        self.current_piece.position.x
+    }
+
+    /// number of pieces locked on the field in this game
+    pub fn pieces_placed(&self) -> i32 {
+        self.pieces
+    }
+
+    /// average number of pieces locked per second of play
+    pub fn pieces_per_second(&self) -> f32 {
+        let elapsed = self.elapsed();
+        if elapsed.as_secs_f32() > 0.0 {
+            self.pieces as f32 / elapsed.as_secs_f32()
+        } else {
+            0.0
         }
     }
 
-    pub fn rotate(&mut self) {
-        let rotated = self.current_piece.rotated_right();
-        if rotated.check_collision(self) {
-            self.current_piece = rotated;
+    /// fill the bottom `rows` rows with garbage for the dig practice drill,
+    /// leaving one hole per row in a column that wanders left and right
+    pub fn fill_cheese(&mut self, rows: usize, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let rows = rows.min(GAME_HEIGHT - 4);
+        let mut gap = rng.gen_range(0, GAME_WIDTH as i32);
+        for y in (GAME_HEIGHT - rows..GAME_HEIGHT).rev() {
+            for x in 0..GAME_WIDTH {
+                self.field[y][x] = if x as i32 == gap { FieldCell::Empty } else { FieldCell::Garbage };
+            }
+            gap = (gap + rng.gen_range(-1, 2)).max(0).min(GAME_WIDTH as i32 - 1);
         }
+        self.cheese = rows > 0;
     }
 
-    pub fn clock_tick(&mut self) {
-        self.delay -= 50;
-        if self.delay == 0 {
-            self.timer_reset();
-            self.move_down()
+    /// fill the bottom `rows` rows with random garbage for a messy start,
+    /// each cell taken with probability `density`. Every row keeps at
+    /// least one hole, and the top rows stay empty so the falling piece
+    /// doesn't collide. Unlike the cheese drill the game goes on when the
+    /// garbage is cleared
+    pub fn seed_initial_garbage(&mut self, rows: usize, density: f32, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let rows = rows.min(GAME_HEIGHT - 4);
+        for y in GAME_HEIGHT - rows..GAME_HEIGHT {
+            for x in 0..GAME_WIDTH {
+                self.field[y][x] = if rng.gen::<f32>() < density { FieldCell::Garbage } else { FieldCell::Empty };
+            }
+            if !self.field[y].contains(&FieldCell::Empty) {
+                self.field[y][rng.gen_range(0, GAME_WIDTH)] = FieldCell::Empty;
+            }
         }
     }
 
-    pub fn is_lost(&self) -> bool {
-        self.lost
+    /// push the stack up by `rows` rows of garbage with a hole in the same
+    /// column, for versus and survival modes, and return the column. The
+    /// holes come from the garbage seed, so the same seeds give the same
+    /// holes whatever the pieces do. The falling piece is pushed up with
+    /// the stack if it has to, and blocks pushed over the top end the game
+    pub fn add_garbage(&mut self, rows: usize) -> usize {
+        let gap = self.garbage.gen_range(0, GAME_WIDTH);
+        let rows = rows.min(GAME_HEIGHT);
+        self.lines_received += rows;
+        let overflow = (0..rows).any(|y| (0..GAME_WIDTH).any(|x| self.is_occupied(y, x)));
+        self.field.rotate_left(rows);
+        for row in self.field[GAME_HEIGHT - rows..].iter_mut() {
+            *row = [FieldCell::Garbage; GAME_WIDTH];
+            row[gap] = FieldCell::Empty;
+        }
+        let pushed = (0..=rows as i32)
+            .map(|up| self.current_piece.translated(0, -up))
+            .find(|piece| piece.check_collision(self));
+        match pushed {
+            Some(piece) if !overflow => self.current_piece = piece,
+            _ => {
+                self.game_over = Some(GameOverReason::BlockOut);
+                self.topped_out = Some(self.current_piece);
+            },
+        }
+        gap
+    }
+
+    /// number of rows that still contain garbage
+    pub fn cheese_left(&self) -> usize {
+        self.field.iter()
+            .filter(|row| row.contains(&FieldCell::Garbage))
+            .count()
+    }
+
+    /// whether this is a cheese drill and all the garbage was cleared
+    pub fn is_cheese_cleared(&self) -> bool {
+        self.cheese && self.cheese_left() == 0
+    }
+
+    /// points still needed to reach the next level, counted as if there
+    /// was no maximum level
+    pub fn points_to_next_level(&self) -> i32 {
+        (self.score.max(self.peak_score) / POINTS_PER_LEVEL + 1) * POINTS_PER_LEVEL - self.score
+    }
+
+    /// whether (y, x) is outside the field or occupied
+    fn is_blocked(&self, y: i32, x: i32) -> bool {
+        y < 0 || x < 0 || y >= GAME_HEIGHT as i32 || x >= GAME_WIDTH as i32
+            || self.is_occupied(y as usize, x as usize)
     }
 
     fn is_occupied(&self, y: usize, x: usize) -> bool {
         match self.field[y][x] {
             FieldCell::Empty => false,
-            FieldCell::Occupied(_) => true,
+            FieldCell::Occupied(_) | FieldCell::Painted(..) | FieldCell::Garbage | FieldCell::Item(..) => true,
+        }
+    }
+
+    /// the game as it would be after `piece` locked where it is, with the
+    /// lines it completes cleared and scored, for an AI to look ahead. The
+    /// next piece doesn't come in, the game doesn't end and `self` stays
+    /// as it is. None if `piece` is off the board or over the stack
+    pub fn simulate_lock(&self, piece: Piece) -> Option<GameState> {
+        if !piece.check_collision(self) {
+            return None;
+        }
+        let mut state = self.clone();
+        state.current_piece = piece;
+        state.last_spin = Spin::None;
+        state.place_piece();
+        state.pieces += 1;
+        state.clear_lines();
+        Some(state)
+    }
+
+    /// write the falling piece on the settled board, with its item in
+    /// items mode, and count the holes it makes
+    fn place_piece(&mut self) {
+        let locked: Vec<Point> = self.current_piece.absolute_cells(&self.shapes).collect();
+        let holes = self.count_holes() as i32;
+        for cell in &locked {
+            self.field[cell.y as usize][cell.x as usize] = self.current_piece.cell();
+        }
+        if let Some(rng) = self.items.as_mut() {
+            if rng.gen_range(0, ITEM_CHANCE) == 0 {
+                let cell = locked[rng.gen_range(0, locked.len())];
+                let item = Item::ALL[rng.gen_range(0, Item::ALL.len())];
+                self.field[cell.y as usize][cell.x as usize] = FieldCell::Item(self.current_piece.shape, item);
+            }
+        }
+        // counted before the clear, the rows cleared could take the holes
+        // away. The lines piled up in a zone aren't scored until it ends
+        if !self.is_zone_active() {
+            self.holes_made = (self.count_holes() as i32 - holes).max(0);
+        }
+    }
+
+    /// clear the lines completed by the piece just placed, or pile them
+    /// during a zone. Returns the rows removed
+    fn clear_lines(&mut self) -> Vec<usize> {
+        self.cascade_steps.clear();
+        let cleared = if self.is_zone_active() {
+            self.pile_zone_lines()
+        } else {
+            self.eliminate_lines()
+        };
+        if self.cascade && !cleared.is_empty() && !self.is_zone_active() {
+            self.run_cascade(cleared.clone());
         }
+        cleared
     }
 
     fn piece_bottom(&mut self) {
-        for y in 0..4 {
-            for x in 0..4 {
-                if self.current_piece.get(y, x) {
-                    let screen_y = self.current_piece.position.y as usize + y;
-                    let screen_x = self.current_piece.position.x as usize + x;
-                    self.field[screen_y][screen_x] = FieldCell::Occupied(self.current_piece.shape);
-                }
+        // a board filled under the falling piece, by `set_field`, garbage
+        // or items, leaves it nowhere to lock: a top out like at the spawn
+        // instead of locking it over the stack
+        if !self.is_lost() {
+            self.check_top_out();
+        }
+        if self.is_lost() {
+            return;
+        }
+        let locked: Vec<Point> = self.current_piece.absolute_cells(&self.shapes).collect();
+        self.place_piece();
+        let min_inputs = self.finesse.min_inputs(self.current_piece.shape, &locked);
+        if min_inputs.is_some_and(|min| self.inputs > min) {
+            self.finesse_faults += 1;
+        }
+        let locked_out = locked.iter().all(|cell| (cell.y as usize) < SPAWN_ROWS);
+        self.events.push(GameEvent::PieceLocked { cells: locked, field: Box::new(self.field) });
+        self.pieces += 1;
+        let before = self.field;
+        let cleared = self.clear_lines();
+        if self.is_cheese_cleared() || self.line_goal.is_some_and(|goal| self.lines >= goal) {
+            self.game_over = Some(GameOverReason::Completed);
+        } else if locked_out && cleared.is_empty() {
+            self.game_over = Some(GameOverReason::LockOut);
+            self.topped_out = Some(self.current_piece);
+        } else {
+            self.add_new_piece();
+            let animated = self.clear_ms + self.collapse_ms > 0 && !self.is_zone_active();
+            if animated && !cleared.is_empty() && !self.is_lost() {
+                self.entry_delay += self.clear_ms + self.collapse_ms;
+                self.clear_animation = Some(ClearAnimation {
+                    phase: ClearPhase::Flash,
+                    rows: cleared,
+                    field: before,
+                });
+                self.update_clear_animation();
             }
         }
+    }
+
+    /// move the line clear animation on with the entry delay, it takes
+    /// the part of the delay that comes before the ARE
+    fn update_clear_animation(&mut self) {
+        let left = self.entry_delay - self.are;
+        match self.clear_animation.as_mut() {
+            Some(_) if left <= 0 => self.clear_animation = None,
+            Some(animation) if left <= self.collapse_ms => animation.phase = ClearPhase::Collapse,
+            _ => (),
+        }
+    }
+
+    /// panic if the game broke a rule the rest of the code relies on: the
+    /// falling piece inside the board and off the stack until the game is
+    /// lost, the level between 1 and the maximum and the one the score
+    /// gives, and no full row left but the lines piled up by a zone. A board
+    /// given to `set_field` or edited with `set_cell` is only held to the
+    /// last two once the falling piece locks. Only in debug builds, the
+    /// moves, rotations, holds and ticks call it after changing the game
+    #[cfg(debug_assertions)]
+    pub fn assert_invariants(&self) {
+        assert!(self.score >= 0, "negative score {}", self.score);
+        assert!(self.lines >= 0, "negative lines {}", self.lines);
+        assert!(self.level >= 1, "level {} under 1", self.level);
+        if let Some(max) = self.max_level {
+            assert!(self.level <= max, "level {} over the maximum {}", self.level, max);
+        }
+        let level = self.capped_level(self.uncapped_level());
+        assert!(self.level == level, "level {} where the score gives {}", self.level, level);
+        if self.is_lost() || self.field_set {
+            return;
+        }
+        for cell in self.current_piece.absolute_cells(&self.shapes) {
+            let inside = (0..GAME_HEIGHT as i32).contains(&cell.y) && (0..GAME_WIDTH as i32).contains(&cell.x);
+            assert!(inside, "falling piece {:?} out of the board", self.current_piece);
+            assert!(!self.is_occupied(cell.y as usize, cell.x as usize), "falling piece {:?} over the stack", self.current_piece);
+        }
+        for (y, row) in self.field[..GAME_HEIGHT - self.zone_lines].iter().enumerate() {
+            assert!(row.contains(&FieldCell::Empty), "row {} full and not cleared", y);
+        }
+    }
+
+    /// `assert_invariants` in debug builds, nothing in release ones
+    fn check_invariants(&self) {
+        #[cfg(debug_assertions)]
+        self.assert_invariants();
+    }
 
-        self.eliminate_lines();
-        self.add_new_piece();
+    /// the game is lost when the new falling piece overlaps the stack
+    fn check_top_out(&mut self) {
+        if !self.current_piece.check_collision(self) {
+            self.game_over = Some(GameOverReason::BlockOut);
+            self.topped_out = Some(self.current_piece);
+        }
     }
 
     fn timer_reset(&mut self) {
-        self.delay = 800 * 0.9f32.powi(self.level).round() as i32;
+        self.delay = if self.is_max_level() {
+            MIN_GRAVITY_DELAY_MS
+        } else {
+            gravity_delay_ms(self.level).max(MIN_GRAVITY_DELAY_MS)
+        };
     }
     
     fn step_down(&mut self) -> bool {
         let moved = self.current_piece.moved(Direction::DOWN);
         if moved.check_collision(self) {
             self.current_piece = moved;
+            self.last_spin = Spin::None;
+            self.lock_delay = LOCK_DELAY_MS;
             true
         } else {
             false
@@ -251,80 +2500,313 @@ impl GameState {
     }
 
     fn add_new_piece(&mut self) {
-        self.current_piece = Piece::random();
-        if !self.current_piece.check_collision(self) {
-            self.lost = true;
+        self.current_piece = self.next_piece;
+        self.next_piece = self.new_piece();
+        self.hold_used = false;
+        self.inputs = 0;
+        self.last_spin = Spin::None;
+        self.lock_delay = LOCK_DELAY_MS;
+        self.lock_resets = 0;
+        self.entry_delay = self.are;
+        self.field_set = false;
+        self.check_top_out();
+        if !self.is_lost() {
+            self.remember_for_rewind();
         }
+        self.piece_spawned();
     }
 
-    fn eliminate_lines(&mut self) {
-        let mut eliminated: usize = 0;
-        'nextline: for y in 0..GAME_HEIGHT {
-            for x in 0..GAME_WIDTH {
-                if self.field[y][x] == FieldCell::Empty {
-                    continue 'nextline;
+    /// tell the hook and the event stream that a new piece came in: after
+    /// a lock, from the next box on a hold with nothing held, and as the
+    /// first piece after a reset
+    fn piece_spawned(&mut self) {
+        let event = GameEvent::PieceSpawned { shape: self.current_piece.shape, topped_out: self.is_lost() };
+        if let Some(hook) = &self.spawn_hook {
+            hook(&event);
+        }
+        self.events.push(event);
+    }
+
+    /// remove the complete lines from the settled board and score them,
+    /// this is done every time a piece locks. The items in them go off
+    /// first. Returns the indices of the removed rows, from top to bottom,
+    /// with the bottom row taken by an item
+    fn eliminate_lines(&mut self) -> Vec<usize> {
+        let full: Vec<bool> = self.field.iter().map(|row| !row.contains(&FieldCell::Empty)).collect();
+        let lines = full.iter().filter(|&&full| full).count();
+        // all the items are found before any goes off, a bomb doesn't
+        // take the items next to it away
+        let items: Vec<(usize, usize, Item)> = (0..GAME_HEIGHT)
+            .filter(|&y| full[y])
+            .flat_map(|y| (0..GAME_WIDTH).map(move |x| (y, x)))
+            .filter_map(|(y, x)| match self.field[y][x] {
+                FieldCell::Item(_, item) => Some((y, x, item)),
+                _ => None,
+            })
+            .collect();
+        let mut clear_bottom = false;
+        for &(y, x, item) in &items {
+            match item {
+                Item::ClearBottom => clear_bottom = true,
+                Item::ClearColumn => {
+                    for row in self.field.iter_mut() {
+                        row[x] = FieldCell::Empty;
+                    }
+                },
+                Item::Bomb => {
+                    for row in &mut self.field[y.saturating_sub(1)..(y + 2).min(GAME_HEIGHT)] {
+                        for cell in &mut row[x.saturating_sub(1)..(x + 2).min(GAME_WIDTH)] {
+                            *cell = FieldCell::Empty;
+                        }
+                    }
+                },
+            }
+        }
+
+        // move the surviving rows down in a single bottom up pass, so
+        // full rows far apart collapse at once like adjacent ones. The
+        // rows left over at the top become empty
+        let removed = |y: usize| full[y] || (clear_bottom && y == GAME_HEIGHT - 1);
+        let mut cleared = Vec::new();
+        let mut target = GAME_HEIGHT;
+        for y in (0..GAME_HEIGHT).rev() {
+            if !removed(y) {
+                target -= 1;
+                if target != y {
+                    self.field[target] = self.field[y];
                 }
+            } else {
+                cleared.push(y);
+            }
+        }
+        for row in self.field[..target].iter_mut() {
+            *row = [FieldCell::Empty; GAME_WIDTH];
+        }
+        cleared.reverse();
+
+        let mut breakdown = self.score_breakdown(lines);
+        breakdown.holes = -self.hole_penalty * std::mem::take(&mut self.holes_made);
+        if lines > 0 || self.last_spin != Spin::None || breakdown.holes != 0 {
+            self.events.push(GameEvent::Scored(breakdown));
+        }
+        self.lines += lines as i32;
+        if self.zone_enabled {
+            self.zone_charge = (self.zone_charge + lines as i32).min(ZONE_CHARGE_LINES);
+        }
+        self.score = (self.score + breakdown.total()).max(0);
+        self.update_level();
+
+        cleared
+    }
+
+    /// let the blocks fall after the clear of `rows` and clear the lines
+    /// they complete, until nothing moves
+    fn run_cascade(&mut self, rows: Vec<usize>) {
+        self.cascade_steps.push(CascadeStep::Clear { rows, field: self.field });
+        // only the clear made by the piece is a T-spin
+        self.last_spin = Spin::None;
+        while self.drop_hanging_blocks() {
+            self.cascade_steps.push(CascadeStep::Fall { field: self.field });
+            // a fall that completes nothing isn't scored, or it would end
+            // the combo the piece just made
+            if !self.has_full_row() {
+                break;
             }
+            let rows = self.eliminate_lines();
+            self.cascade_steps.push(CascadeStep::Clear { rows, field: self.field });
+        }
+    }
 
-            eliminated += 1;
+    fn has_full_row(&self) -> bool {
+        self.field.iter().any(|row| !row.contains(&FieldCell::Empty))
+    }
+
+    /// drop every group of touching blocks as far as it goes, the lowest
+    /// ones first so the ones above can land on them. Returns false if
+    /// nothing moved
+    fn drop_hanging_blocks(&mut self) -> bool {
+        let mut moved = false;
+        loop {
+            let mut groups = self.block_groups();
+            groups.sort_by_key(|group| std::cmp::Reverse(group.iter().map(|&(y, _)| y).max()));
+            let mut moved_now = false;
+            for group in groups {
+                let cells: Vec<FieldCell> = group.iter().map(|&(y, x)| self.field[y][x]).collect();
+                for &(y, x) in &group {
+                    self.field[y][x] = FieldCell::Empty;
+                }
+                let fits = |field: &Field, distance: usize| group.iter().all(|&(y, x)| {
+                    y + distance < GAME_HEIGHT && field[y + distance][x] == FieldCell::Empty
+                });
+                let mut distance = 0;
+                while fits(&self.field, distance + 1) {
+                    distance += 1;
+                }
+                for (&(y, x), &cell) in group.iter().zip(&cells) {
+                    self.field[y + distance][x] = cell;
+                }
+                moved_now |= distance > 0;
+            }
+            if !moved_now {
+                return moved;
+            }
+            moved = true;
+        }
+    }
 
-            // shift all lines down
-            for h in (3..y+1).rev() {
-                for x in 0..GAME_WIDTH {
-                    self.field[h][x] = self.field[h - 1][x];
+    /// the occupied cells of the board split in groups touching each other
+    /// by a side, as (y, x)
+    fn block_groups(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut seen = [[false; GAME_WIDTH]; GAME_HEIGHT];
+        let mut groups = Vec::new();
+        for y in 0..GAME_HEIGHT {
+            for x in 0..GAME_WIDTH {
+                if seen[y][x] || !self.is_occupied(y, x) {
+                    continue;
                 }
+                seen[y][x] = true;
+                let mut group = vec![(y, x)];
+                let mut next = 0;
+                while next < group.len() {
+                    let (y, x) = group[next];
+                    next += 1;
+                    let neighbors = [(y.wrapping_sub(1), x), (y + 1, x), (y, x.wrapping_sub(1)), (y, x + 1)];
+                    for &(ny, nx) in &neighbors {
+                        if ny < GAME_HEIGHT && nx < GAME_WIDTH && !seen[ny][nx] && self.is_occupied(ny, nx) {
+                            seen[ny][nx] = true;
+                            group.push((ny, nx));
+                        }
+                    }
+                }
+                groups.push(group);
             }
         }
+        groups
+    }
 
+    /// points for clearing `lines` with the piece that just locked,
+    /// updating the combo and back to back streaks and the lines sent
+    fn score_breakdown(&mut self, lines: usize) -> ScoreBreakdown {
         let points_per_line = [1, 40, 100, 300, 1200];
+        let perfect_clear = [0, 800, 1200, 1800, 2000];
 
-        self.score += points_per_line[eliminated];
-        self.level = 1 + self.score / 700;
+        let n = lines.min(4);
+        let mut breakdown = ScoreBreakdown {
+            lines,
+            base: points_per_line[n],
+            ..ScoreBreakdown::default()
+        };
+        breakdown.tspin = match self.last_spin {
+            Spin::None => 0,
+            Spin::Mini => 100 * (n as i32 + 1),
+            Spin::Full => 400 * (n as i32 + 1),
+        };
+        if lines == 0 {
+            self.combo = 0;
+            return breakdown;
+        }
+        breakdown.combo = 50 * self.combo;
+        let clear_type = ClearType::new(lines, self.last_spin);
+        self.lines_sent += attack_lines(clear_type, self.combo as usize, self.back_to_back);
+        self.combo += 1;
+        let difficult = clear_type.is_difficult();
+        if difficult && self.back_to_back {
+            breakdown.b2b = (breakdown.base + breakdown.tspin) / 2;
+        }
+        self.back_to_back = difficult;
+        if self.field.iter().flatten().all(|&cell| cell == FieldCell::Empty) {
+            breakdown.perfect = perfect_clear[n];
+        }
+        breakdown
+    }
+}
 
+/// the board as text with the falling piece on it, a letter for the
+/// blocks of each shape, `#` for garbage and `.` for the empty cells
+impl std::fmt::Display for GameState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for y in 0..GAME_HEIGHT {
+            let row: String = (0..GAME_WIDTH).map(|x| match self.get(y, x) {
+                FieldCell::Empty => '.',
+                FieldCell::Occupied(shape) | FieldCell::Painted(shape, _) | FieldCell::Item(shape, _) => shape.letter(),
+                FieldCell::Garbage => '#',
+            }).collect();
+            writeln!(f, "|{}|", row)?;
+        }
+        write!(f, "+{}+", "-".repeat(GAME_WIDTH))
     }
 }
 
+// rotations of the tetrominoes as in the Super Rotation System,
+// in the order normal, left, reverse, right
 const TETRIS: [[[[u8; 4]; 4]; 4]; 7] = [
 	[ /* I */
-		[[1,0,0,0],[1,0,0,0],[1,0,0,0],[1,0,0,0]],
-		[[1,1,1,1],[0,0,0,0],[0,0,0,0],[0,0,0,0]],
-		[[1,0,0,0],[1,0,0,0],[1,0,0,0],[1,0,0,0]],
-		[[1,1,1,1],[0,0,0,0],[0,0,0,0],[0,0,0,0]],
+		[[0,0,0,0],[1,1,1,1],[0,0,0,0],[0,0,0,0]],
+		[[0,1,0,0],[0,1,0,0],[0,1,0,0],[0,1,0,0]],
+		[[0,0,0,0],[0,0,0,0],[1,1,1,1],[0,0,0,0]],
+		[[0,0,1,0],[0,0,1,0],[0,0,1,0],[0,0,1,0]],
 	], 
 	[ /* O */
-		[[1,1,0,0],[1,1,0,0],[0,0,0,0],[0,0,0,0]],
-		[[1,1,0,0],[1,1,0,0],[0,0,0,0],[0,0,0,0]],
-		[[1,1,0,0],[1,1,0,0],[0,0,0,0],[0,0,0,0]],
-		[[1,1,0,0],[1,1,0,0],[0,0,0,0],[0,0,0,0]],
+		[[0,1,1,0],[0,1,1,0],[0,0,0,0],[0,0,0,0]],
+		[[0,1,1,0],[0,1,1,0],[0,0,0,0],[0,0,0,0]],
+		[[0,1,1,0],[0,1,1,0],[0,0,0,0],[0,0,0,0]],
+		[[0,1,1,0],[0,1,1,0],[0,0,0,0],[0,0,0,0]],
 	], 
 	[ /* L */
-		[[1,1,0,0],[1,0,0,0],[1,0,0,0],[0,0,0,0]],
-		[[1,1,1,0],[0,0,1,0],[0,0,0,0],[0,0,0,0]],
-		[[0,1,0,0],[0,1,0,0],[1,1,0,0],[0,0,0,0]],
-		[[1,0,0,0],[1,1,1,0],[0,0,0,0],[0,0,0,0]],
+		[[0,0,1,0],[1,1,1,0],[0,0,0,0],[0,0,0,0]],
+		[[1,1,0,0],[0,1,0,0],[0,1,0,0],[0,0,0,0]],
+		[[0,0,0,0],[1,1,1,0],[1,0,0,0],[0,0,0,0]],
+		[[0,1,0,0],[0,1,0,0],[0,1,1,0],[0,0,0,0]],
 	], 
 	[ /* J */
-		[[1,1,0,0],[0,1,0,0],[0,1,0,0],[0,0,0,0]],
-		[[0,0,1,0],[1,1,1,0],[0,0,0,0],[0,0,0,0]],
-		[[1,0,0,0],[1,0,0,0],[1,1,0,0],[0,0,0,0]],
-		[[1,1,1,0],[1,0,0,0],[0,0,0,0],[0,0,0,0]],
+		[[1,0,0,0],[1,1,1,0],[0,0,0,0],[0,0,0,0]],
+		[[0,1,0,0],[0,1,0,0],[1,1,0,0],[0,0,0,0]],
+		[[0,0,0,0],[1,1,1,0],[0,0,1,0],[0,0,0,0]],
+		[[0,1,1,0],[0,1,0,0],[0,1,0,0],[0,0,0,0]],
 	], 
 	[ /* T */
 		[[0,1,0,0],[1,1,1,0],[0,0,0,0],[0,0,0,0]],
-		[[1,0,0,0],[1,1,0,0],[1,0,0,0],[0,0,0,0]],
-		[[1,1,1,0],[0,1,0,0],[0,0,0,0],[0,0,0,0]],
 		[[0,1,0,0],[1,1,0,0],[0,1,0,0],[0,0,0,0]],
+		[[0,0,0,0],[1,1,1,0],[0,1,0,0],[0,0,0,0]],
+		[[0,1,0,0],[0,1,1,0],[0,1,0,0],[0,0,0,0]],
 	], 
 	[ /* S */
-		[[0,1,0,0],[1,1,0,0],[1,0,0,0],[0,0,0,0]],
-		[[1,1,0,0],[0,1,1,0],[0,0,0,0],[0,0,0,0]],
-		[[0,1,0,0],[1,1,0,0],[1,0,0,0],[0,0,0,0]],
-		[[1,1,0,0],[0,1,1,0],[0,0,0,0],[0,0,0,0]],
-	], 
-	[ /* Z */
-		[[1,0,0,0],[1,1,0,0],[0,1,0,0],[0,0,0,0]],
 		[[0,1,1,0],[1,1,0,0],[0,0,0,0],[0,0,0,0]],
 		[[1,0,0,0],[1,1,0,0],[0,1,0,0],[0,0,0,0]],
-		[[0,1,1,0],[1,1,0,0],[0,0,0,0],[0,0,0,0]],
+		[[0,0,0,0],[0,1,1,0],[1,1,0,0],[0,0,0,0]],
+		[[0,1,0,0],[0,1,1,0],[0,0,1,0],[0,0,0,0]],
+	], 
+	[ /* Z */
+		[[1,1,0,0],[0,1,1,0],[0,0,0,0],[0,0,0,0]],
+		[[0,1,0,0],[1,1,0,0],[1,0,0,0],[0,0,0,0]],
+		[[0,0,0,0],[1,1,0,0],[0,1,1,0],[0,0,0,0]],
+		[[0,0,1,0],[0,1,1,0],[0,1,0,0],[0,0,0,0]],
 	],
 ];
+
+// wall kicks of the Super Rotation System as (x, y) offsets with y going
+// down, tried in order until the rotated piece fits
+const JLSTZ_KICKS: [[(i32, i32); 5]; 8] = [
+	[(0, 0), (-1, 0), (-1,-1), (0, 2), (-1, 2)], // normal -> right
+	[(0, 0), ( 1, 0), ( 1, 1), (0,-2), ( 1,-2)], // right -> normal
+	[(0, 0), ( 1, 0), ( 1, 1), (0,-2), ( 1,-2)], // right -> reverse
+	[(0, 0), (-1, 0), (-1,-1), (0, 2), (-1, 2)], // reverse -> right
+	[(0, 0), ( 1, 0), ( 1,-1), (0, 2), ( 1, 2)], // reverse -> left
+	[(0, 0), (-1, 0), (-1, 1), (0,-2), (-1,-2)], // left -> reverse
+	[(0, 0), (-1, 0), (-1, 1), (0,-2), (-1,-2)], // left -> normal
+	[(0, 0), ( 1, 0), ( 1,-1), (0, 2), ( 1, 2)], // normal -> left
+];
+
+// the I piece has its own table. Its last kicks move it up by two rows for
+// the transitions that would otherwise push it into the floor, so a flat I
+// lying on the floor can still stand up
+const I_KICKS: [[(i32, i32); 5]; 8] = [
+	[(0, 0), (-2, 0), ( 1, 0), (-2, 1), ( 1,-2)], // normal -> right
+	[(0, 0), ( 2, 0), (-1, 0), ( 2,-1), (-1, 2)], // right -> normal
+	[(0, 0), (-1, 0), ( 2, 0), (-1,-2), ( 2, 1)], // right -> reverse
+	[(0, 0), ( 1, 0), (-2, 0), ( 1, 2), (-2,-1)], // reverse -> right
+	[(0, 0), ( 2, 0), (-1, 0), ( 2,-1), (-1, 2)], // reverse -> left
+	[(0, 0), (-2, 0), ( 1, 0), (-2, 1), ( 1,-2)], // left -> reverse
+	[(0, 0), ( 1, 0), (-2, 0), ( 1, 2), (-2,-1)], // left -> normal
+	[(0, 0), (-1, 0), ( 2, 0), (-1,-2), ( 2, 1)], // normal -> left
+];
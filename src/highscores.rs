@@ -0,0 +1,127 @@
+// High score table, saved as JSON in ~/.local/share/tetris-rust/highscores.json.
+// Older versions only kept the best score as a plain number in the
+// `highscore` file next to it, which is converted the first time.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+const MAX_ENTRIES: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: i32,
+    pub lines: i32,
+    pub mode: String,
+    /// seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl HighScore {
+    pub fn new(name: &str, score: i32, lines: i32, mode: &str) -> HighScore {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        HighScore {
+            name: name.to_string(),
+            score,
+            lines,
+            mode: mode.to_string(),
+            timestamp,
+        }
+    }
+
+    /// day of the score as YYYY-MM-DD, in UTC
+    pub fn date(&self) -> String {
+        // days to civil date, from Howard Hinnant's date algorithms
+        let days = (self.timestamp / 86400) as i64 + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// the best scores, highest first
+#[derive(Clone, Debug, Default)]
+pub struct HighScores {
+    entries: Vec<HighScore>,
+    path: Option<PathBuf>,
+}
+
+impl HighScores {
+    /// load the table from `dir`, converting the old plain number file if
+    /// there is no table yet. A missing file gives an empty table, one that
+    /// can't be read or parsed is an error, to not save over the scores in it
+    pub fn load(dir: Option<PathBuf>) -> Result<HighScores, String> {
+        let dir = match dir {
+            Some(dir) => dir,
+            None => return Ok(HighScores::default()),
+        };
+        let path = dir.join("highscores.json");
+        let mut scores = HighScores { entries: Vec::new(), path: Some(path.clone()) };
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                scores.entries = serde_json::from_str(&content).map_err(|err| format!("{}: {}", path.display(), err))?;
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => scores.migrate(&dir.join("highscore")),
+            Err(err) => return Err(format!("{}: {}", path.display(), err)),
+        }
+        scores.entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        Ok(scores)
+    }
+
+    fn migrate(&mut self, legacy: &Path) {
+        let score = match std::fs::read_to_string(legacy).ok().and_then(|content| content.trim().parse().ok()) {
+            Some(score) => score,
+            None => return,
+        };
+        self.entries.push(HighScore {
+            name: "?".to_string(),
+            score,
+            lines: 0,
+            mode: "marathon".to_string(),
+            timestamp: 0,
+        });
+        if self.save().is_ok() {
+            let _ = std::fs::remove_file(legacy);
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let json = serde_json::to_string_pretty(&self.entries).map_err(|err| err.to_string())?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+        }
+        std::fs::write(path, json).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    /// add the score if it makes the table and save it, saving is best
+    /// effort so a read-only home doesn't stop the game
+    pub fn add(&mut self, entry: HighScore) {
+        let position = self.entries.iter().position(|e| entry.score > e.score).unwrap_or(self.entries.len());
+        if position >= MAX_ENTRIES {
+            return;
+        }
+        self.entries.insert(position, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        let _ = self.save();
+    }
+
+    pub fn best(&self) -> i32 {
+        self.entries.first().map_or(0, |entry| entry.score)
+    }
+
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+}
@@ -0,0 +1,16 @@
+//! Positions of text and windows on the screen, kept inside it when the
+//! terminal is too small for them.
+
+/// first column of something `width` columns wide centered in `available`
+/// columns. Something wider than the space starts at its left edge
+pub fn center_x(available: i32, width: i32) -> i32 {
+    ((available - width) / 2).max(0)
+}
+
+/// the start of `text` that fits in `available` columns, one per character
+pub fn clip(text: &str, available: i32) -> &str {
+    match text.char_indices().nth(available.max(0) as usize) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
@@ -0,0 +1,16 @@
+//! Game logic of tetris-rust, independent of the terminal front-end.
+
+pub mod ai;
+pub mod bitboard;
+pub mod clock;
+pub mod config;
+pub mod finesse;
+pub mod game;
+pub mod highscores;
+pub mod layout;
+pub mod replay;
+pub mod source;
+pub mod theme;
+pub mod viewport;
+#[cfg(feature = "wasm")]
+pub mod wasm;
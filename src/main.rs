@@ -1,11 +1,125 @@
 // TODO:
-//  - next piece preview
-//  - high score
 //  - use an async mechanism to advance pieces
 
-mod game;
+mod session;
 mod ui; 
 
+use config::Config;
+use tetris_rust::{ai, clock, config, game, highscores, layout, replay, source, theme, viewport};
+
+const USAGE: &str = "usage: tetris-rust [--cheese ROWS] [--sprint LINES] [--ultra SECONDS] [--zone] [--snapshot NAME] [--debug] [--safe] [--headless] [--steps N] [--seed N] [--export-on-loss PATH] [--export-replay PATH] [--watch-replay TEXT]";
+
+// gravity steps run by --headless when --steps isn't given
+const DEFAULT_HEADLESS_STEPS: usize = 1000;
+
+fn parse_args(config: &mut Config) -> Result<(), String> {
+    // a kind of game given here replaces the `mode` of the configuration file
+    if std::env::args().any(|arg| ["--cheese", "--sprint", "--ultra"].contains(&arg.as_str())) {
+        config.set_mode(config::Mode::Marathon);
+    }
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cheese" => {
+                let rows = args.next().ok_or("--cheese requires the number of rows")?;
+                let rows = rows.parse().map_err(|_| format!("invalid number of rows: {}", rows))?;
+                config.cheese_rows = Some(rows);
+            },
+            "--sprint" => {
+                let lines = args.next().ok_or("--sprint requires the number of lines")?;
+                let lines = lines.parse().map_err(|_| format!("invalid number of lines: {}", lines))?;
+                config.sprint_lines = Some(lines);
+            },
+            "--ultra" => {
+                let seconds = args.next().ok_or("--ultra requires the number of seconds")?;
+                let seconds = seconds.parse().map_err(|_| format!("invalid number of seconds: {}", seconds))?;
+                config.ultra_seconds = Some(seconds);
+            },
+            "--zone" => config.zone = true,
+            "--snapshot" => {
+                let name = args.next().ok_or("--snapshot requires a name")?;
+                config.snapshot = Some(name);
+            },
+            "--debug" => config.debug = true,
+            "--safe" => {
+                config.safe = true;
+                // no escape sequences the terminal may not understand
+                config.pause_on_focus_loss = false;
+            },
+            "--headless" => {
+                config.headless_steps.get_or_insert(DEFAULT_HEADLESS_STEPS);
+            },
+            "--steps" => {
+                let steps = args.next().ok_or("--steps requires the number of steps")?;
+                let steps = steps.parse().map_err(|_| format!("invalid number of steps: {}", steps))?;
+                config.headless_steps = Some(steps);
+            },
+            "--seed" => {
+                let seed = args.next().ok_or("--seed requires a number")?;
+                config.seed = Some(seed.parse().map_err(|_| format!("invalid seed: {}", seed))?);
+            },
+            "--export-on-loss" => {
+                let path = args.next().ok_or("--export-on-loss requires a path")?;
+                config.export_on_loss = Some(path);
+            },
+            "--export-replay" => {
+                let path = args.next().ok_or("--export-replay requires a path")?;
+                config.export_replay = Some(path);
+            },
+            "--watch-replay" => {
+                let text = args.next().ok_or("--watch-replay requires a share string")?;
+                replay::Replay::from_share_string(&text).map_err(|err| format!("--watch-replay: {}", err))?;
+                config.watch_replay = Some(text);
+            },
+            _ => return Err(format!("unknown argument: {}\n{}", arg, USAGE)),
+        }
+    }
+    Ok(())
+}
+
 fn main() {
-    ui::Ui::new().game_loop();
+    let config = Config::load().and_then(|mut config| {
+        parse_args(&mut config)?;
+        Ok(config)
+    }).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    if let Some(steps) = config.headless_steps {
+        run_headless(&config, steps);
+        return;
+    }
+    ui::Ui::new(config).start();
+}
+
+/// let gravity alone play a seeded game without touching the terminal,
+/// for smoke tests and for reproducing a seed
+fn run_headless(config: &Config, steps: usize) {
+    let seed = config.seed.unwrap_or(0);
+    let mut state = game::GameState::with_config(game::GameConfig {
+        seed: Some(seed),
+        start_level: config.start_level.unwrap_or(1),
+        max_level: config.max_level,
+        level_cap: config.level_cap,
+        line_goal: config.sprint_lines,
+        time_limit_ms: config.ultra_seconds.map(|seconds| seconds * 1000),
+        ..game::GameConfig::default()
+    });
+    if let Some(rows) = config.cheese_rows {
+        state.fill_cheese(rows, seed);
+    } else if config.garbage_rows > 0 {
+        state.seed_initial_garbage(config.garbage_rows, config.garbage_density(), seed);
+    }
+    let mut done = 0;
+    while done < steps && !state.is_lost() {
+        state.move_down();
+        done += 1;
+    }
+    println!("steps: {}", done);
+    println!("score: {}", state.score);
+    println!("lines: {}", state.lines_cleared());
+    println!("pieces: {}", state.pieces_placed());
+    if let Some(reason) = state.game_over_reason() {
+        println!("game over: {:?}", reason);
+    }
 }
@@ -0,0 +1,322 @@
+// Recording of a game as the inputs and clock ticks applied to it, played
+// back by applying them again to a copy of the starting state. A copy of
+// the game is also kept every few pieces, so going back only has to run
+// the steps since the closest copy.
+//
+// A replay of a game started from a seed can also be written as a line of
+// text to share it: "tr1:", the seed in hex, ":" and the steps. Each action
+// is a letter from 'a', in the order of `Action::ALL`, after the number of
+// ticks before it when there are any, and the ticks after the last action
+// end the line. A wait longer than `MAX_SHARED_TICKS` is written as runs of
+// ticks split by '.', and lines of more than `MAX_SHARED_STEPS` steps in all
+// aren't read. The game is played again with the default rules, so a game
+// where a rule changed while playing can't be shared.
+
+use crate::game::{Action, GameError, GameState, ReplayProblem, TICK_MS};
+
+/// pieces between two copies of the game kept by `ReplayPlayer`
+const CHECKPOINT_PIECES: i32 = 10;
+
+/// start of a share string, with the version of the format
+const SHARE_PREFIX: &str = "tr1:";
+
+/// longest run of ticks read from a share string, an hour of the game
+const MAX_SHARED_TICKS: u32 = 60 * 60 * 1000 / TICK_MS as u32;
+
+/// most steps read from a share string, ticks and actions, about ten hours
+/// of the game
+const MAX_SHARED_STEPS: usize = 10 * MAX_SHARED_TICKS as usize;
+
+/// something that happened to the game, in order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    Tick,
+    Action(Action),
+    /// the entry delay was set to this many milliseconds while playing
+    EntryDelay(i32),
+    /// the next piece was hidden or shown while playing
+    NextHidden(bool),
+}
+
+impl Step {
+    /// do to `state` what happened to the game
+    pub fn run(self, state: &mut GameState) {
+        match self {
+            Step::Tick => state.clock_tick(),
+            Step::Action(action) => state.apply(action),
+            Step::EntryDelay(ms) => state.set_entry_delay(ms),
+            Step::NextHidden(hidden) => state.set_next_hidden(hidden),
+        }
+    }
+}
+
+/// a game from the state it started in
+#[derive(Clone)]
+pub struct Replay {
+    start: GameState,
+    /// seed of the game if it is a new game with the default rules
+    seed: Option<u64>,
+    steps: Vec<Step>,
+}
+
+impl Replay {
+    /// start recording a game from `start`
+    pub fn new(start: &GameState) -> Replay {
+        Replay {
+            start: start.clone(),
+            seed: None,
+            steps: Vec::new(),
+        }
+    }
+
+    /// start recording a new game made by `GameState::with_seed(seed)`,
+    /// which can be shared with `to_share_string`
+    pub fn seeded(seed: u64) -> Replay {
+        Replay {
+            seed: Some(seed),
+            ..Replay::new(&GameState::with_seed(seed))
+        }
+    }
+
+    /// read back a replay written by `to_share_string`
+    pub fn from_share_string(s: &str) -> Result<Replay, GameError> {
+        let invalid = GameError::InvalidReplay;
+        let rest = s.trim().strip_prefix(SHARE_PREFIX).ok_or(invalid(ReplayProblem::Format))?;
+        let (seed, steps) = rest.split_once(':').ok_or(invalid(ReplayProblem::Format))?;
+        let seed = u64::from_str_radix(seed, 16).map_err(|_| invalid(ReplayProblem::Seed))?;
+        let mut replay = Replay::seeded(seed);
+        let mut ticks: u32 = 0;
+        for c in steps.chars() {
+            if replay.steps.len() > MAX_SHARED_STEPS {
+                return Err(invalid(ReplayProblem::Steps));
+            }
+            if c == '.' {
+                replay.record_ticks(std::mem::take(&mut ticks));
+                continue;
+            }
+            if let Some(digit) = c.to_digit(10) {
+                ticks = ticks.checked_mul(10)
+                    .and_then(|ticks| ticks.checked_add(digit))
+                    .filter(|&ticks| ticks <= MAX_SHARED_TICKS)
+                    .ok_or(invalid(ReplayProblem::Ticks))?;
+                continue;
+            }
+            let action = (c as usize).checked_sub('a' as usize)
+                .and_then(|index| Action::ALL.get(index))
+                .ok_or(invalid(ReplayProblem::Character(c)))?;
+            replay.record_ticks(std::mem::take(&mut ticks));
+            replay.record(Step::Action(*action));
+        }
+        replay.record_ticks(ticks);
+        if replay.steps.len() > MAX_SHARED_STEPS {
+            return Err(invalid(ReplayProblem::Steps));
+        }
+        Ok(replay)
+    }
+
+    /// the replay as a line of text, None if it doesn't start from a seed
+    /// or a rule changed during the game
+    pub fn to_share_string(&self) -> Option<String> {
+        let mut text = format!("{}{:x}:", SHARE_PREFIX, self.seed?);
+        let mut ticks = 0;
+        for step in &self.steps {
+            match *step {
+                Step::Tick => ticks += 1,
+                Step::Action(action) => {
+                    write_ticks(&mut text, std::mem::take(&mut ticks));
+                    let index = Action::ALL.iter().position(|&a| a == action).expect("every action is in ALL");
+                    text.push((b'a' + index as u8) as char);
+                },
+                Step::EntryDelay(_) | Step::NextHidden(_) => return None,
+            }
+        }
+        write_ticks(&mut text, ticks);
+        Some(text)
+    }
+
+    fn record_ticks(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.record(Step::Tick);
+        }
+    }
+
+    pub fn record(&mut self, step: Step) {
+        self.steps.push(step);
+    }
+
+    pub fn start(&self) -> &GameState {
+        &self.start
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+/// write a wait of `ticks` to a share string, in runs short enough to be
+/// read back
+fn write_ticks(text: &mut String, mut ticks: u32) {
+    while ticks > MAX_SHARED_TICKS {
+        *text += &format!("{}.", MAX_SHARED_TICKS);
+        ticks -= MAX_SHARED_TICKS;
+    }
+    if ticks > 0 {
+        *text += &ticks.to_string();
+    }
+}
+
+/// plays a replay back, moving forward and backward in it
+pub struct ReplayPlayer {
+    replay: Replay,
+    state: GameState,
+    /// steps applied to `state`
+    position: usize,
+    /// position where each piece starts falling
+    piece_starts: Vec<usize>,
+    /// positions right after a line clear
+    clears: Vec<usize>,
+    /// copies of the game at some positions, the first one at the start
+    checkpoints: Vec<(usize, GameState)>,
+}
+
+impl ReplayPlayer {
+    /// play `replay` from its start. The whole game is run once to find
+    /// the pieces and the line clears
+    pub fn new(replay: Replay) -> ReplayPlayer {
+        let mut state = replay.start.clone();
+        let mut piece_starts = vec![0];
+        let mut clears = Vec::new();
+        let mut checkpoints = vec![(0, state.clone())];
+        for (i, &step) in replay.steps.iter().enumerate() {
+            let (pieces, lines) = (state.pieces_placed(), state.lines_cleared());
+            step.run(&mut state);
+            state.take_events();
+            if state.pieces_placed() > pieces {
+                piece_starts.push(i + 1);
+                if state.pieces_placed() % CHECKPOINT_PIECES == 0 {
+                    checkpoints.push((i + 1, state.clone()));
+                }
+            }
+            if state.lines_cleared() > lines {
+                clears.push(i + 1);
+            }
+        }
+        let state = replay.start.clone();
+        ReplayPlayer {
+            replay,
+            state,
+            position: 0,
+            piece_starts,
+            clears,
+            checkpoints,
+        }
+    }
+
+    /// the game as it was at the current position
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// steps played so far
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn len(&self) -> usize {
+        self.replay.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replay.is_empty()
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.position == self.replay.len()
+    }
+
+    /// index of the piece falling at the current position, from 0
+    pub fn piece(&self) -> usize {
+        self.piece_starts.iter().filter(|&&start| start <= self.position).count() - 1
+    }
+
+    /// pieces that fell during the game, the last one may not have locked
+    pub fn pieces(&self) -> usize {
+        self.piece_starts.len()
+    }
+
+    /// play the next step, false at the end of the replay
+    pub fn step_forward(&mut self) -> bool {
+        match self.replay.steps.get(self.position) {
+            Some(&step) => {
+                step.run(&mut self.state);
+                self.state.take_events();
+                self.position += 1;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// play the steps up to the next clock tick, what the game did in a frame
+    pub fn step_frame(&mut self) -> bool {
+        while let Some(&step) = self.replay.steps.get(self.position) {
+            self.step_forward();
+            if step == Step::Tick {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// go to the state after `position` steps, starting from the closest
+    /// copy of the game when going back
+    pub fn seek(&mut self, position: usize) {
+        let position = position.min(self.replay.len());
+        if position < self.position {
+            let (start, state) = self.checkpoints.iter()
+                .rev()
+                .find(|(start, _)| *start <= position)
+                .expect("the first checkpoint is at the start");
+            self.position = *start;
+            self.state = state.clone();
+        }
+        while self.position < position {
+            self.step_forward();
+        }
+    }
+
+    /// go to where the next piece starts falling, or to the end
+    pub fn next_piece(&mut self) {
+        let next = self.piece_starts.get(self.piece() + 1).copied().unwrap_or(self.replay.len());
+        self.seek(next);
+    }
+
+    /// go back to where the previous piece started falling
+    pub fn previous_piece(&mut self) {
+        let piece = self.piece().saturating_sub(1);
+        self.seek(self.piece_starts[piece]);
+    }
+
+    /// go to right after the next line clear, false if there isn't one
+    pub fn next_clear(&mut self) -> bool {
+        match self.clears.iter().find(|&&clear| clear > self.position) {
+            Some(&clear) => {
+                self.seek(clear);
+                true
+            },
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// State that lives across the games played in one run of the program.
+// The high score table is kept in ~/.local/share/tetris-rust/highscores.json,
+// saved positions in snapshots/NAME.json next to it.
+
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::game::{GameState, Snapshot};
+use crate::highscores::{HighScore, HighScores};
+
+const DEFAULT_SNAPSHOT: &str = "default";
+
+pub struct Session {
+    /// settings chosen at startup and changed while playing
+    pub config: Config,
+    games_played: i32,
+    best_score: i32,
+    high_scores: HighScores,
+    /// why the high score table couldn't be loaded, it isn't saved then
+    high_scores_error: Option<String>,
+    /// position to go back to with the retry key
+    snapshot: Option<Snapshot>,
+}
+
+impl Session {
+    pub fn new(config: Config) -> Session {
+        let snapshot = Session::snapshot_path(&config)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok());
+        // a table that failed to load starts empty and is never saved, so
+        // the scores in the file are still there to recover
+        let (high_scores, high_scores_error) = match HighScores::load(Session::data_dir()) {
+            Ok(high_scores) => (high_scores, None),
+            Err(err) => (HighScores::default(), Some(err)),
+        };
+        Session {
+            config,
+            games_played: 0,
+            best_score: 0,
+            high_scores,
+            high_scores_error,
+            snapshot,
+        }
+    }
+
+    fn data_dir() -> Option<PathBuf> {
+        let base = match std::env::var_os("XDG_DATA_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".local").join("share"),
+        };
+        Some(base.join("tetris-rust"))
+    }
+
+    fn snapshot_path(config: &Config) -> Option<PathBuf> {
+        let name = config.snapshot.as_deref().unwrap_or(DEFAULT_SNAPSHOT);
+        Some(Session::data_dir()?.join("snapshots").join(format!("{}.json", name)))
+    }
+
+    /// name for the high score table, from the config or the login name
+    fn player_name(&self) -> String {
+        self.config.name.clone()
+            .or_else(|| std::env::var("USER").ok())
+            .unwrap_or_else(|| "player".to_string())
+    }
+
+    /// kind of game being played, saved with the high scores
+    pub fn mode(&self) -> &'static str {
+        self.config.mode().name()
+    }
+
+    /// keep the position for retrying it, also on disk for later sessions
+    pub fn save_snapshot(&mut self, snapshot: Snapshot) -> Result<(), String> {
+        let path = Session::snapshot_path(&self.config).ok_or("no home directory to save the snapshot")?;
+        let json = serde_json::to_string(&snapshot).map_err(|err| err.to_string())?;
+        self.snapshot = Some(snapshot);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+        }
+        std::fs::write(&path, json).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn snapshot(&self) -> Option<&Snapshot> {
+        self.snapshot.as_ref()
+    }
+
+    /// record the result of a finished game
+    pub fn finish_game(&mut self, state: &GameState) {
+        self.games_played += 1;
+        self.best_score = self.best_score.max(state.score);
+        if state.score > 0 {
+            let entry = HighScore::new(&self.player_name(), state.score, state.lines_cleared(), self.mode());
+            self.high_scores.add(entry);
+        }
+    }
+
+    pub fn games_played(&self) -> i32 {
+        self.games_played
+    }
+
+    /// best score of the games played in this session
+    pub fn best_score(&self) -> i32 {
+        self.best_score
+    }
+
+    /// best score ever, including this session
+    pub fn high_score(&self) -> i32 {
+        self.high_scores.best().max(self.best_score)
+    }
+
+    pub fn high_scores(&self) -> &HighScores {
+        &self.high_scores
+    }
+
+    pub fn high_scores_error(&self) -> Option<&str> {
+        self.high_scores_error.as_deref()
+    }
+}
@@ -0,0 +1,151 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+use crate::game::PieceShape;
+
+/// where the shapes of the new pieces come from, a random generator in
+/// the game and a fixed script in tests
+pub trait PieceSource {
+    /// shape of the next piece, out of a set of `count` shapes
+    fn next_shape(&mut self, count: usize) -> PieceShape;
+
+    /// a copy of the source, to clone the game using it
+    fn clone_box(&self) -> Box<dyn PieceSource>;
+
+    /// shapes left in the bag the source draws from, in no particular
+    /// order, empty for a source without a bag
+    fn remaining_bag(&self) -> &[PieceShape] {
+        &[]
+    }
+}
+
+/// how the shapes of the new pieces are picked
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Randomizer {
+    /// any shape each time
+    #[default]
+    Random,
+    /// every shape once, shuffled, then again with a new bag
+    Bag,
+}
+
+impl Randomizer {
+    pub const ALL: [Randomizer; 2] = [Randomizer::Random, Randomizer::Bag];
+
+    /// name in the configuration file
+    pub fn name(self) -> &'static str {
+        match self {
+            Randomizer::Random => "random",
+            Randomizer::Bag => "bag",
+        }
+    }
+
+    /// a source picking shapes this way, seeded from the system
+    pub fn source(self) -> Box<dyn PieceSource> {
+        self.with_rng(StdRng::from_entropy())
+    }
+
+    /// a source picking shapes this way, in an order that only depends
+    /// on `seed`
+    pub fn seeded(self, seed: u64) -> Box<dyn PieceSource> {
+        self.with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(self, rng: StdRng) -> Box<dyn PieceSource> {
+        match self {
+            Randomizer::Random => Box::new(rng),
+            Randomizer::Bag => Box::new(BagSource::new(rng)),
+        }
+    }
+}
+
+impl std::str::FromStr for Randomizer {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Randomizer, ()> {
+        Randomizer::ALL.iter().copied().find(|randomizer| randomizer.name() == s).ok_or(())
+    }
+}
+
+impl Clone for Box<dyn PieceSource> {
+    fn clone(&self) -> Box<dyn PieceSource> {
+        self.clone_box()
+    }
+}
+
+impl PieceSource for StdRng {
+    fn next_shape(&mut self, count: usize) -> PieceShape {
+        PieceShape(self.gen_range(0, count))
+    }
+
+    fn clone_box(&self) -> Box<dyn PieceSource> {
+        Box::new(self.clone())
+    }
+}
+
+/// draws every shape of the set once in a random order before starting
+/// a new bag, so there are never long runs without a shape
+#[derive(Clone, Debug)]
+pub struct BagSource {
+    rng: StdRng,
+    /// shapes not drawn yet, sorted
+    bag: Vec<PieceShape>,
+}
+
+impl BagSource {
+    pub fn new(rng: StdRng) -> BagSource {
+        BagSource { rng, bag: Vec::new() }
+    }
+
+    /// a bag source where the order of the shapes only depends on `seed`
+    pub fn with_seed(seed: u64) -> BagSource {
+        BagSource::new(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl PieceSource for BagSource {
+    fn next_shape(&mut self, count: usize) -> PieceShape {
+        // a shape that isn't in the set anymore means the set changed
+        if self.bag.is_empty() || self.bag.iter().any(|shape| shape.0 >= count) {
+            self.bag = (0..count).map(PieceShape).collect();
+        }
+        // taking a random shape keeps the others sorted, so the bag
+        // doesn't tell the order they'll come in
+        let index = self.rng.gen_range(0, self.bag.len());
+        self.bag.remove(index)
+    }
+
+    fn clone_box(&self) -> Box<dyn PieceSource> {
+        Box::new(self.clone())
+    }
+
+    fn remaining_bag(&self) -> &[PieceShape] {
+        &self.bag
+    }
+}
+
+/// the shapes given, in order, starting over after the last one
+#[derive(Clone, Debug)]
+pub struct FixedSource {
+    shapes: Vec<PieceShape>,
+    next: usize,
+}
+
+impl FixedSource {
+    pub fn new(shapes: Vec<PieceShape>) -> FixedSource {
+        assert!(!shapes.is_empty(), "a fixed source needs at least one shape");
+        FixedSource { shapes, next: 0 }
+    }
+}
+
+impl PieceSource for FixedSource {
+    fn next_shape(&mut self, count: usize) -> PieceShape {
+        let shape = self.shapes[self.next];
+        self.next = (self.next + 1) % self.shapes.len();
+        PieceShape(shape.0 % count)
+    }
+
+    fn clone_box(&self) -> Box<dyn PieceSource> {
+        Box::new(self.clone())
+    }
+}
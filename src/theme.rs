@@ -0,0 +1,92 @@
+use crate::game::FieldCell;
+
+/// colors the pieces take from a theme, one for each standard piece in
+/// the order I, O, L, J, T, S, Z
+pub const PIECE_COLORS: usize = 7;
+
+/// colors of the pieces and of the garbage
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Theme {
+    #[default]
+    Classic,
+    Pastel,
+    Mono,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Classic, Theme::Pastel, Theme::Mono];
+
+    /// the theme after this one, going back to the first after the last
+    pub fn next(self) -> Theme {
+        let i = Theme::ALL.iter().position(|&theme| theme == self).unwrap_or(0);
+        Theme::ALL[(i + 1) % Theme::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Classic => "classic",
+            Theme::Pastel => "pastel",
+            Theme::Mono => "mono",
+        }
+    }
+
+    /// foreground colors of the seven pieces, in the order of the
+    /// standard piece set, followed by the garbage. Colors up to 7 are the
+    /// eight of the terminal (1 red, 2 green, 3 yellow, 4 blue, 5 magenta,
+    /// 6 cyan, 7 white), those above from the 256 color palette. They are
+    /// plain numbers so the library doesn't need ncurses
+    pub fn colors(self) -> [i16; PIECE_COLORS + 1] {
+        match self {
+            Theme::Classic => [6, 3, 203, 4, 5, 2, 1, 7],
+            Theme::Pastel => [159, 229, 216, 111, 183, 157, 210, 250],
+            Theme::Mono => [252, 252, 252, 252, 252, 252, 252, 244],
+        }
+    }
+
+    /// color `cell` is drawn with, None for an empty cell
+    pub fn cell_color(self, cell: FieldCell) -> Option<i16> {
+        color_slot(cell).map(|slot| self.colors()[slot])
+    }
+}
+
+impl std::str::FromStr for Theme {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Theme, ()> {
+        Theme::ALL.iter().copied().find(|theme| theme.name() == s).ok_or(())
+    }
+}
+
+/// index in `Theme::colors` of the color `cell` is drawn with, None for
+/// an empty cell. The garbage takes the last one
+pub fn color_slot(cell: FieldCell) -> Option<usize> {
+    match cell {
+        FieldCell::Empty => None,
+        FieldCell::Occupied(p) | FieldCell::Item(p, _) => Some(p.color_index()),
+        FieldCell::Painted(_, color) => Some(color as usize % PIECE_COLORS),
+        FieldCell::Garbage => Some(PIECE_COLORS),
+    }
+}
+
+/// `color` if a terminal with `color_count` colors has it, otherwise
+/// white, like orange on 8 colors
+pub fn available_color(color: i16, color_count: i32) -> i16 {
+    if (color as i32) < color_count {
+        color
+    } else {
+        7
+    }
+}
+
+/// how to show two cells of the board stacked in one character, `upper`
+/// over `lower`, each the color of its block or None if it's empty: the
+/// half block character to draw and its foreground and background colors,
+/// None for those of the terminal. An empty upper cell takes the lower
+/// half block, so the foreground is always a block
+pub fn half_block<T: Copy>(upper: Option<T>, lower: Option<T>) -> (char, Option<T>, Option<T>) {
+    match (upper, lower) {
+        (None, None) => (' ', None, None),
+        (Some(upper), lower) => ('\u{2580}', Some(upper), lower),
+        (None, Some(lower)) => ('\u{2584}', Some(lower), None),
+    }
+}
@@ -1,90 +1,910 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
 use ncurses::*;
 
-use crate::game::{GAME_HEIGHT, GAME_WIDTH, FieldCell, GameState};
+use crate::ai::{self, Autoplay, Move};
+use crate::clock::{Clock, FrameLimiter, RepeatGuard, SystemClock};
+use crate::config::{Background, CellChars, Config, HudField, Mode, Profile, RenderStyle, MAX_START_LEVEL};
+use crate::session::Session;
+use crate::layout;
+use crate::game::{GAME_HEIGHT, GAME_WIDTH, PIECE_SIZE, POINTS_PER_LEVEL, REWIND_PIECES, ZONE_CHARGE_LINES, Action, CascadeStep, ClearAnimation, ClearPhase, Field, FieldCell, GameConfig, GameEvent, GameOverReason, GameState, Item, LevelCap, Piece, PieceShape, Point, Rotation, RotationSystem, ScoreBreakdown, Spin};
+use crate::replay::{Replay, ReplayPlayer, Step};
+use crate::source::Randomizer;
+use crate::viewport::Viewport;
+use crate::theme::{self, available_color, half_block, Theme, PIECE_COLORS};
 
 const BLOCK: chtype = ' ' as chtype | A_REVERSE();
+/// color pairs of the colors of the pieces, in the order of `Theme::colors`
+const PIECE_PAIRS: [i16; PIECE_COLORS] = [1, 2, 3, 4, 5, 6, 7];
+const PROGRESS_BAR_WIDTH: i32 = 10;
+const GARBAGE_COLOR: i16 = 8;
+const TOP_OUT_COLOR: i16 = 9;
+const GHOST_COLOR: i16 = 10;
+const BORDER_COLOR: i16 = 11;
+const FOCUS_COLOR: i16 = 12;
+const DANGER_COLOR: i16 = 13;
+/// first of the pairs of two colors of the half block cells, one for each
+/// color of the top half and color or none of the bottom half
+const HALF_BLOCK_PAIRS: i16 = 16;
+/// colors of the half block cells: those of `theme::color_slot`, the
+/// shapes and the garbage, then the piece that topped out and the ghost
+const HALF_BLOCK_SLOTS: usize = PIECE_COLORS + 3;
+const GARBAGE_SLOT: usize = PIECE_COLORS;
+const TOP_OUT_SLOT: usize = PIECE_COLORS + 1;
+const GHOST_SLOT: usize = PIECE_COLORS + 2;
+/// column of the window of the first board column drawn with half blocks,
+/// the narrower board is centered in the window
+const HALF_BLOCK_LEFT: i32 = 1 + GAME_WIDTH as i32 / 2;
+const HOLD_BOX_X: i32 = 14;
+const ROTATION_FEEDBACK_FRAMES: u32 = 10;
+const BREAKDOWN_X: i32 = 28;
+const BREAKDOWN_FRAMES: u32 = 40;
+const STATUS_FRAMES: u32 = 40;
+/// modes offered on the options screen, with their usual numbers
+const MODES: [Mode; 4] = [Mode::Marathon, Mode::Sprint(40), Mode::Ultra(180), Mode::Cheese(10)];
+const INPUT_LOG_SIZE: usize = 8;
+const INPUT_LOG_WIDTH: i32 = 20;
+const FOCUS_REPORTING_ON: &str = "\x1b[?1004h";
+const FOCUS_REPORTING_OFF: &str = "\x1b[?1004l";
+const LOCK_FLASH_MS: i32 = 80;
+/// time each stage of a cascade is shown
+const CASCADE_STEP_MS: i32 = 120;
+const DEMO_IDLE_TICKS: i32 = 200; // 10 seconds of 50ms input timeouts
+const TITLE_HEIGHT: i32 = 7;
+const GAME_WINDOW_WIDTH: i32 = GAME_WIDTH as i32 * 2 + 2;
+// smallest terminal the game can be played in, with the board scrolling
+const MIN_VISIBLE_ROWS: i32 = PIECE_SIZE as i32;
+const MIN_SCORE_WIDTH: i32 = 16;
+
+/// what the terminal can display, probed at startup
+#[derive(Clone, Copy, Debug)]
+struct Capabilities {
+    colors: bool,
+    color_count: i32,
+    utf8: bool,
+}
+
+impl Capabilities {
+    /// must be called after initscr, starts the colors if there are any.
+    /// With `safe` there are no colors and no UTF-8 characters at all
+    fn probe(safe: bool) -> Capabilities {
+        let colors = has_colors() && !safe;
+        if colors {
+            start_color();
+        }
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_lowercase();
+        let conservative = safe || Capabilities::remote_or_unknown();
+        let color_count = if colors { COLORS() } else { 0 };
+        Capabilities {
+            colors,
+            // the basic 8 colors are the ones every terminal agrees on
+            color_count: if conservative { color_count.min(8) } else { color_count },
+            utf8: !conservative && (locale.contains("utf-8") || locale.contains("utf8")),
+        }
+    }
+
+    /// true over SSH or when TERM doesn't name a known terminal, the
+    /// locale and color count may be those of another machine
+    fn remote_or_unknown() -> bool {
+        let term = std::env::var("TERM").unwrap_or_default();
+        std::env::var_os("SSH_CONNECTION").is_some() || term.is_empty() || term == "dumb" || term == "unknown"
+    }
+}
+
+/// set by the SIGINT handler, which can't do more than that safely. The
+/// input reads check it, the keys come in at least every 50ms
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// leave after a Ctrl-C, giving the terminal back as it was. Unlike `Q`
+/// it doesn't save the settings changed while playing
+fn exit_interrupted() -> ! {
+    // harmless if focus reporting wasn't on
+    putp(FOCUS_REPORTING_OFF);
+    endwin();
+    std::process::exit(130);
+}
 
 mod input {
+    #[allow(clippy::upper_case_acronyms)]
     #[derive(Clone, Copy, PartialEq, Eq)]
     pub enum Character {
         ASCII(char),
-        Control(i32),
+        Control(Key),
+        Timeout,
+        FocusIn,
+        FocusOut,
     }
 
+    /// keys that are not printable characters
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Key {
+        Left,
+        Right,
+        Up,
+        Down,
+        Enter,
+        Escape,
+        Function(i32),
+        /// raw ncurses code of a key without a name here
+        Other(i32),
+    }
+
+    const ESCAPE: i32 = 27;
+    const FUNCTION_KEYS: i32 = 64;
+
     pub fn read() -> Character {
         let ch = super::getch();
-        if ch < 127 {
+        if super::INTERRUPTED.load(super::Ordering::SeqCst) {
+            super::exit_interrupted();
+        }
+        if ch == super::ERR {
+            Character::Timeout
+        } else if ch == ESCAPE {
+            read_escape_sequence()
+        } else if ch == '\n' as i32 || ch == '\r' as i32 {
+            Character::Control(Key::Enter)
+        } else if ch < 127 {
             Character::ASCII(ch as u8 as char)
         } else {
-            Character::Control(ch)
+            Character::Control(key(ch))
+        }
+    }
+
+    fn key(code: i32) -> Key {
+        match code {
+            super::KEY_LEFT => Key::Left,
+            super::KEY_RIGHT => Key::Right,
+            super::KEY_UP => Key::Up,
+            super::KEY_DOWN => Key::Down,
+            super::KEY_ENTER => Key::Enter,
+            code if (super::KEY_F0..super::KEY_F0 + FUNCTION_KEYS).contains(&code) => Key::Function(code - super::KEY_F0),
+            code => Key::Other(code),
+        }
+    }
+
+    /// focus events are reported as ESC [ I and ESC [ O, and arrows the
+    /// terminal didn't translate as ESC [ A to ESC [ D. Any other byte
+    /// after ESC, or after ESC [, is put back to be read as a key of its own
+    fn read_escape_sequence() -> Character {
+        match super::getch() {
+            ch if ch == '[' as i32 => {},
+            super::ERR => return Character::Control(Key::Escape),
+            ch => {
+                super::ungetch(ch);
+                return Character::Control(Key::Escape);
+            },
+        }
+        match super::getch() {
+            ch if ch == 'I' as i32 => Character::FocusIn,
+            ch if ch == 'O' as i32 => Character::FocusOut,
+            ch if ch == 'A' as i32 => Character::Control(Key::Up),
+            ch if ch == 'B' as i32 => Character::Control(Key::Down),
+            ch if ch == 'C' as i32 => Character::Control(Key::Right),
+            ch if ch == 'D' as i32 => Character::Control(Key::Left),
+            super::ERR => Character::Control(Key::Escape),
+            ch => {
+                super::ungetch(ch);
+                Character::Control(Key::Escape)
+            },
+        }
+    }
+
+    /// kind and code of a key, for the input log
+    pub fn describe(ch: Character) -> String {
+        match ch {
+            Character::ASCII(c) => format!("ASCII {:?} {}", c, c as u32),
+            Character::Control(Key::Other(code)) => {
+                let name = super::keyname(code).unwrap_or_default();
+                format!("Control {} {}", code, name)
+            },
+            Character::Control(key) => format!("Control {:?}", key),
+            Character::Timeout => "timeout".to_string(),
+            Character::FocusIn => "focus in".to_string(),
+            Character::FocusOut => "focus out".to_string(),
         }
     }
 
-    pub fn read_yes_no() -> bool {
+    /// wait for one of `keys` to be pressed
+    pub fn read_one_of(keys: &[char]) -> char {
         loop {
-            match read() {
-                Character::ASCII('y') => return true,
-                Character::ASCII('n') => return false,
-                _ => {},
+            if let Character::ASCII(c) = read() {
+                if keys.contains(&c) {
+                    return c;
+                }
             }
         }
     }
 }
 
 mod controls {
-    use super::input::Character;
+    use super::input::{Character, Key};
     use super::input::Character::*;
-    use ncurses;
     pub const PAUSE: Character = ASCII('p');
+    pub const GRID: Character = ASCII('g');
+    pub const GHOST: Character = ASCII('o');
+    pub const MARKERS: Character = ASCII('m');
+    pub const HIDE_NEXT: Character = ASCII('n');
+    pub const MIRROR: Character = ASCII('i');
+    pub const HELP: Character = ASCII('?');
+    pub const HINT: Character = ASCII('h');
+    pub const THEME: Character = ASCII('t');
+    pub const SAVE: Character = ASCII('s');
+    pub const RETRY: Character = ASCII('r');
+    pub const HOLD: Character = ASCII('c');
+    pub const REST: Character = ASCII('d');
+    pub const ZONE: Character = ASCII('z');
     pub const QUIT: Character = ASCII('q');
+    pub const MENU: Character = Control(Key::Escape);
+    pub const MENU_UP: Character = Control(Key::Up);
+    pub const MENU_DOWN: Character = Control(Key::Down);
+    pub const SELECT: Character = Control(Key::Enter);
     pub const BOTTOM: Character = ASCII(' ');
-    pub const LEFT: Character = Control(ncurses::KEY_LEFT);
-    pub const RIGHT: Character = Control(ncurses::KEY_RIGHT);
-    pub const ROTATE: Character = Control(ncurses::KEY_UP);
-    pub const DOWN: Character = Control(ncurses::KEY_DOWN);
+    pub const LEFT: Character = Control(Key::Left);
+    pub const RIGHT: Character = Control(Key::Right);
+    pub const ROTATE: Character = Control(Key::Up);
+    pub const ROTATE_CCW: Character = ASCII('x');
+    pub const DOWN: Character = Control(Key::Down);
+}
+
+/// how the value of a setting is changed on the options screen
+enum Editor {
+    Toggle(bool),
+    /// one of the names, the index of the chosen one
+    Choice(Vec<String>, usize),
+    /// a number kept from `min` to `max`, changed by `step`
+    Number { value: i32, min: i32, max: i32, step: i32 },
+}
+
+impl Editor {
+    /// a choice of `names` starting on `current`
+    fn choice(names: Vec<String>, current: &str) -> Editor {
+        let index = names.iter().position(|name| name == current).unwrap_or(0);
+        Editor::Choice(names, index)
+    }
+
+    /// go `by` steps forward or back, toggles just flip
+    fn change(&mut self, by: i32) {
+        match self {
+            Editor::Toggle(on) => *on = !*on,
+            Editor::Choice(names, index) => {
+                *index = (*index as i32 + by).rem_euclid(names.len() as i32) as usize;
+            },
+            Editor::Number { value, min, max, step } => *value = (*value + by * *step).max(*min).min(*max),
+        }
+    }
+
+    /// the value as written in the configuration file
+    fn value(&self) -> String {
+        match self {
+            Editor::Toggle(on) => on.to_string(),
+            Editor::Choice(names, index) => names[*index].clone(),
+            Editor::Number { value, .. } => value.to_string(),
+        }
+    }
+
+    /// the value as shown on the options screen
+    fn text(&self) -> String {
+        match self {
+            Editor::Toggle(true) => "on".to_string(),
+            Editor::Toggle(false) => "off".to_string(),
+            _ => self.value(),
+        }
+    }
+}
+
+/// a line of the options screen, `key` is the setting of the
+/// configuration file it changes, None for the hint that isn't saved
+struct Setting {
+    label: &'static str,
+    key: Option<&'static str>,
+    editor: Editor,
+}
+
+impl Setting {
+    fn new(label: &'static str, key: &'static str, editor: Editor) -> Setting {
+        Setting { label, key: Some(key), editor }
+    }
 }
 
 pub struct Ui {
     game_window: WINDOW,
+    /// rows of the board that fit in the game window
+    viewport: Cell<Viewport>,
+    /// the board is drawn with half blocks, two rows in a row of the window
+    half_blocks: bool,
     score_window: WINDOW,
     state: GameState,
+    /// the current game so far, to watch it again when it's over
+    replay: Replay,
+    session: Session,
+    caps: Capabilities,
+    /// settings changed while playing, saved to the configuration file
+    /// on exit
+    changed_settings: Vec<(&'static str, String)>,
+    /// last keys received, shown in debug mode
+    input_log: VecDeque<input::Character>,
+    input_window: Option<WINDOW>,
+    frames: u64,
+    /// keeps the game loop from spinning when keys come in fast
+    limiter: FrameLimiter,
+    clock: SystemClock,
+    /// keep a rotate key held down from spinning the piece, clockwise
+    /// then counterclockwise
+    rotate_guards: [RepeatGuard; 2],
+    /// last kicked or spinning rotation and for how many frames to show it
+    rotation_feedback: Option<(Rotation, u32)>,
+    /// points of the last clear and for how many frames to show them
+    score_breakdown: Option<(ScoreBreakdown, u32)>,
+    /// short message for the player and for how many frames to show it
+    status: Option<(String, u32)>,
+    show_hint: bool,
+    /// the stack is close to the top, the border turns red
+    danger: bool,
+    /// suggested placement, with the pieces placed and holds it was computed for
+    hint: Option<((i32, i32), Option<Move>)>,
 }
 
 impl Ui {
-    pub fn new() -> Ui {
+    pub fn new(config: Config) -> Ui {
+        if config.render_style == RenderStyle::HalfBlock && !config.safe {
+            // the half blocks are multibyte characters, written in the
+            // encoding of the locale
+            setlocale(LcCategory::all, "");
+        }
         Ui::initialize_cursess();
-        Ui::initialize_colors();
-        Ui::print_title();
+        Ui::check_terminal_size();
+        let caps = Capabilities::probe(config.safe);
+        let half_blocks = Ui::uses_half_blocks(caps, &config);
+        Ui::initialize_colors(caps, &config);
+        let top = Ui::window_top();
+        if top > 0 {
+            Ui::print_title();
+        }
+        if config.pause_on_focus_loss {
+            // terminals that can't report focus changes just ignore this
+            putp(FOCUS_REPORTING_ON);
+        }
+        let input_window = if config.debug { Some(Ui::create_input_window(top)) } else { None };
+        let game_window = Ui::create_game_window(top, half_blocks);
+        let visible_rows = (getmaxy(game_window) as usize - 2) * if half_blocks { 2 } else { 1 };
+        let (state, replay) = Ui::new_game(&config);
+        let limiter = FrameLimiter::new(config.max_fps());
+        let rotate_guard = RepeatGuard::new(config.rotate_cooldown_ms);
         Ui {
-            game_window: Ui::create_game_window(),
-            score_window: Ui::create_score_window(),
-            state: GameState::new(),
+            game_window,
+            viewport: Cell::new(Viewport::new(GAME_HEIGHT, visible_rows)),
+            half_blocks,
+            score_window: Ui::create_score_window(top),
+            replay,
+            state,
+            session: Session::new(config),
+            caps,
+            changed_settings: Vec::new(),
+            input_log: VecDeque::new(),
+            input_window,
+            frames: 0,
+            limiter,
+            clock: SystemClock::new(),
+            rotate_guards: [rotate_guard; 2],
+            rotation_feedback: None,
+            score_breakdown: None,
+            status: None,
+            show_hint: false,
+            danger: false,
+            hint: None,
+        }
+    }
+
+    /// a new game and the replay to record it in. The replay can be
+    /// shared if the game is one a share string can start again: a seed
+    /// and the default rules
+    fn new_game(config: &Config) -> (GameState, Replay) {
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let game_config = GameConfig { seed: Some(seed), ..config.game_config() };
+        let shareable = game_config == GameConfig { seed: Some(seed), ..GameConfig::default() }
+            && config.cheese_rows.is_none()
+            && config.garbage_rows == 0;
+        let mut state = GameState::with_config(game_config);
+        if let Some(rows) = config.cheese_rows {
+            state.fill_cheese(rows, rand::random());
+        } else if config.garbage_rows > 0 {
+            state.seed_initial_garbage(config.garbage_rows, config.garbage_density(), rand::random());
+        }
+        let replay = if shareable { Replay::seeded(seed) } else { Replay::new(&state) };
+        (state, replay)
+    }
+
+    /// wait on the start screen, running the demo when left idle
+    pub fn start(&mut self) {
+        self.watch_shared_replay();
+        while self.wait_for_start() {
+            self.demo_loop();
+        }
+        self.offer_last_mode();
+        let mode = self.session.config.mode().to_string();
+        self.remember_setting("last_mode", mode);
+        self.remember_setting("last_zone", self.session.config.zone.to_string());
+        self.reset_clock();
+        self.update();
+        self.game_loop();
+    }
+
+    /// watch the replay given with `--watch-replay` before the first game
+    fn watch_shared_replay(&mut self) {
+        let replay = match self.session.config.watch_replay.as_deref().map(Replay::from_share_string) {
+            Some(Ok(replay)) => replay,
+            // a string that can't be read stops the program at the start
+            _ => return,
+        };
+        let recording = std::mem::replace(&mut self.replay, replay);
+        self.watch_replay();
+        self.replay = recording;
+    }
+
+    /// offer to play the kind of game of the last time again, unless the
+    /// command line picked one or it was a plain marathon
+    fn offer_last_mode(&mut self) {
+        let config = &self.session.config;
+        if config.mode() != Mode::Marathon || config.zone {
+            return;
+        }
+        let last = match config.last_mode {
+            Some(mode) if mode != Mode::Marathon || config.last_zone => mode,
+            _ => return,
+        };
+        let zone = if config.last_zone { " + zone" } else { "" };
+        let items = [format!("last: {}{}", last, zone), "marathon".to_string()];
+        if self.menu("START", &items) == Some(0) {
+            self.session.config.set_mode(last);
+            self.session.config.zone = self.session.config.last_zone;
+            (self.state, self.replay) = Ui::new_game(&self.session.config);
+        }
+    }
+
+    fn reset_clock(&mut self) {
+        self.frames = 0;
+        self.state.reset_timer();
+    }
+
+    /// true if the idle timeout expired, false if a key was pressed
+    fn wait_for_start(&mut self) -> bool {
+        werase(self.game_window);
+        draw_border(self.game_window, self.border_color(false));
+        wrefresh(self.game_window);
+        werase(self.score_window);
+        mvwprintw(self.score_window, 1, 0, "press any key to start");
+        self.print_high_scores(3);
+        mvwprintw(self.score_window, 11, 0, &self.controls());
+        wrefresh(self.score_window);
+        for _ in 0..DEMO_IDLE_TICKS {
+            match input::read() {
+                input::Character::Timeout => {},
+                controls::QUIT => self.quit(),
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// let the computer play until a key is pressed
+    fn demo_loop(&mut self) {
+        let mut state = GameState::new();
+        let mut autoplay = Autoplay::new();
+        std::mem::swap(&mut self.state, &mut state);
+        self.reset_clock();
+        while input::read() == input::Character::Timeout {
+            self.frames += 1;
+            self.state.clock_tick();
+            autoplay.step(&mut self.state);
+            self.handle_events();
+            if self.state.is_lost() {
+                self.state = GameState::new();
+                autoplay = Autoplay::new();
+            }
+            self.update();
+            mvwprintw(self.score_window, 0, 0, "DEMO - press any key");
+            wrefresh(self.score_window);
         }
+        self.state = state;
     }
 
     pub fn game_loop(&mut self) {
         loop {
+            self.frames += 1;
             self.state.clock_tick();
+            self.replay.record(Step::Tick);
             self.handle_input();
+            self.handle_events();
+            self.update_danger();
+            self.update_hint();
             self.update();
-            if self.state.is_lost() {
-                self.prompt_new_game();
+            self.rotation_feedback = match self.rotation_feedback {
+                Some((rotation, frames)) if frames > 1 => Some((rotation, frames - 1)),
+                _ => None,
+            };
+            self.score_breakdown = match self.score_breakdown {
+                Some((breakdown, frames)) if frames > 1 => Some((breakdown, frames - 1)),
+                _ => None,
+            };
+            self.status = match self.status.take() {
+                Some((message, frames)) if frames > 1 => Some((message, frames - 1)),
+                _ => None,
+            };
+            if self.state.can_rewind() {
+                self.draw_topped_out();
+                if self.prompt_rewind() {
+                    continue;
+                }
+            }
+            if let Some(reason) = self.state.game_over_reason() {
+                self.draw_topped_out();
+                let message = self.game_over_message(reason);
+                self.prompt_new_game(&message);
             }
+            std::thread::sleep(self.limiter.remaining(self.clock.now()));
         }
     }
 
     fn handle_input(&mut self) {
         use controls::*;
-        match input::read() {
-            LEFT => self.state.move_left(),
-            RIGHT => self.state.move_right(),
-            DOWN => self.state.move_down(),
-            BOTTOM => self.state.move_bottom(),
-            ROTATE => self.state.rotate(),
+        let key = input::read();
+        if key != input::Character::Timeout && self.input_window.is_some() {
+            if self.input_log.len() == INPUT_LOG_SIZE {
+                self.input_log.pop_front();
+            }
+            self.input_log.push_back(key);
+        }
+        match self.flipped(key) {
+            input::Character::Timeout => {},
+            LEFT => self.act(self.mirrored(Action::MoveLeft)),
+            RIGHT => self.act(self.mirrored(Action::MoveRight)),
+            DOWN => self.act(Action::MoveDown),
+            BOTTOM if self.session.config.hard_drop_enabled() => self.act(Action::HardDrop),
+            REST => self.act(Action::DropToRest),
+            ROTATE => self.rotate_key(Action::RotateCw),
+            ROTATE_CCW => self.rotate_key(Action::RotateCcw),
+            input::Character::ASCII(key @ '0'..='9') if self.session.config.column_keys => self.move_to_key_column(key),
+            HOLD => self.act(Action::Hold),
+            ZONE => self.act(Action::StartZone),
             QUIT => self.quit(),
-            PAUSE => while input::read() != PAUSE {},
+            GRID => self.session.config.show_grid = !self.session.config.show_grid,
+            GHOST => self.toggle_ghost(),
+            MARKERS => self.session.config.column_markers = !self.session.config.column_markers,
+            MIRROR => self.session.config.mirror = !self.session.config.mirror,
+            HELP => self.toggle_controls(),
+            HINT => self.show_hint = !self.show_hint,
+            THEME => self.cycle_theme(),
+            SAVE => self.save_snapshot(),
+            RETRY => self.retry(),
+            HIDE_NEXT => {
+                let hidden = self.state.is_next_hidden();
+                self.change_rule(Step::NextHidden(!hidden));
+            },
+            PAUSE => self.pause(PAUSE),
+            MENU => self.open_menu(),
+            input::Character::FocusOut if self.session.config.pause_on_focus_loss => self.pause(input::Character::FocusIn),
+            _ => {},
+        }
+    }
+
+    /// show the pause overlay and wait for `resume` or the pause key
+    fn pause(&mut self, resume: input::Character) {
+        let y = self.window_rows() / 2;
+        self.state.set_paused(true);
+        self.update_game_window();
+        wattron(self.game_window, A_REVERSE());
+        Ui::draw_centered(self.game_window, y, " PAUSE ");
+        wattroff(self.game_window, A_REVERSE());
+        wrefresh(self.game_window);
+        loop {
+            match input::read() {
+                controls::QUIT => self.quit(),
+                key if key == resume || key == controls::PAUSE => break,
+                _ => {},
+            }
+        }
+        self.state.set_paused(false);
+    }
+
+    /// the pause menu, the game is stopped until it's closed
+    fn open_menu(&mut self) {
+        self.state.set_paused(true);
+        self.update_game_window();
+        let items = ["resume", "restart", "options", "quit"];
+        match self.menu("PAUSED", &items) {
+            Some(1) => self.restart(),
+            Some(2) => self.options_menu(),
+            Some(3) => self.quit(),
             _ => {},
         }
+        self.state.set_paused(false);
+        self.update();
+    }
+
+    /// the settings, changed with LEFT and RIGHT or ENTER and applied at
+    /// once. The ones changed are saved to the configuration on exit
+    fn options_menu(&mut self) {
+        let mut settings = self.settings();
+        let mut selected = 0;
+        loop {
+            let mut items: Vec<String> = settings.iter()
+                .map(|setting| format!("{}: {}", setting.label, setting.editor.text()))
+                .collect();
+            items.push("back".to_string());
+            self.update_game_window();
+            self.draw_menu("OPTIONS", &items, selected);
+            let by = match input::read() {
+                controls::MENU_UP => {
+                    selected = (selected + items.len() - 1) % items.len();
+                    continue;
+                },
+                controls::MENU_DOWN => {
+                    selected = (selected + 1) % items.len();
+                    continue;
+                },
+                controls::LEFT => -1,
+                controls::RIGHT | controls::SELECT => 1,
+                controls::MENU => return,
+                _ => continue,
+            };
+            let setting = match settings.get_mut(selected) {
+                Some(setting) => setting,
+                None if by > 0 => return,
+                None => continue,
+            };
+            setting.editor.change(by);
+            if let Err(err) = self.apply_setting(setting) {
+                setting.editor.change(-by);
+                self.status = Some((err, STATUS_FRAMES));
+            }
+        }
+    }
+
+    /// the lines of the options screen, with the values in use
+    fn settings(&self) -> Vec<Setting> {
+        let config = &self.session.config;
+        vec![
+            Setting::new("grid", "show_grid", Editor::Toggle(config.show_grid)),
+            Setting::new("ghost", "show_ghost", Editor::Toggle(config.show_ghost())),
+            Setting::new("columns", "column_markers", Editor::Toggle(config.column_markers)),
+            Setting { label: "hint", key: None, editor: Editor::Toggle(self.show_hint) },
+            Setting::new("hide next", "hide_next", Editor::Toggle(self.state.is_next_hidden())),
+            Setting::new("controls", "show_controls", Editor::Toggle(config.show_controls())),
+            Setting::new("column keys", "column_keys", Editor::Toggle(config.column_keys)),
+            Setting::new("theme", "theme", Editor::choice(
+                Theme::ALL.iter().map(|theme| theme.name().to_string()).collect(),
+                config.theme.name(),
+            )),
+            Setting::new("profile", "profile", Editor::choice(
+                Profile::ALL.iter().map(|profile| profile.name().to_string()).collect(),
+                config.profile.name(),
+            )),
+            Setting::new("mode", "mode", Editor::choice(Ui::mode_choices(config.mode()), &config.mode().to_string())),
+            Setting::new("start level", "start_level", Editor::Number {
+                value: config.start_level.unwrap_or(1),
+                min: 1,
+                max: MAX_START_LEVEL,
+                step: 1,
+            }),
+            Setting::new("rotation", "rotation_system", Editor::choice(
+                RotationSystem::ALL.iter().map(|system| system.name().to_string()).collect(),
+                config.rotation_system.name(),
+            )),
+            Setting::new("randomizer", "randomizer", Editor::choice(
+                Randomizer::ALL.iter().map(|randomizer| randomizer.name().to_string()).collect(),
+                config.randomizer.name(),
+            )),
+            Setting::new("entry delay", "are_ms", Editor::Number { value: config.are_ms, min: 0, max: 1000, step: 50 }),
+            Setting::new("max fps", "max_fps", Editor::Number { value: config.max_fps() as i32, min: 0, max: 240, step: 10 }),
+        ]
+    }
+
+    /// the modes offered on the options screen, with the one in use if
+    /// it was set to other numbers
+    fn mode_choices(current: Mode) -> Vec<String> {
+        let mut modes: Vec<String> = MODES.iter().map(|mode| mode.to_string()).collect();
+        let current = current.to_string();
+        if !modes.contains(&current) {
+            modes.push(current);
+        }
+        modes
+    }
+
+    /// use the value of `setting` from now on
+    fn apply_setting(&mut self, setting: &Setting) -> Result<(), String> {
+        let value = setting.editor.value();
+        let key = match setting.key {
+            Some(key) => key,
+            None => {
+                self.show_hint = !self.show_hint;
+                return Ok(());
+            },
+        };
+        self.session.config.set(key, &value)?;
+        let config = &self.session.config;
+        match key {
+            "theme" => Ui::initialize_colors(self.caps, config),
+            "hide_next" => self.change_rule(Step::NextHidden(config.hide_next)),
+            "are_ms" => self.change_rule(Step::EntryDelay(config.are_ms)),
+            "max_fps" => self.limiter = FrameLimiter::new(config.max_fps()),
+            "mode" | "start_level" | "rotation_system" | "randomizer" => {
+                self.status = Some(("from the next game".to_string(), STATUS_FRAMES));
+            },
+            _ => {},
+        }
+        self.remember_setting(key, value);
+        Ok(())
+    }
+
+    /// save `key = value` to the configuration on exit
+    fn remember_setting(&mut self, key: &'static str, value: String) {
+        self.changed_settings.retain(|(changed, _)| *changed != key);
+        self.changed_settings.push((key, value));
+    }
+
+    fn toggle_ghost(&mut self) {
+        let shown = self.session.config.show_ghost();
+        self.session.config.show_ghost = Some(!shown);
+    }
+
+    fn toggle_controls(&mut self) {
+        let shown = self.session.config.show_controls();
+        self.session.config.show_controls = Some(!shown);
+    }
+
+    /// show a list of items over the board, returns the index of the one
+    /// chosen with enter, or None if the menu was closed with escape
+    fn menu<T: AsRef<str>>(&self, title: &str, items: &[T]) -> Option<usize> {
+        self.menu_from(title, items, 0)
+    }
+
+    /// draw the items over the board with `selected` highlighted, in a
+    /// box as wide as the longest item, up to the whole board. When they
+    /// don't fit in the window, the ones around `selected` are shown
+    fn draw_menu<T: AsRef<str>>(&self, title: &str, items: &[T], selected: usize) {
+        let longest = items.iter().map(|item| item.as_ref().len() as i32 + 2).max().unwrap_or(0);
+        let width = longest.max(GAME_WIDTH as i32 * 2 - 4).min(GAME_WIDTH as i32 * 2);
+        let left = 1 + layout::center_x(GAME_WIDTH as i32 * 2, width);
+        let shown = (self.window_rows() as usize).clamp(1, items.len().max(1));
+        let first = (selected + 1).saturating_sub(shown);
+        let top = ((self.window_rows() - shown as i32) / 2).max(1);
+        mvwprintw(self.game_window, top - 1, left, &format!("{:^1$}", title, width as usize));
+        for (i, item) in items.iter().enumerate().skip(first).take(shown) {
+            let attributes = if i == selected { A_REVERSE() } else { A_NORMAL() };
+            wattron(self.game_window, attributes);
+            mvwprintw(self.game_window, top + (i - first) as i32, left, &format!(" {:<1$}", item.as_ref(), width as usize - 1));
+            wattroff(self.game_window, attributes);
+        }
+        wrefresh(self.game_window);
+    }
+
+    fn menu_from<T: AsRef<str>>(&self, title: &str, items: &[T], mut selected: usize) -> Option<usize> {
+        loop {
+            self.draw_menu(title, items, selected);
+            match input::read() {
+                controls::MENU_UP => selected = (selected + items.len() - 1) % items.len(),
+                controls::MENU_DOWN => selected = (selected + 1) % items.len(),
+                controls::SELECT => return Some(selected),
+                controls::MENU => return None,
+                _ => {},
+            }
+        }
+    }
+
+    fn save_snapshot(&mut self) {
+        let message = match self.session.save_snapshot(self.state.snapshot()) {
+            Ok(()) => "position saved".to_string(),
+            Err(err) => err,
+        };
+        self.status = Some((message, STATUS_FRAMES));
+    }
+
+    /// go back to the saved position
+    fn retry(&mut self) {
+        let result = match self.session.snapshot() {
+            Some(snapshot) => self.state.restore(snapshot).map_err(|err| err.to_string()),
+            None => Err("no saved position, press S to save one".to_string()),
+        };
+        match result {
+            // the replay can't go through a jump, it starts again from here
+            Ok(()) => self.replay = Replay::new(&self.state),
+            Err(err) => self.status = Some((err, STATUS_FRAMES)),
+        }
+    }
+
+    /// change a rule of the game while playing, recording it in the replay
+    /// so it plays back the same
+    fn change_rule(&mut self, step: Step) {
+        self.replay.record(step);
+        step.run(&mut self.state);
+    }
+
+    /// apply an input of the player to the game, recording it in the replay
+    fn act(&mut self, action: Action) {
+        self.replay.record(Step::Action(action));
+        match action {
+            Action::RotateCw => self.rotate(true),
+            Action::RotateCcw => self.rotate(false),
+            _ => self.state.apply(action),
+        }
+    }
+
+    /// move the piece to the column of a number key, 1 for the first one
+    /// on the screen and 0 for the tenth, recording the moves made
+    fn move_to_key_column(&mut self, key: char) {
+        let column = (key as i32 - '1' as i32).rem_euclid(10);
+        let cells: Vec<i32> = self.state.current_piece().absolute_cells(self.state.piece_set()).map(|cell| cell.x).collect();
+        let width = cells.iter().max().unwrap_or(&0) - cells.iter().min().unwrap_or(&0) + 1;
+        // mirrored, the left of the piece on the screen is its right side
+        let target = if self.session.config.mirror { GAME_WIDTH as i32 - column - width } else { column };
+        let moved = self.state.move_to_column(target);
+        let action = if moved < 0 { Action::MoveLeft } else { Action::MoveRight };
+        for _ in 0..moved.abs() {
+            self.replay.record(Step::Action(action));
+        }
+    }
+
+    /// rotate for a key of the player, unless the key is held down
+    fn rotate_key(&mut self, action: Action) {
+        let now = self.clock.now();
+        if self.rotate_guards[(action == Action::RotateCcw) as usize].allow(now) {
+            self.act(self.mirrored(action));
+        }
+    }
+
+    fn rotate(&mut self, clockwise: bool) {
+        let rotation = if clockwise { self.state.rotate_cw() } else { self.state.rotate_ccw() };
+        if let Some(rotation) = rotation {
+            if rotation.kick != (Point { x: 0, y: 0 }) || rotation.spin != Spin::None {
+                self.rotation_feedback = Some((rotation, ROTATION_FEEDBACK_FRAMES));
+            }
+        }
+    }
+
+    /// warn when the stack gets close to the top, and stop when it goes
+    /// back down
+    fn update_danger(&mut self) {
+        let danger = self.state.stack_reaches(self.session.config.danger_rows());
+        if danger && !self.danger && self.session.config.danger_beep {
+            beep();
+        }
+        self.danger = danger;
+    }
+
+    /// compute the suggested placement again when the falling piece changes
+    fn update_hint(&mut self) {
+        if !self.show_hint {
+            return;
+        }
+        let key = (self.state.pieces_placed(), self.state.hold_count());
+        if self.hint.is_none_or(|(hint_key, _)| hint_key != key) {
+            self.hint = Some((key, ai::best_move(&self.state)));
+        }
+    }
+
+    fn handle_events(&mut self) {
+        for event in self.state.take_events() {
+            match event {
+                GameEvent::PieceLocked { cells, field } => {
+                    self.flash_locked(&cells, &field);
+                    self.draw_cascade();
+                },
+                GameEvent::Scored(breakdown) => self.score_breakdown = Some((breakdown, BREAKDOWN_FRAMES)),
+                // a kick shown is about the piece that locked
+                GameEvent::PieceSpawned { .. } => self.rotation_feedback = None,
+            }
+        }
     }
 
     fn initialize_cursess() {
@@ -94,105 +914,1032 @@ impl Ui {
         noecho();
         curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE); 
         timeout(50);            // wait 50ms for input
+        // after initscr, which installs a handler of its own
+        unsafe {
+            libc::signal(libc::SIGINT, on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t);
+        }
     }
 
-    fn initialize_colors() {
+    /// set the color pairs of the pieces and the garbage from the theme,
+    /// also to switch theme while playing
+    fn initialize_colors(caps: Capabilities, config: &Config) {
+        if !caps.colors {
+            return;
+        }
         use_default_colors();
-        start_color();
-        init_pair(1, COLOR_CYAN, -1);
-        init_pair(2, COLOR_YELLOW, -1);
-        init_pair(3, 203, -1);
-        init_pair(4, COLOR_BLUE, -1);
-        init_pair(5, COLOR_MAGENTA, -1);
-        init_pair(6, COLOR_GREEN, -1);
-        init_pair(7, COLOR_RED, -1);
+        let available = |color: i16| available_color(color, caps.color_count);
+        let colors = config.theme.colors();
+        for (&pair, &color) in PIECE_PAIRS.iter().zip(colors.iter()) {
+            init_pair(pair, available(color), -1);
+        }
+        init_pair(GARBAGE_COLOR, available(colors[PIECE_COLORS]), -1);
+        init_pair(TOP_OUT_COLOR, COLOR_WHITE, COLOR_RED);
+        init_pair(DANGER_COLOR, COLOR_RED, -1);
+        if let Some(color) = config.ghost_color {
+            init_pair(GHOST_COLOR, available(color), -1);
+        }
+        if let Some(color) = config.border_color {
+            init_pair(BORDER_COLOR, available(color), -1);
+        }
+        if let Some(color) = config.focus_color {
+            init_pair(FOCUS_COLOR, available(color), -1);
+        }
+        if Ui::uses_half_blocks(caps, config) {
+            let slot_color = |slot: usize| match slot {
+                GARBAGE_SLOT => available(colors[PIECE_COLORS]),
+                TOP_OUT_SLOT => COLOR_RED,
+                // dark gray unless a color is set
+                GHOST_SLOT => available(config.ghost_color.unwrap_or(8)),
+                slot => available(colors[slot]),
+            };
+            for top in 0..HALF_BLOCK_SLOTS {
+                init_pair(Ui::half_block_pair(top, None), slot_color(top), -1);
+                for bottom in 0..HALF_BLOCK_SLOTS {
+                    init_pair(Ui::half_block_pair(top, Some(bottom)), slot_color(top), slot_color(bottom));
+                }
+            }
+        }
+    }
+
+    /// whether the board is drawn with half blocks: if the configuration
+    /// asks for it and the terminal has UTF-8 and the pairs of colors. Not
+    /// in items mode, a half block has no room for the mark of an item
+    fn uses_half_blocks(caps: Capabilities, config: &Config) -> bool {
+        let pairs = HALF_BLOCK_PAIRS as i32 + (HALF_BLOCK_SLOTS * (HALF_BLOCK_SLOTS + 1)) as i32;
+        config.render_style == RenderStyle::HalfBlock && !config.items && caps.utf8 && caps.colors && COLOR_PAIRS() >= pairs
+    }
+
+    /// color pair of a half block cell with `top` over `bottom`, None for
+    /// the terminal background
+    fn half_block_pair(top: usize, bottom: Option<usize>) -> i16 {
+        let bottom = bottom.unwrap_or(HALF_BLOCK_SLOTS);
+        HALF_BLOCK_PAIRS + (top * (HALF_BLOCK_SLOTS + 1) + bottom) as i16
+    }
+
+    /// switch to the next theme and repaint everything at once
+    fn cycle_theme(&mut self) {
+        let theme = self.session.config.theme.next();
+        self.session.config.theme = theme;
+        self.remember_setting("theme", theme.name().to_string());
+        Ui::initialize_colors(self.caps, &self.session.config);
+        self.status = Some((format!("theme: {}", theme.name()), STATUS_FRAMES));
+        self.update();
+    }
+
+    /// write `text` centered on row `y` of `window`, cut to its width
+    fn draw_centered(window: WINDOW, y: i32, text: &str) {
+        let width = getmaxx(window);
+        let text = layout::clip(text, width);
+        mvwprintw(window, y, layout::center_x(width, text.chars().count() as i32), text);
     }
 
     fn print_title() {
-        let start = COLS() / 2 - 26;
-        let mut x = start;
-        let mut y = 1;
-        for c in TITLE.chars() {
-            match c {
-                '\n' => { y += 1; x = start },
-                ' ' => x += 1,
-                '1'..='7' => { 
-                    attron(COLOR_PAIR(c as i16 - 48));
-                    mvaddch(y, x, BLOCK); 
-                    attroff(COLOR_PAIR(c as i16 - 48));
-                    x += 1 
-                }, 
-                _ => { mvaddch(y, x, c as chtype); x += 1 },
-            }
-        }
-    }
-
-    fn create_score_window() -> WINDOW {
-        let x = COLS() / 2 + 4;
+        let width = TITLE.lines().map(|line| line.trim_end().len()).max().unwrap_or(0);
+        let start = layout::center_x(COLS(), width as i32);
+        for (y, line) in TITLE.lines().enumerate() {
+            // the end of the lines is left out on a narrow terminal
+            for (x, c) in layout::clip(line, COLS() - start).chars().enumerate() {
+                let (y, x) = (y as i32 + 1, start + x as i32);
+                match c {
+                    ' ' => {},
+                    '1'..='7' => {
+                        // the color of the n-th piece
+                        let color = COLOR_PAIR(PIECE_PAIRS[PieceShape(c as usize - '1' as usize).color_index()]);
+                        attron(color);
+                        mvaddch(y, x, BLOCK);
+                        attroff(color);
+                    },
+                    _ => { mvaddch(y, x, c as chtype); },
+                }
+            }
+        }
+    }
+
+    /// exit with an error if the terminal can't fit even a scrolling board
+    fn check_terminal_size() {
+        let (lines, cols) = (MIN_VISIBLE_ROWS + 2, GAME_WINDOW_WIDTH + 1 + MIN_SCORE_WIDTH);
+        if LINES() < lines || COLS() < cols {
+            endwin();
+            eprintln!("the terminal is too small: {}x{}, at least {}x{} is needed", COLS(), LINES(), cols, lines);
+            std::process::exit(1);
+        }
+    }
+
+    /// row of the top of the windows, below the title if there is room
+    /// for the whole board under it
+    fn window_top() -> i32 {
+        if LINES() >= TITLE_HEIGHT + GAME_HEIGHT as i32 + 2 { TITLE_HEIGHT } else { 0 }
+    }
+
+    /// height of the windows from `top`, cut to the bottom of the terminal
+    fn window_height(top: i32) -> i32 {
+        (GAME_HEIGHT as i32 + 2).min(LINES() - top)
+    }
+
+    /// the board and the narrowest score window are centered together
+    fn game_window_x() -> i32 {
+        layout::center_x(COLS(), GAME_WINDOW_WIDTH + 1 + MIN_SCORE_WIDTH)
+    }
+
+    fn create_score_window(top: i32) -> WINDOW {
+        let x = Ui::game_window_x() + GAME_WINDOW_WIDTH + 1;
         let width = COLS() - x;
-        newwin(10, width, 7, x)
+        newwin(Ui::window_height(top), width, top, x)
+    }
+
+    /// at the left of the board, where there is nothing else
+    fn create_input_window(top: i32) -> WINDOW {
+        newwin((INPUT_LOG_SIZE as i32 + 1).min(LINES() - top), INPUT_LOG_WIDTH, top, 0)
+    }
+
+    /// with half blocks only as high as half the board, the score window
+    /// next to it keeps its height
+    fn create_game_window(top: i32, half_blocks: bool) -> WINDOW {
+        let height = if half_blocks {
+            ((GAME_HEIGHT as i32 + 1) / 2 + 2).min(LINES() - top)
+        } else {
+            Ui::window_height(top)
+        };
+        newwin(height, GAME_WINDOW_WIDTH, top, Ui::game_window_x())
+    }
+
+    /// rows inside the border of the game window
+    fn window_rows(&self) -> i32 {
+        getmaxy(self.game_window) - 2
+    }
+
+    /// draw the two halves of the board cell at (y, x), if it's in view
+    fn put_cell(&self, y: i32, x: i32, left: chtype, right: chtype) {
+        if let Some(row) = self.viewport.get().row(y) {
+            let (row, x) = (self.screen_row(row), self.screen_column(x));
+            mvwaddch(self.game_window, row, x * 2 + 1, left);
+            mvwaddch(self.game_window, row, x * 2 + 2, right);
+        }
+    }
+
+    /// row of the window where row `row` of the viewport is drawn, under
+    /// the border. With half blocks a row of the window holds two rows of
+    /// the viewport, `row` is the one of the window
+    fn screen_row(&self, row: i32) -> i32 {
+        if self.session.config.flip {
+            self.window_rows() - row
+        } else {
+            row + 1
+        }
+    }
+
+    /// rows of the window borders on the side of the top and of the bottom
+    /// of the board
+    fn border_rows(&self) -> (i32, i32) {
+        let bottom = self.window_rows() + 1;
+        if self.session.config.flip { (bottom, 0) } else { (0, bottom) }
+    }
+
+    /// on the flipped board the up and down keys are swapped, so the
+    /// key towards the stack still moves the piece down
+    fn flipped(&self, key: input::Character) -> input::Character {
+        match key {
+            controls::DOWN if self.session.config.flip => controls::ROTATE,
+            controls::ROTATE if self.session.config.flip => controls::DOWN,
+            key => key,
+        }
     }
 
-    fn create_game_window() -> WINDOW {
-        let x = COLS() / 2 - 19;
-        let height = GAME_HEIGHT as i32 + 2;
-        let width = GAME_WIDTH as i32 * 2 + 2;
-        newwin(height, width, 7, x)
+    /// column of the window where board column `x` is drawn
+    fn screen_column(&self, x: i32) -> i32 {
+        if self.session.config.mirror {
+            GAME_WIDTH as i32 - 1 - x
+        } else {
+            x
+        }
+    }
+
+    /// the action that looks like `action` on the mirrored or flipped
+    /// board
+    fn mirrored(&self, action: Action) -> Action {
+        action.as_drawn(self.session.config.mirror, self.session.config.flip)
+    }
+
+    fn draw_cell(&self, y: usize, x: usize, cell: FieldCell) {
+        let grid_dot = (if self.caps.utf8 { ACS_BULLET() } else { '.' as chtype }) | A_DIM();
+        let faint_block = (if self.caps.utf8 { ACS_CKBOARD() } else { ':' as chtype }) | A_DIM();
+        let (left, right, col) = match cell {
+            FieldCell::Empty if self.session.config.show_grid => (grid_dot, ' ' as chtype, 0),
+            FieldCell::Empty => match self.session.config.background {
+                Background::Space => (' ' as chtype, ' ' as chtype, 0),
+                Background::Dot => ('.' as chtype | A_DIM(), ' ' as chtype, 0),
+                Background::Block => (faint_block, faint_block, 0),
+            },
+            FieldCell::Occupied(p) => (self.block(p), self.block(p), PIECE_PAIRS[p.color_index()]),
+            FieldCell::Painted(p, color) => (self.block(p), self.block(p), PIECE_PAIRS[color as usize % PIECE_COLORS]),
+            FieldCell::Garbage => (BLOCK, BLOCK, GARBAGE_COLOR),
+            FieldCell::Item(p, item) => {
+                let symbol = match item {
+                    Item::ClearBottom => '_',
+                    Item::ClearColumn => '|',
+                    Item::Bomb => '*',
+                } as chtype | A_REVERSE() | A_BOLD();
+                (symbol, symbol, PIECE_PAIRS[p.color_index()])
+            },
+        };
+        wattron(self.game_window, COLOR_PAIR(col));
+        self.put_cell(y as i32, x as i32, left, right);
+        wattroff(self.game_window, COLOR_PAIR(col));
+    }
+
+    /// draw a settled board instead of the one of the game
+    fn draw_field(&self, field: &Field) {
+        if self.half_blocks {
+            self.draw_half_blocks(|y, x| theme::color_slot(field[y][x]));
+            return;
+        }
+        for (y, row) in field.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                self.draw_cell(y, x, cell);
+            }
+        }
+    }
+
+    /// draw the board in view two rows at a time, each a half block of
+    /// the color `slot` gives for the cell at (y, x), None for empty
+    fn draw_half_blocks(&self, slot: impl Fn(usize, usize) -> Option<usize>) {
+        let viewport = self.viewport.get();
+        let (top, rows) = (viewport.top(), viewport.rows());
+        let at = |y: usize, x: usize| if y < top + rows { slot(y, x) } else { None };
+        for row in 0..rows.div_ceil(2) {
+            let (mut upper, mut lower) = (top + row * 2, top + row * 2 + 1);
+            if self.session.config.flip {
+                std::mem::swap(&mut upper, &mut lower);
+            }
+            for x in 0..GAME_WIDTH {
+                let (block, fg, bg) = half_block(at(upper, x), at(lower, x));
+                let pair = fg.map_or(0, |fg| Ui::half_block_pair(fg, bg));
+                let column = HALF_BLOCK_LEFT + self.screen_column(x as i32);
+                wattron(self.game_window, COLOR_PAIR(pair));
+                mvwaddstr(self.game_window, self.screen_row(row as i32), column, &block.to_string());
+                wattroff(self.game_window, COLOR_PAIR(pair));
+            }
+        }
+    }
+
+    /// half of a block of the shape, without colors the pieces are
+    /// told apart by their letter
+    fn block(&self, shape: PieceShape) -> chtype {
+        if self.caps.colors {
+            return BLOCK;
+        }
+        shape.letter() as chtype | A_REVERSE()
+    }
+
+    /// color pair of the board border, red when the stack is close to the
+    /// top and with the focus color while the game is running. None keeps
+    /// the plain border
+    fn border_color(&self, focused: bool) -> Option<i16> {
+        let config = &self.session.config;
+        if !self.caps.colors {
+            None
+        } else if self.danger {
+            Some(DANGER_COLOR)
+        } else if focused && config.focus_color.is_some() {
+            Some(FOCUS_COLOR)
+        } else {
+            config.border_color.map(|_| BORDER_COLOR)
+        }
     }
 
     fn update_game_window(&self) {
-        box_(self.game_window, 0, 0);
+        let focused = !self.state.is_paused() && !self.state.is_lost();
+        draw_border(self.game_window, self.border_color(focused));
+        self.scroll_to_piece();
+        if let Some(animation) = self.state.clear_animation() {
+            self.draw_clear_animation(animation);
+            wnoutrefresh(self.game_window);
+            return;
+        }
+        if self.session.config.column_markers {
+            self.draw_column_markers();
+        }
+        if self.half_blocks {
+            // the ghost is a gray block, there's no room for the marks of
+            // the hint in half a character
+            let ghost = self.session.config.show_ghost().then(|| self.state.ghost_piece());
+            let shapes = self.state.piece_set();
+            self.draw_half_blocks(|y, x| match self.state.get(y, x) {
+                FieldCell::Empty if ghost.is_some_and(|ghost| ghost.contains(shapes, Point { x: x as i32, y: y as i32 })) => {
+                    Some(GHOST_SLOT)
+                },
+                cell => theme::color_slot(cell),
+            });
+            wnoutrefresh(self.game_window);
+            return;
+        }
         for y in 0..GAME_HEIGHT {
             for x in 0..GAME_WIDTH {
-                let (c, col) = match self.state.get(y, x) {
-                    FieldCell::Empty => (' ' as chtype, 0),
-                    FieldCell::Occupied(p) => (BLOCK, p as i16 + 1),
-                };
-                wattron(self.game_window, COLOR_PAIR(col));
-                mvwaddch(self.game_window, y as i32 + 1, x as i32 * 2 + 1, c);
-                mvwaddch(self.game_window, y as i32 + 1, x as i32 * 2 + 2, c);
-                wattroff(self.game_window, COLOR_PAIR(col));
+                self.draw_cell(y, x, self.state.get(y, x));
+            }
+        }
+        // every cell is drawn again, so turning the ghost off leaves nothing behind
+        if self.session.config.show_ghost() {
+            self.draw_ghost();
+        }
+        if self.show_hint {
+            self.draw_hint();
+        }
+        wnoutrefresh(self.game_window);
+    }
+
+    /// keep the falling piece in view when the board doesn't fit, with
+    /// arrows on the border where rows are hidden
+    fn scroll_to_piece(&self) {
+        let mut viewport = self.viewport.get();
+        let cells = self.state.current_piece().cells(self.state.piece_set());
+        if let (Some(first), Some(last)) = (cells.iter().map(|c| c.y).min(), cells.iter().map(|c| c.y).max()) {
+            viewport.follow(first, last);
+        }
+        self.viewport.set(viewport);
+        let (top, bottom) = self.border_rows();
+        let (up, down) = if self.session.config.flip { ('v', '^') } else { ('^', 'v') };
+        if viewport.hides_above() {
+            mvwaddch(self.game_window, top, GAME_WINDOW_WIDTH / 2, up as chtype);
+        }
+        if viewport.hides_below() {
+            mvwaddch(self.game_window, bottom, GAME_WINDOW_WIDTH / 2, down as chtype);
+        }
+    }
+
+    /// arrows on the border at the top of the board over the columns of
+    /// the falling piece
+    fn draw_column_markers(&self) {
+        let piece = self.state.current_piece();
+        let color = COLOR_PAIR(PIECE_PAIRS[piece.color_index()]) | A_BOLD();
+        let arrow = match (self.session.config.flip, self.caps.utf8) {
+            (false, true) => ACS_DARROW(),
+            (false, false) => 'v' as chtype,
+            (true, true) => ACS_UARROW(),
+            (true, false) => '^' as chtype,
+        };
+        let (row, _) = self.border_rows();
+        wattron(self.game_window, color);
+        for cell in piece.absolute_cells(self.state.piece_set()) {
+            let x = self.screen_column(cell.x);
+            if self.half_blocks {
+                mvwaddch(self.game_window, row, HALF_BLOCK_LEFT + x, arrow);
+            } else {
+                mvwaddch(self.game_window, row, x * 2 + 1, arrow);
+                mvwaddch(self.game_window, row, x * 2 + 2, arrow);
+            }
+        }
+        wattroff(self.game_window, color);
+    }
+
+    /// outline where the falling piece lands, in the empty cells
+    fn draw_ghost(&self) {
+        let config = &self.session.config;
+        let ghost = self.state.ghost_piece();
+        let color = match config.ghost_color {
+            Some(_) => COLOR_PAIR(GHOST_COLOR),
+            None => COLOR_PAIR(PIECE_PAIRS[ghost.color_index()]) | A_DIM(),
+        };
+        let CellChars(left, right) = config.ghost_chars;
+        wattron(self.game_window, color);
+        for cell in ghost.absolute_cells(self.state.piece_set()) {
+            if self.state.get(cell.y as usize, cell.x as usize) == FieldCell::Empty {
+                self.put_cell(cell.y, cell.x, left as chtype, right as chtype);
+            }
+        }
+        wattroff(self.game_window, color);
+    }
+
+    /// outline the suggested landing cells that are still empty
+    fn draw_hint(&self) {
+        let landing = match self.hint {
+            Some((_, Some(hint))) => hint.landing,
+            _ => return,
+        };
+        let color = COLOR_PAIR(PIECE_PAIRS[landing.color_index()]) | A_BOLD();
+        wattron(self.game_window, color);
+        for cell in landing.absolute_cells(self.state.piece_set()) {
+            if self.state.get(cell.y as usize, cell.x as usize) == FieldCell::Empty {
+                self.put_cell(cell.y, cell.x, '<' as chtype, '>' as chtype);
+            }
+        }
+        wattroff(self.game_window, color);
+    }
+
+    /// the board as it was before the line clear, with the cleared rows
+    /// lit up and then empty. The next piece isn't shown yet
+    fn draw_clear_animation(&self, animation: &ClearAnimation) {
+        if self.half_blocks {
+            self.draw_half_blocks(|y, x| match animation.phase {
+                ClearPhase::Flash if animation.rows.contains(&y) => Some(GARBAGE_SLOT),
+                ClearPhase::Collapse if animation.rows.contains(&y) => None,
+                _ => theme::color_slot(animation.field[y][x]),
+            });
+            return;
+        }
+        self.draw_field(&animation.field);
+        for &y in &animation.rows {
+            for x in 0..GAME_WIDTH {
+                match animation.phase {
+                    ClearPhase::Flash => {
+                        let flash = BLOCK | A_BOLD();
+                        wattron(self.game_window, COLOR_PAIR(GARBAGE_COLOR));
+                        self.put_cell(y as i32, x as i32, flash, flash);
+                        wattroff(self.game_window, COLOR_PAIR(GARBAGE_COLOR));
+                    },
+                    ClearPhase::Collapse => self.draw_cell(y, x, FieldCell::Empty),
+                }
             }
         }
+    }
+
+    /// briefly highlight the cells of the piece that was just locked, on
+    /// the board before the clear so the cells are still where it locked
+    fn flash_locked(&self, cells: &[Point], field: &Field) {
+        if self.half_blocks {
+            self.draw_half_blocks(|y, x| if cells.contains(&Point { x: x as i32, y: y as i32 }) {
+                Some(GARBAGE_SLOT)
+            } else {
+                theme::color_slot(field[y][x])
+            });
+            wrefresh(self.game_window);
+            napms(LOCK_FLASH_MS);
+            return;
+        }
+        self.draw_field(field);
+        let flash = BLOCK | A_BOLD();
+        wattron(self.game_window, COLOR_PAIR(GARBAGE_COLOR));
+        for cell in cells {
+            self.put_cell(cell.y, cell.x, flash, flash);
+        }
+        wattroff(self.game_window, COLOR_PAIR(GARBAGE_COLOR));
+        wrefresh(self.game_window);
+        napms(LOCK_FLASH_MS);
+    }
+
+    /// show the clears and falls of a cascade one after the other, a
+    /// single clear has nothing to show
+    fn draw_cascade(&self) {
+        if self.state.cascade_steps().len() < 2 {
+            return;
+        }
+        for step in self.state.cascade_steps() {
+            match step {
+                CascadeStep::Clear { field, .. } | CascadeStep::Fall { field } => self.draw_field(field),
+            }
+            wrefresh(self.game_window);
+            napms(CASCADE_STEP_MS);
+        }
+    }
+
+    /// draw the piece that ended the game over the stack
+    fn draw_topped_out(&self) {
+        let piece = match self.state.topped_out_piece() {
+            Some(piece) => piece,
+            None => return,
+        };
+        if self.half_blocks {
+            let shapes = self.state.piece_set();
+            self.draw_half_blocks(|y, x| if piece.contains(shapes, Point { x: x as i32, y: y as i32 }) {
+                Some(TOP_OUT_SLOT)
+            } else {
+                theme::color_slot(self.state.field_cell(y, x))
+            });
+            wrefresh(self.game_window);
+            return;
+        }
+        wattron(self.game_window, COLOR_PAIR(TOP_OUT_COLOR) | A_BOLD());
+        for cell in piece.absolute_cells(self.state.piece_set()) {
+            if (0..GAME_HEIGHT as i32).contains(&cell.y) && (0..GAME_WIDTH as i32).contains(&cell.x) {
+                self.put_cell(cell.y, cell.x, '>' as chtype, '<' as chtype);
+            }
+        }
+        wattroff(self.game_window, COLOR_PAIR(TOP_OUT_COLOR) | A_BOLD());
         wrefresh(self.game_window);
     }
 
     fn update_score_window(&self) {
-        wclear(self.score_window);
-        mvwprintw(self.score_window, 1, 0, &format!("level: {}", self.state.level));
-        mvwprintw(self.score_window, 2, 0, &format!("score: {}", self.state.score));
-        mvwprintw(self.score_window, 4, 0, CONTROLS);
-        wrefresh(self.score_window);
+        werase(self.score_window);
+        if self.session.config.cheese_rows.is_some() {
+            mvwprintw(self.score_window, 0, 0, &format!("cheese: {} rows left", self.state.cheese_left()));
+        } else if let Some(goal) = self.session.config.sprint_lines {
+            let left = (goal - self.state.lines_cleared()).max(0);
+            mvwprintw(self.score_window, 0, 0, &format!("sprint: {} lines left", left));
+        }
+        let mut fields = self.session.config.hud.0.clone();
+        if self.session.config.show_pps && !fields.contains(&HudField::Pps) {
+            fields.push(HudField::Pps);
+        }
+        if self.state.is_zone_enabled() && !fields.contains(&HudField::Zone) {
+            fields.push(HudField::Zone);
+        }
+        if self.state.rewinds() > 0 && !fields.contains(&HudField::Rewinds) {
+            fields.push(HudField::Rewinds);
+        }
+        let mut row = 1;
+        // top row of a preview box that has room for another one beside it
+        let mut beside = None;
+        for field in fields {
+            let preview = field == HudField::Next || field == HudField::Hold;
+            match beside {
+                Some(top) if preview => {
+                    self.print_hud_field(field, top, HOLD_BOX_X);
+                    beside = None;
+                },
+                _ => {
+                    beside = if preview { Some(row) } else { None };
+                    row += self.print_hud_field(field, row, 0);
+                },
+            }
+        }
+        self.print_rotation_feedback(row);
+        self.print_score_breakdown();
+        if let Some((message, _)) = &self.status {
+            mvwprintw(self.score_window, row, BREAKDOWN_X, message);
+        }
+        // the window is erased above, so hiding the keys leaves nothing behind
+        let controls = if self.session.config.show_controls() { self.controls() } else { "?: show keys".to_string() };
+        mvwprintw(self.score_window, row + 1, 0, &controls);
+        wnoutrefresh(self.score_window);
+    }
+
+    /// the help of the keys, without the ones turned off
+    fn controls(&self) -> String {
+        let hard_drop = self.session.config.hard_drop_enabled();
+        let column_keys = self.session.config.column_keys;
+        CONTROLS.lines()
+            .filter(|line| hard_drop || !line.starts_with("SPACE"))
+            .filter(|line| column_keys || !line.starts_with("1-0"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// print a field of the score window from row `top`, returns the rows it took
+    fn print_hud_field(&self, field: HudField, top: i32, left: i32) -> i32 {
+        match field {
+            HudField::Level => {
+                let max = if self.state.is_max_level() { " (max)" } else { "" };
+                mvwprintw(self.score_window, top, left, &format!("level: {}{}", self.state.level, max));
+                mvwprintw(self.score_window, top, HOLD_BOX_X, &format!("high: {}", self.session.high_score()));
+                1
+            },
+            HudField::Score => {
+                mvwprintw(self.score_window, top, left, &format!("score: {}", self.state.score));
+                mvwprintw(self.score_window, top, HOLD_BOX_X, &format!("best: {}", self.session.best_score()));
+                self.print_level_progress(top + 1);
+                2
+            },
+            HudField::Lines => {
+                mvwprintw(self.score_window, top, left, &format!("lines: {}", self.state.lines_cleared()));
+                1
+            },
+            HudField::Time => {
+                let (label, time) = match self.state.time_left() {
+                    Some(time_left) => ("time left", time_left),
+                    None => ("time", self.state.elapsed()),
+                };
+                mvwprintw(self.score_window, top, left, &format!("{}: {}", label, format_time(time)));
+                1
+            },
+            HudField::Combo => {
+                mvwprintw(self.score_window, top, left, &format!("combo: {}", self.state.combo()));
+                1
+            },
+            HudField::Pps => {
+                self.print_speed(top);
+                1
+            },
+            HudField::Next => {
+                self.print_next_piece(top, left);
+                PIECE_SIZE as i32 + 1
+            },
+            HudField::Hold => {
+                self.print_held_piece(top, left);
+                PIECE_SIZE as i32 + 1
+            },
+            HudField::Zone => {
+                self.print_zone(top, left);
+                1
+            },
+            HudField::Drop => {
+                let next = self.state.time_to_next_drop().as_millis();
+                mvwprintw(self.score_window, top, left, &format!("next drop: {}ms", next));
+                1
+            },
+            HudField::Bag => {
+                self.print_bag(top, left);
+                1
+            },
+            HudField::Rewinds => {
+                let text = format!("rewinds: {}/{}", self.state.rewinds_left(), self.state.rewinds());
+                mvwprintw(self.score_window, top, left, &text);
+                1
+            },
+            HudField::Attack => {
+                mvwprintw(self.score_window, top, left, &format!("sent: {}", self.state.lines_sent()));
+                mvwprintw(self.score_window, top, HOLD_BOX_X, &format!("received: {}", self.state.lines_received()));
+                1
+            },
+        }
+    }
+
+    fn print_zone(&self, top: i32, left: i32) {
+        if !self.state.is_zone_enabled() {
+            mvwprintw(self.score_window, top, left, "zone: off");
+            return;
+        }
+        let text = match self.state.zone_time_left() {
+            Some(time_left) => format!("ZONE {:.1}s  lines: {}", time_left.as_secs_f32(), self.state.zone_lines()),
+            None => {
+                let charge = self.state.zone_charge() as usize;
+                let meter = "#".repeat(charge) + &"-".repeat(ZONE_CHARGE_LINES as usize - charge);
+                let ready = if charge == ZONE_CHARGE_LINES as usize { " Z!" } else { "" };
+                format!("zone: [{}]{}", meter, ready)
+            },
+        };
+        mvwprintw(self.score_window, top, left, &text);
+    }
+
+    /// the shapes left in the bag, each in its color
+    fn print_bag(&self, top: i32, left: i32) {
+        mvwprintw(self.score_window, top, left, "bag:");
+        if self.session.config.randomizer != Randomizer::Bag {
+            wprintw(self.score_window, " off");
+            return;
+        }
+        for &shape in self.state.remaining_bag() {
+            waddch(self.score_window, ' ' as chtype);
+            wattron(self.score_window, COLOR_PAIR(PIECE_PAIRS[shape.color_index()]));
+            waddch(self.score_window, shape.letter() as chtype);
+            wattroff(self.score_window, COLOR_PAIR(PIECE_PAIRS[shape.color_index()]));
+        }
+    }
+
+    fn print_speed(&self, top: i32) {
+        let elapsed = self.state.elapsed();
+        let fps = self.frames as f32 / elapsed.as_secs_f32().max(1.0);
+        let pps = self.state.pieces_per_second();
+        mvwprintw(self.score_window, top, 0, &format!("pps: {:.2}  fps: {:.1}", pps, fps));
+    }
+
+    fn print_next_piece(&self, top: i32, left: i32) {
+        let next = match self.state.next_piece() {
+            Some(next) => next,
+            None => {
+                mvwprintw(self.score_window, top, left, "next: HIDDEN");
+                mvwprintw(self.score_window, top + 2, left + 2, "+---+");
+                mvwprintw(self.score_window, top + 3, left + 2, "| ? |");
+                mvwprintw(self.score_window, top + 4, left + 2, "+---+");
+                return;
+            }
+        };
+        mvwprintw(self.score_window, top, left, "next:");
+        self.draw_piece(top + 1, left + 2, next);
+    }
+
+    fn print_rotation_feedback(&self, top: i32) {
+        if let Some((rotation, _)) = self.rotation_feedback {
+            let spin = match rotation.spin {
+                Spin::None => "",
+                Spin::Mini => "MINI T-SPIN",
+                Spin::Full => "T-SPIN",
+            };
+            // offsets are shown with y going up, as in the SRS tables
+            let kick = format!("kick: {:+},{:+}", rotation.kick.x, -rotation.kick.y);
+            wattron(self.score_window, A_BOLD());
+            mvwprintw(self.score_window, top, 0, &format!("{}  {}", kick, spin));
+            wattroff(self.score_window, A_BOLD());
+        }
+    }
+
+    fn print_score_breakdown(&self) {
+        let breakdown = match self.score_breakdown {
+            Some((breakdown, _)) => breakdown,
+            None => return,
+        };
+        let name = match breakdown.lines {
+            _ if breakdown.zone > 0 => "lines",
+            0 => "no lines",
+            1 => "single",
+            2 => "double",
+            3 => "triple",
+            _ => "tetris",
+        };
+        let parts = [
+            (name, breakdown.base),
+            ("t-spin", breakdown.tspin),
+            ("combo", breakdown.combo),
+            ("b2b", breakdown.b2b),
+            ("perfect", breakdown.perfect),
+            ("zone", breakdown.zone),
+            ("holes", breakdown.holes),
+        ];
+        let mut y = 5;
+        wattron(self.score_window, A_BOLD());
+        for (name, points) in parts.iter().filter(|(_, points)| *points != 0) {
+            mvwprintw(self.score_window, y, BREAKDOWN_X, &format!("{:<8} {:+}", name, points));
+            y += 1;
+        }
+        mvwprintw(self.score_window, y, BREAKDOWN_X, &format!("{:<8} {:+}", "total", breakdown.total()));
+        wattroff(self.score_window, A_BOLD());
+    }
+
+    /// the held piece, dimmed while hold was already used for this piece
+    fn print_held_piece(&self, top: i32, left: i32) {
+        let available = self.state.hold_available();
+        if !available {
+            wattron(self.score_window, A_DIM());
+        }
+        mvwprintw(self.score_window, top, left, "hold:");
+        if let Some(held) = self.state.held_piece() {
+            self.draw_piece(top + 1, left + 2, held);
+        }
+        if !available {
+            wattroff(self.score_window, A_DIM());
+        }
+    }
+
+    /// draw a piece in its spawn orientation in the score window, flipped
+    /// in place with the board
+    fn draw_piece(&self, top: i32, left: i32, piece: Piece) {
+        let shape = piece.shape();
+        let shapes = self.state.piece_set();
+        let columns: Vec<usize> = (0..PIECE_SIZE)
+            .filter(|&x| (0..PIECE_SIZE).any(|y| shapes.preview(shape, y, x)))
+            .collect();
+        let rows: Vec<usize> = (0..PIECE_SIZE)
+            .filter(|&y| (0..PIECE_SIZE).any(|x| shapes.preview(shape, y, x)))
+            .collect();
+        let (first, last) = (columns.first().copied().unwrap_or(0), columns.last().copied().unwrap_or(0));
+        let (first_row, last_row) = (rows.first().copied().unwrap_or(0), rows.last().copied().unwrap_or(0));
+        let color = COLOR_PAIR(PIECE_PAIRS[piece.color_index()]);
+        wattron(self.score_window, color);
+        for y in 0..PIECE_SIZE {
+            for x in 0..PIECE_SIZE {
+                if shapes.preview(shape, y, x) {
+                    let x = if self.session.config.mirror { first + last - x } else { x };
+                    let y = if self.session.config.flip { first_row + last_row - y } else { y };
+                    mvwaddch(self.score_window, y as i32 + top, x as i32 * 2 + left, self.block(shape));
+                    mvwaddch(self.score_window, y as i32 + top, x as i32 * 2 + left + 1, self.block(shape));
+                }
+            }
+        }
+        wattroff(self.score_window, color);
+    }
+
+    fn print_level_progress(&self, top: i32) {
+        if self.state.is_max_level() && self.session.config.level_cap == LevelCap::Stop {
+            mvwprintw(self.score_window, top, 0, "level up: max level");
+            return;
+        }
+        let remaining = self.state.points_to_next_level();
+        let filled = (POINTS_PER_LEVEL - remaining) * PROGRESS_BAR_WIDTH / POINTS_PER_LEVEL;
+        let bar: String = (0..PROGRESS_BAR_WIDTH)
+            .map(|i| if i < filled { '#' } else { '-' })
+            .collect();
+        mvwprintw(self.score_window, top, 0, &format!("level up: [{}] {} pts", bar, remaining));
+    }
+
+    /// end of game statistics, returns the row below them
+    fn print_stats(&self, top: i32) -> i32 {
+        let stats = [
+            format!("score:  {}", self.state.score),
+            format!("level:  {}", self.state.level),
+            format!("lines:  {}", self.state.lines_cleared()),
+            format!("pieces: {}", self.state.pieces_placed()),
+            format!("holds:  {}", self.state.hold_count()),
+            format!("finesse faults: {}", self.state.finesse_faults()),
+            format!("session best: {} in {} games", self.session.best_score(), self.session.games_played()),
+            format!("high score: {}", self.session.high_score()),
+        ];
+        for (i, line) in stats.iter().enumerate() {
+            mvwprintw(self.score_window, top + i as i32, 0, line);
+        }
+        top + stats.len() as i32
     }
 
+    /// the best scores with a header, on the seven rows from `top`
+    fn print_high_scores(&self, top: i32) {
+        let entries = self.session.high_scores().entries();
+        if entries.is_empty() {
+            return;
+        }
+        mvwprintw(self.score_window, top, 0, &format!("{:<6} {:>6} {:<8} {}", "name", "score", "mode", "date"));
+        for (i, entry) in entries.iter().take(HIGH_SCORE_ROWS).enumerate() {
+            let name: String = entry.name.chars().take(6).collect();
+            let date = if entry.timestamp == 0 { "-".to_string() } else { entry.date() };
+            let line = format!("{:<6} {:>6} {:<8} {}", name, entry.score, entry.mode, date);
+            mvwprintw(self.score_window, top + 1 + i as i32, 0, &line);
+        }
+    }
+
+    /// repaint the windows, the screen changes in a single refresh
     fn update(&self) {
         self.update_game_window();
         self.update_score_window();
+        if let Some(window) = self.input_window {
+            self.update_input_window(window);
+        }
+        doupdate();
+    }
+
+    fn update_input_window(&self, window: WINDOW) {
+        werase(window);
+        mvwprintw(window, 0, 0, "input:");
+        for (i, &key) in self.input_log.iter().rev().enumerate() {
+            mvwprintw(window, i as i32 + 1, 0, &input::describe(key));
+        }
+        wnoutrefresh(window);
     }
 
     fn quit(&self) {
+        if self.session.config.pause_on_focus_loss {
+            putp(FOCUS_REPORTING_OFF);
+        }
         endwin();
+        for (key, value) in &self.changed_settings {
+            if let Err(err) = Config::save_setting(key, value) {
+                eprintln!("{}", err);
+            }
+        }
+        if let Some(err) = self.session.high_scores_error() {
+            eprintln!("high scores not saved, {}", err);
+        }
+        if self.session.config.debug {
+            eprintln!("terminal capabilities: {:?}", self.caps);
+        }
         std::process::exit(0);
     }
 
-    fn prompt_new_game(&mut self) {
-        mvwprintw(self.score_window, 1, 0, &format!("You lost :( score: {}", self.state.score));
-        mvwprintw(self.score_window, 2, 0, "play another game? (y/n)");
-        if input::read_yes_no() {
-            self.state = GameState::new();
-            self.update();
-        } else {
-            self.quit();
+    fn game_over_message(&self, reason: GameOverReason) -> String {
+        let score = self.state.score;
+        match reason {
+            GameOverReason::BlockOut => format!("Block out :( score: {}", score),
+            GameOverReason::LockOut => format!("Lock out :( score: {}", score),
+            GameOverReason::TimeUp => format!("Time up! score: {}", score),
+            GameOverReason::Completed if self.state.is_cheese_cleared() => {
+                format!("Cheese cleared in {} pieces!", self.state.pieces_placed())
+            },
+            GameOverReason::Completed => {
+                format!("{} lines in {}!", self.state.lines_cleared(), format_time(self.state.elapsed()))
+            },
         }
     }
+
+    /// offer to take the top out back, true if the game was rewound. The
+    /// timers stop while the question is up
+    fn prompt_rewind(&mut self) -> bool {
+        self.state.set_paused(true);
+        werase(self.score_window);
+        mvwprintw(self.score_window, 1, 0, &format!("Topped out! rewind {} pieces? (y/n)", REWIND_PIECES));
+        mvwprintw(self.score_window, 2, 0, &format!("rewinds left: {}", self.state.rewinds_left()));
+        wrefresh(self.score_window);
+        let answer = input::read_one_of(&['y', 'n']);
+        self.state.set_paused(false);
+        if answer == 'n' {
+            return false;
+        }
+        self.act(Action::Rewind);
+        self.hint = None;
+        self.update();
+        true
+    }
+
+    fn prompt_new_game(&mut self, message: &str) {
+        self.state.set_paused(true);
+        self.session.finish_game(&self.state);
+        let export_error = self.session.config.export_on_loss.as_ref()
+            .and_then(|path| std::fs::write(path, self.final_board(message)).err())
+            .map(|err| format!("board not saved: {}", err));
+        let replay_error = self.session.config.export_replay.as_ref().and_then(|path| match self.replay.to_share_string() {
+            Some(text) => std::fs::write(path, text + "\n").err().map(|err| format!("replay not saved: {}", err)),
+            None => Some("replay not saved, not the default rules".to_string()),
+        });
+        loop {
+            werase(self.score_window);
+            mvwprintw(self.score_window, 1, 0, message);
+            let bottom = self.print_stats(3);
+            mvwprintw(self.score_window, bottom + 1, 0, "play another game? (y/n)");
+            mvwprintw(self.score_window, bottom + 2, 0, "W: watch the replay");
+            for (row, err) in export_error.iter().chain(&replay_error).enumerate() {
+                mvwprintw(self.score_window, bottom + 4 + row as i32, 0, err);
+            }
+            wrefresh(self.score_window);
+            match input::read_one_of(&['y', 'n', 'w']) {
+                'y' => return self.restart(),
+                'n' => self.quit(),
+                _ => self.watch_replay(),
+            }
+        }
+    }
+
+    /// the end of the game as text for `--export-on-loss`: the message,
+    /// the stats and the board
+    fn final_board(&self, message: &str) -> String {
+        format!(
+            "{}\nmode:   {}\nscore:  {}\nlevel:  {}\nlines:  {}\npieces: {}\ntime:   {}\n\n{}\n",
+            message,
+            self.session.mode(),
+            self.state.score,
+            self.state.level,
+            self.state.lines_cleared(),
+            self.state.pieces_placed(),
+            format_time(self.state.elapsed()),
+            self.state,
+        )
+    }
+
+    /// play the last game back: SPACE pauses, LEFT and RIGHT go a piece
+    /// back and forward, L goes to the next line clear, Q or ESC stop
+    fn watch_replay(&mut self) {
+        let mut player = ReplayPlayer::new(self.replay.clone());
+        let game = std::mem::replace(&mut self.state, player.state().clone());
+        // what was shown for the last moves of the game
+        self.hint = None;
+        self.rotation_feedback = None;
+        self.score_breakdown = None;
+        let mut paused = false;
+        loop {
+            match input::read() {
+                input::Character::Timeout if !paused => {
+                    paused = !player.step_frame();
+                },
+                controls::BOTTOM | controls::PAUSE => paused = !paused,
+                controls::RIGHT => player.next_piece(),
+                controls::LEFT => player.previous_piece(),
+                input::Character::ASCII('l') => {
+                    player.next_clear();
+                },
+                controls::QUIT | controls::MENU => break,
+                _ => {},
+            }
+            self.state = player.state().clone();
+            self.update_game_window();
+            self.update_score_window();
+            self.print_replay_position(&player, paused);
+            doupdate();
+        }
+        self.state = game;
+        self.update_game_window();
+        doupdate();
+        self.draw_topped_out();
+    }
+
+    /// where the replay is, over the score window
+    fn print_replay_position(&self, player: &ReplayPlayer, paused: bool) {
+        let filled = if player.is_empty() { 0 } else { player.position() * PROGRESS_BAR_WIDTH as usize / player.len() };
+        let bar: String = (0..PROGRESS_BAR_WIDTH as usize).map(|i| if i < filled { '#' } else { '-' }).collect();
+        let state = if paused { "PAUSED" } else { "" };
+        wmove(self.score_window, 0, 0);
+        wclrtoeol(self.score_window);
+        mvwprintw(self.score_window, 0, 0, &format!("replay: piece {}/{} [{}] {}", player.piece() + 1, player.pieces(), bar, state));
+        wnoutrefresh(self.score_window);
+    }
+
+    fn restart(&mut self) {
+        (self.state, self.replay) = Ui::new_game(&self.session.config);
+        self.hint = None;
+        self.score_breakdown = None;
+        self.danger = false;
+        self.reset_clock();
+        self.update();
+    }
 }
 
+/// draw the border of `window`, in color pair `color` if given
+fn draw_border(window: WINDOW, color: Option<i16>) {
+    if let Some(color) = color {
+        wattron(window, COLOR_PAIR(color));
+    }
+    box_(window, 0, 0);
+    if let Some(color) = color {
+        wattroff(window, COLOR_PAIR(color));
+    }
+}
+
+/// a time as MM:SS
+fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+// scores listed on the start screen
+const HIGH_SCORE_ROWS: usize = 6;
+
 const CONTROLS: &str = "\
 LEFT/RIGHT: move left/right
-UP: rotate piece
-DOWN: move down
+UP/X: rotate right/left
+DOWN/D: move down/to stack
+1-0: move to a column
 SPACE: fast down
-P: pause game
+C/Z: hold piece/zone
+P/ESC: pause/menu
+G/O/M: grid/ghost/columns
+T: next theme
+H: toggle hint
+S/R: save/retry position
+N/I: hide next/mirror
+?: hide these keys
 Q: quit";
 
 const TITLE: &str = "\
@@ -0,0 +1,70 @@
+/// rows kept visible above and below the followed rows when scrolling
+const SCROLL_MARGIN: usize = 2;
+
+/// the rows of the board shown in a window that can be shorter than the
+/// board, scrolled to keep the falling piece in view
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    board_rows: usize,
+    rows: usize,
+    top: usize,
+}
+
+impl Viewport {
+    /// a view of at most `rows` rows of a board of `board_rows` rows,
+    /// starting at the bottom of the board
+    pub fn new(board_rows: usize, rows: usize) -> Viewport {
+        let rows = rows.min(board_rows);
+        Viewport {
+            board_rows,
+            rows,
+            top: board_rows - rows,
+        }
+    }
+
+    /// number of rows shown
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// first board row shown
+    pub fn top(&self) -> usize {
+        self.top
+    }
+
+    /// true if there are rows of the board above the view
+    pub fn hides_above(&self) -> bool {
+        self.top > 0
+    }
+
+    /// true if there are rows of the board below the view
+    pub fn hides_below(&self) -> bool {
+        self.top + self.rows < self.board_rows
+    }
+
+    /// scroll as little as possible to show the board rows from `first`
+    /// to `last`, with a margin around them when there is room for it.
+    /// If they don't fit, the last ones are shown
+    pub fn follow(&mut self, first: i32, last: i32) {
+        let first = first.max(0) as usize;
+        let last = (last.max(0) as usize).max(first).min(self.board_rows - 1);
+        let span = last - first + 1;
+        let margin = SCROLL_MARGIN.min(self.rows.saturating_sub(span) / 2);
+        if span > self.rows || last + margin >= self.top + self.rows {
+            self.top = last + margin + 1 - self.rows;
+        } else if first < self.top + margin {
+            self.top = first.saturating_sub(margin);
+        }
+        self.top = self.top.min(self.board_rows - self.rows);
+    }
+
+    /// row of the view where board row `y` is, None if it's scrolled out
+    pub fn row(&self, y: i32) -> Option<i32> {
+        let top = self.top as i32;
+        if (top..top + self.rows as i32).contains(&y) {
+            Some(y - top)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! JavaScript bindings of the game, built with the `wasm` feature:
+//!
+//! ```text
+//! wasm-pack build --target web -- --features wasm
+//! ```
+//!
+//! `examples/wasm/index.html` plays a game with them in a browser.
+
+use std::rc::Rc;
+use std::time::Duration;
+
+use wasm_bindgen::prelude::*;
+
+use crate::clock::Clock;
+use crate::game::{FieldCell, GameState, GAME_HEIGHT, GAME_WIDTH};
+
+/// value of a garbage cell in `Game::field_snapshot`
+const GARBAGE_CELL: u8 = 255;
+
+/// the time of the browser, there is no system clock on wasm32
+struct BrowserClock;
+
+impl Clock for BrowserClock {
+    fn now(&self) -> Duration {
+        Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+    }
+}
+
+/// a game driven from JavaScript, which calls `tick` every 50 ms and
+/// `apply` for the keys of the player
+#[wasm_bindgen]
+pub struct Game {
+    state: GameState,
+}
+
+#[wasm_bindgen]
+impl Game {
+    /// a new game with the pieces drawn from `seed`
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u64) -> Game {
+        let mut state = GameState::with_seed(seed);
+        state.set_clock(Rc::new(BrowserClock));
+        Game { state }
+    }
+
+    /// apply an action by name: `left`, `right`, `down`, `rest`, `drop`,
+    /// `cw`, `ccw`, `hold` or `zone`. False if the name is unknown
+    pub fn apply(&mut self, action: &str) -> bool {
+        match action.parse() {
+            Ok(action) => {
+                self.state.apply(action);
+                true
+            },
+            Err(()) => false,
+        }
+    }
+
+    /// move the game on by one tick of the clock
+    pub fn tick(&mut self) {
+        self.state.clock_tick();
+        self.state.take_events();
+    }
+
+    /// the board with the falling piece, row by row from the top: 0 for an
+    /// empty cell, the shape number plus one for a piece and 255 for garbage
+    pub fn field_snapshot(&self) -> Vec<u8> {
+        let mut cells = Vec::with_capacity(GAME_WIDTH * GAME_HEIGHT);
+        for y in 0..GAME_HEIGHT {
+            for x in 0..GAME_WIDTH {
+                cells.push(match self.state.get(y, x) {
+                    FieldCell::Empty => 0,
+                    FieldCell::Occupied(shape) | FieldCell::Painted(shape, _) | FieldCell::Item(shape, _) => shape.0 as u8 + 1,
+                    FieldCell::Garbage => GARBAGE_CELL,
+                });
+            }
+        }
+        cells
+    }
+
+    pub fn width(&self) -> usize {
+        GAME_WIDTH
+    }
+
+    pub fn height(&self) -> usize {
+        GAME_HEIGHT
+    }
+
+    pub fn score(&self) -> i32 {
+        self.state.score
+    }
+
+    pub fn lines(&self) -> i32 {
+        self.state.lines_cleared()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.state.is_lost()
+    }
+}
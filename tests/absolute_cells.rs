@@ -0,0 +1,31 @@
+use tetris_rust::game::{GameState, Point, GAME_WIDTH};
+
+#[test]
+fn cells_follow_the_piece_to_the_walls() {
+    for seed in 0..20 {
+        let mut state = GameState::with_seed(seed);
+        for _ in 0..GAME_WIDTH {
+            state.move_left();
+        }
+        let cells: Vec<Point> = state.current_piece().absolute_cells(state.piece_set()).collect();
+        assert_eq!(cells.len(), 4);
+        assert_eq!(cells.iter().map(|cell| cell.x).min(), Some(0), "seed {}", seed);
+
+        for _ in 0..GAME_WIDTH {
+            state.move_right();
+        }
+        let piece = state.current_piece();
+        let cells: Vec<Point> = piece.absolute_cells(state.piece_set()).collect();
+        assert_eq!(cells.iter().map(|cell| cell.x).max(), Some(GAME_WIDTH as i32 - 1), "seed {}", seed);
+        assert!(cells.iter().all(|&cell| piece.contains(state.piece_set(), cell)));
+    }
+}
+
+#[test]
+fn cells_are_translated_with_the_piece() {
+    let state = GameState::with_seed(3);
+    let piece = state.current_piece();
+    let moved = piece.with_position(piece.position().translated(-2, 5));
+    let expected: Vec<Point> = piece.absolute_cells(state.piece_set()).map(|cell| cell.translated(-2, 5)).collect();
+    assert_eq!(moved.cells(state.piece_set()), expected);
+}
@@ -0,0 +1,29 @@
+use tetris_rust::game::Action;
+
+#[test]
+fn action_names_parse_back() {
+    for &action in Action::ALL.iter() {
+        assert_eq!(action.name().parse(), Ok(action));
+    }
+    assert_eq!("hard drop".parse::<Action>(), Err(()));
+    assert_eq!("".parse::<Action>(), Err(()));
+}
+
+#[test]
+fn a_mirror_or_a_flip_turns_rotations_around() {
+    let turns = |mirrored, flipped| (Action::RotateCw.as_drawn(mirrored, flipped), Action::RotateCcw.as_drawn(mirrored, flipped));
+    assert_eq!(turns(false, false), (Action::RotateCw, Action::RotateCcw));
+    assert_eq!(turns(true, false), (Action::RotateCcw, Action::RotateCw));
+    assert_eq!(turns(false, true), (Action::RotateCcw, Action::RotateCw));
+    assert_eq!(turns(true, true), (Action::RotateCw, Action::RotateCcw));
+}
+
+#[test]
+fn only_a_mirror_swaps_left_and_right() {
+    assert_eq!(Action::MoveLeft.as_drawn(true, false), Action::MoveRight);
+    assert_eq!(Action::MoveRight.as_drawn(true, true), Action::MoveLeft);
+    assert_eq!(Action::MoveLeft.as_drawn(false, true), Action::MoveLeft);
+    for &action in &[Action::MoveDown, Action::HardDrop, Action::Hold] {
+        assert_eq!(action.as_drawn(true, true), action);
+    }
+}
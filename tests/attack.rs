@@ -0,0 +1,68 @@
+pub mod common;
+
+use common::i_piece_game;
+use tetris_rust::game::{attack_lines, ClearType, FieldCell, GAME_HEIGHT, GAME_WIDTH};
+
+#[test]
+fn line_clears_send_their_lines() {
+    assert_eq!(attack_lines(ClearType::Lines(0), 0, false), 0);
+    assert_eq!(attack_lines(ClearType::Lines(1), 0, false), 0);
+    assert_eq!(attack_lines(ClearType::Lines(2), 0, false), 1);
+    assert_eq!(attack_lines(ClearType::Lines(3), 0, false), 2);
+    assert_eq!(attack_lines(ClearType::Lines(4), 0, false), 4);
+}
+
+#[test]
+fn t_spins_send_twice_their_lines() {
+    assert_eq!(attack_lines(ClearType::TSpin(0), 0, false), 0);
+    assert_eq!(attack_lines(ClearType::TSpin(1), 0, false), 2);
+    assert_eq!(attack_lines(ClearType::TSpin(2), 0, false), 4);
+    assert_eq!(attack_lines(ClearType::TSpin(3), 0, false), 6);
+    assert_eq!(attack_lines(ClearType::TSpinMini(1), 0, false), 0);
+    assert_eq!(attack_lines(ClearType::TSpinMini(2), 0, false), 1);
+}
+
+#[test]
+fn back_to_back_only_adds_to_difficult_clears() {
+    assert_eq!(attack_lines(ClearType::Lines(4), 0, true), 5);
+    assert_eq!(attack_lines(ClearType::TSpin(1), 0, true), 3);
+    assert_eq!(attack_lines(ClearType::TSpin(2), 0, true), 5);
+    assert_eq!(attack_lines(ClearType::TSpin(3), 0, true), 7);
+    assert_eq!(attack_lines(ClearType::TSpinMini(2), 0, true), 2);
+    assert_eq!(attack_lines(ClearType::Lines(1), 0, true), 0);
+    assert_eq!(attack_lines(ClearType::Lines(2), 0, true), 1);
+    assert_eq!(attack_lines(ClearType::Lines(3), 0, true), 2);
+    // nothing is sent without lines, whatever the streaks
+    assert_eq!(attack_lines(ClearType::TSpin(0), 3, true), 0);
+}
+
+#[test]
+fn combos_add_lines_up_to_a_limit() {
+    assert_eq!(attack_lines(ClearType::Lines(1), 1, false), 1);
+    assert_eq!(attack_lines(ClearType::Lines(1), 3, false), 2);
+    assert_eq!(attack_lines(ClearType::Lines(2), 4, false), 3);
+    assert_eq!(attack_lines(ClearType::Lines(1), 10, false), 5);
+    assert_eq!(attack_lines(ClearType::Lines(1), 50, false), 5);
+}
+
+#[test]
+fn the_game_counts_the_lines_sent_and_received() {
+    let mut state = i_piece_game();
+    let columns: Vec<usize> = state.ghost_piece().absolute_cells(state.piece_set()).map(|cell| cell.x as usize).collect();
+    for y in GAME_HEIGHT - 2..GAME_HEIGHT {
+        for x in (0..GAME_WIDTH).filter(|x| !columns.contains(x)) {
+            state.set_cell(y, x, FieldCell::Garbage).unwrap();
+        }
+    }
+    // a single then a single again, the second one a combo
+    state.move_bottom();
+    state.move_bottom();
+    assert_eq!(state.lines_cleared(), 2);
+    assert_eq!(state.lines_sent(), 1);
+
+    state.add_garbage(3);
+    assert_eq!(state.lines_received(), 3);
+
+    state.reset();
+    assert_eq!((state.lines_sent(), state.lines_received()), (0, 0));
+}
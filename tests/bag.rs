@@ -0,0 +1,55 @@
+use std::collections::HashSet;
+
+use tetris_rust::game::{GameState, PieceShape};
+use tetris_rust::source::{BagSource, PieceSource};
+
+#[test]
+fn every_bag_has_each_shape_once() {
+    let mut source = BagSource::with_seed(1);
+    for _ in 0..5 {
+        let bag: HashSet<PieceShape> = (0..7).map(|_| source.next_shape(7)).collect();
+        assert_eq!(bag.len(), 7);
+        assert!(source.remaining_bag().is_empty());
+    }
+}
+
+#[test]
+fn the_bag_empties_as_pieces_are_drawn_and_refills() {
+    let mut source = BagSource::with_seed(2);
+    assert!(source.remaining_bag().is_empty());
+    let first = source.next_shape(7);
+    assert_eq!(source.remaining_bag().len(), 6);
+    assert!(!source.remaining_bag().contains(&first));
+    let mut drawn = vec![first];
+    while !source.remaining_bag().is_empty() {
+        let left = source.remaining_bag().to_vec();
+        let shape = source.next_shape(7);
+        assert!(left.contains(&shape));
+        drawn.push(shape);
+    }
+    assert_eq!(drawn.len(), 7);
+    source.next_shape(7);
+    assert_eq!(source.remaining_bag().len(), 6);
+}
+
+#[test]
+fn the_game_shows_what_comes_after_the_next_piece() {
+    let mut state = GameState::with_seed(0);
+    assert!(state.remaining_bag().is_empty());
+    state.set_source(Box::new(BagSource::with_seed(3)));
+    // the falling and the next piece came out of the bag
+    assert_eq!(state.remaining_bag().len(), 5);
+    let mut seen = vec![state.current_piece().shape(), state.next_piece().unwrap().shape()];
+    seen.extend_from_slice(state.remaining_bag());
+    seen.sort_by_key(|shape| shape.0);
+    assert_eq!(seen, (0..7).map(PieceShape).collect::<Vec<_>>());
+
+    let coming = state.remaining_bag().to_vec();
+    for _ in 0..5 {
+        state.move_bottom();
+        assert!(coming.contains(&state.next_piece().unwrap().shape()));
+    }
+    assert!(state.remaining_bag().is_empty());
+    state.move_bottom();
+    assert_eq!(state.remaining_bag().len(), 6);
+}
@@ -0,0 +1,71 @@
+pub mod common;
+
+use common::i_piece_game;
+use tetris_rust::game::{FieldCell, GameError, GameState, GAME_HEIGHT, GAME_WIDTH, SPAWN_ROWS};
+
+#[test]
+fn set_cell_changes_one_cell() {
+    let mut state = GameState::with_seed(0);
+    assert_eq!(state.set_cell(GAME_HEIGHT - 1, 2, FieldCell::Garbage), Ok(()));
+    assert_eq!(state.field_cell(GAME_HEIGHT - 1, 2), FieldCell::Garbage);
+    assert_eq!(state.field_cell(GAME_HEIGHT - 1, 3), FieldCell::Empty);
+    assert_eq!(state.set_cell(GAME_HEIGHT - 1, 2, FieldCell::Empty), Ok(()));
+    assert_eq!(state.field_cell(GAME_HEIGHT - 1, 2), FieldCell::Empty);
+}
+
+#[test]
+fn set_cell_outside_the_board_fails() {
+    let mut state = GameState::with_seed(0);
+    assert_eq!(state.set_cell(GAME_HEIGHT, 0, FieldCell::Garbage), Err(GameError::OutsideBoard { y: GAME_HEIGHT, x: 0 }));
+    assert_eq!(state.set_cell(0, GAME_WIDTH, FieldCell::Garbage), Err(GameError::OutsideBoard { y: 0, x: GAME_WIDTH }));
+    assert_eq!(state.field_cell(GAME_HEIGHT, 0), FieldCell::Empty);
+}
+
+#[test]
+fn set_cell_leaves_the_falling_piece_alone() {
+    // the I spawns flat in row 1 over columns 3 to 6
+    let mut state = i_piece_game();
+    let piece = state.current_piece();
+    assert_eq!(state.set_cell(1, 4, FieldCell::Garbage), Err(GameError::CoveredByPiece { y: 1, x: 4 }));
+    assert_eq!(state.field_cell(1, 4), FieldCell::Empty);
+    assert_eq!(state.set_cell(0, 4, FieldCell::Garbage), Ok(()));
+    assert_eq!(state.current_piece(), piece);
+    assert!(!state.is_lost());
+}
+
+#[test]
+fn clear_field_keeps_the_falling_piece() {
+    let mut state = GameState::with_seed(0);
+    state.fill_cheese(5, 1);
+    let piece = state.current_piece();
+    state.clear_field();
+    for y in 0..GAME_HEIGHT {
+        for x in 0..GAME_WIDTH {
+            assert_eq!(state.field_cell(y, x), FieldCell::Empty);
+        }
+    }
+    assert_eq!(state.current_piece(), piece);
+}
+
+#[test]
+fn field_snapshot_copies_the_settled_board() {
+    let mut state = GameState::with_seed(0);
+    state.set_cell(GAME_HEIGHT - 1, 2, FieldCell::Garbage).unwrap();
+    state.set_cell(0, 9, FieldCell::Garbage).unwrap();
+    let snapshot = state.field_snapshot(true);
+    assert_eq!(snapshot.len(), GAME_HEIGHT);
+    for (y, row) in snapshot.iter().enumerate() {
+        assert_eq!(row.len(), GAME_WIDTH);
+        for (x, &cell) in row.iter().enumerate() {
+            assert_eq!(cell, state.field_cell(y, x), "cell ({}, {})", y, x);
+        }
+    }
+    // the falling piece isn't part of it
+    let piece = state.current_piece().cells(state.piece_set())[0];
+    assert_eq!(snapshot[piece.y as usize][piece.x as usize], FieldCell::Empty);
+
+    let visible = state.field_snapshot(false);
+    assert_eq!(visible.len(), GAME_HEIGHT - SPAWN_ROWS);
+    assert_eq!(visible[0], snapshot[SPAWN_ROWS]);
+    assert_eq!(visible[GAME_HEIGHT - SPAWN_ROWS - 1][2], FieldCell::Garbage);
+}
@@ -0,0 +1,31 @@
+pub mod common;
+
+use common::o_game;
+use tetris_rust::game::{FieldCell, PieceShape, GAME_HEIGHT, GAME_WIDTH};
+
+#[test]
+fn the_board_is_written_a_row_per_line() {
+    let mut state = o_game();
+    state.set_cell(GAME_HEIGHT - 1, 0, FieldCell::Garbage).unwrap();
+    state.set_cell(GAME_HEIGHT - 1, 1, FieldCell::Occupied(PieceShape::T)).unwrap();
+    let text = state.to_string();
+    let lines: Vec<&str> = text.lines().collect();
+
+    assert_eq!(lines.len(), GAME_HEIGHT + 1);
+    assert_eq!(lines[GAME_HEIGHT - 1], format!("|#T{}|", ".".repeat(GAME_WIDTH - 2)));
+    assert_eq!(lines[GAME_HEIGHT], format!("+{}+", "-".repeat(GAME_WIDTH)));
+    assert!(lines.iter().all(|line| line.len() == GAME_WIDTH + 2));
+}
+
+#[test]
+fn the_falling_piece_is_drawn_on_the_board() {
+    let state = o_game();
+    let text = state.to_string();
+    let cells = state.current_piece().absolute_cells(state.piece_set()).collect::<Vec<_>>();
+
+    assert_eq!(text.matches('O').count(), cells.len());
+    for cell in cells {
+        let line = text.lines().nth(cell.y as usize).unwrap();
+        assert_eq!(line.chars().nth(cell.x as usize + 1), Some('O'));
+    }
+}
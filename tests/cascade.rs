@@ -0,0 +1,115 @@
+pub mod common;
+
+use common::{empty_field, o_game};
+use tetris_rust::game::{CascadeStep, Field, FieldCell, GameState, GAME_HEIGHT, GAME_WIDTH};
+
+const BOTTOM: usize = GAME_HEIGHT - 1;
+
+/// a game of O pieces on `field`, without the cells where the first O
+/// lands on an empty board
+fn o_pieces_on(mut field: Field) -> GameState {
+    let mut state = o_game();
+    state.set_cascade(true);
+    for cell in state.ghost_piece().absolute_cells(state.piece_set()) {
+        field[cell.y as usize][cell.x as usize] = FieldCell::Empty;
+    }
+    state.set_field(field);
+    state
+}
+
+fn filled(cells: &[(usize, usize)]) -> Field {
+    let mut field = empty_field();
+    for &(y, x) in cells {
+        field[y][x] = FieldCell::Garbage;
+    }
+    field
+}
+
+#[test]
+fn a_hanging_block_falls_and_clears_a_second_line() {
+    // the O completes the bottom row; the row above keeps a hole in the
+    // last column, where a loose block three rows up falls after the clear
+    let mut cells: Vec<_> = (0..GAME_WIDTH).map(|x| (BOTTOM, x)).collect();
+    cells.extend((0..GAME_WIDTH - 1).map(|x| (BOTTOM - 1, x)));
+    cells.push((BOTTOM - 3, GAME_WIDTH - 1));
+    let mut state = o_pieces_on(filled(&cells));
+    state.move_bottom();
+
+    let steps: Vec<CascadeStep> = state.cascade_steps().cloned().collect();
+    assert_eq!(steps.len(), 3);
+    match &steps[0] {
+        CascadeStep::Clear { rows, field } => {
+            assert_eq!(rows, &vec![BOTTOM]);
+            assert_eq!(field[BOTTOM - 2][GAME_WIDTH - 1], FieldCell::Garbage);
+            assert_eq!(field[BOTTOM][GAME_WIDTH - 1], FieldCell::Empty);
+        },
+        step => panic!("expected a clear, got {:?}", step),
+    }
+    match &steps[1] {
+        CascadeStep::Fall { field } => {
+            assert_eq!(field[BOTTOM - 2][GAME_WIDTH - 1], FieldCell::Empty);
+            assert!(!field[BOTTOM].contains(&FieldCell::Empty));
+        },
+        step => panic!("expected a fall, got {:?}", step),
+    }
+    match &steps[2] {
+        CascadeStep::Clear { rows, field } => {
+            assert_eq!(rows, &vec![BOTTOM]);
+            assert!(field.iter().flatten().all(|&cell| cell == FieldCell::Empty));
+        },
+        step => panic!("expected a clear, got {:?}", step),
+    }
+    assert_eq!(state.lines_cleared(), 2);
+}
+
+#[test]
+fn touching_blocks_fall_together() {
+    // an L of three blocks hangs over the cleared row, held by nothing
+    let mut cells: Vec<_> = (0..GAME_WIDTH).map(|x| (BOTTOM, x)).collect();
+    cells.extend([(BOTTOM - 4, 0), (BOTTOM - 3, 0), (BOTTOM - 3, 1)]);
+    let mut state = o_pieces_on(filled(&cells));
+    state.move_bottom();
+
+    let steps: Vec<CascadeStep> = state.cascade_steps().cloned().collect();
+    assert_eq!(steps.len(), 2);
+    assert!(matches!(steps[0], CascadeStep::Clear { .. }));
+    assert!(matches!(steps[1], CascadeStep::Fall { .. }));
+    assert_eq!(state.field_cell(BOTTOM - 1, 0), FieldCell::Garbage);
+    assert_eq!(state.field_cell(BOTTOM, 0), FieldCell::Garbage);
+    assert_eq!(state.field_cell(BOTTOM, 1), FieldCell::Garbage);
+    assert_eq!(state.lines_cleared(), 1);
+}
+
+#[test]
+fn no_cascade_without_a_clear() {
+    let mut state = o_pieces_on(filled(&[(BOTTOM - 5, 0)]));
+    state.move_bottom();
+    assert_eq!(state.cascade_steps().count(), 0);
+    assert_eq!(state.field_cell(BOTTOM - 5, 0), FieldCell::Garbage);
+}
+
+#[test]
+fn a_fall_that_clears_nothing_keeps_the_combo() {
+    // the four bottom rows are full but for the columns of the O, so each
+    // O clears two of them, and a loose block falls after the first clear
+    let empty = o_pieces_on(filled(&[]));
+    let columns: Vec<usize> = empty.ghost_piece().absolute_cells(empty.piece_set()).map(|cell| cell.x as usize).collect();
+    let mut cells: Vec<_> = (BOTTOM - 3..=BOTTOM)
+        .flat_map(|y| (0..GAME_WIDTH).map(move |x| (y, x)))
+        .filter(|(_, x)| !columns.contains(x))
+        .collect();
+    cells.push((BOTTOM - 8, 0));
+    let mut state = o_pieces_on(filled(&cells));
+    let mut without_cascade = state.clone();
+    without_cascade.set_cascade(false);
+    state.move_bottom();
+    without_cascade.move_bottom();
+
+    assert!(matches!(state.cascade_steps().last(), Some(CascadeStep::Fall { .. })));
+    assert_eq!(state.field_cell(BOTTOM - 2, 0), FieldCell::Garbage);
+    assert_eq!(state.score, without_cascade.score);
+
+    state.move_bottom();
+    assert_eq!(state.lines_cleared(), 4);
+    assert_eq!(state.combo(), 1);
+}
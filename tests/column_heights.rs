@@ -0,0 +1,66 @@
+pub mod common;
+
+use common::o_game;
+use tetris_rust::game::{FieldCell, GameState, GAME_HEIGHT, GAME_WIDTH};
+
+#[test]
+fn heights_count_from_the_floor_to_the_highest_block() {
+    let mut state = GameState::with_seed(0);
+    assert_eq!(state.column_heights(), [0; GAME_WIDTH]);
+    state.set_cell(GAME_HEIGHT - 1, 0, FieldCell::Garbage).unwrap();
+    // a block over a hole counts the hole too
+    state.set_cell(GAME_HEIGHT - 5, 9, FieldCell::Garbage).unwrap();
+    let heights = state.column_heights();
+    assert_eq!(heights[0], 1);
+    assert_eq!(heights[9], 5);
+    assert_eq!(heights[1..9], [0; 8]);
+}
+
+#[test]
+fn danger_starts_when_the_stack_reaches_the_top_rows() {
+    let mut state = GameState::with_seed(0);
+    state.set_cell(5, 0, FieldCell::Garbage).unwrap();
+    assert!(!state.stack_reaches(4));
+    assert!(!state.stack_reaches(5));
+    assert!(state.stack_reaches(6));
+    assert!(!state.stack_reaches(0));
+}
+
+#[test]
+fn danger_clears_when_the_stack_goes_down() {
+    let mut state = o_game();
+    for y in 3..GAME_HEIGHT {
+        state.set_cell(y, 0, FieldCell::Garbage).unwrap();
+    }
+    // the O dropped on the right completes the bottom row
+    for x in 1..GAME_WIDTH - 2 {
+        state.set_cell(GAME_HEIGHT - 1, x, FieldCell::Garbage).unwrap();
+    }
+    assert!(state.stack_reaches(4));
+    for _ in 0..GAME_WIDTH {
+        state.move_right();
+    }
+    state.move_bottom();
+    assert_eq!(state.lines_cleared(), 1);
+    assert!(!state.stack_reaches(4));
+}
+
+#[test]
+fn heights_and_surface_of_a_stack_with_holes() {
+    // column 0: 3 blocks, column 1: a block over two holes, column 2:
+    // empty, column 3: a single block at the top of the board
+    let mut state = GameState::with_seed(0);
+    for y in GAME_HEIGHT - 3..GAME_HEIGHT {
+        state.set_cell(y, 0, FieldCell::Garbage).unwrap();
+    }
+    state.set_cell(GAME_HEIGHT - 3, 1, FieldCell::Garbage).unwrap();
+    state.set_cell(2, 3, FieldCell::Garbage).unwrap();
+    let heights = state.column_heights();
+    assert_eq!(heights[..4], [3, 3, 0, GAME_HEIGHT - 2]);
+    assert_eq!(heights[4..], [0; GAME_WIDTH - 4]);
+    assert_eq!(state.bitboard().column_heights(), heights);
+
+    let profile = state.surface_profile();
+    assert_eq!(profile[..4], [0, -3, GAME_HEIGHT as i32 - 2, -(GAME_HEIGHT as i32 - 2)]);
+    assert_eq!(profile[4..], [0; GAME_WIDTH - 5]);
+}
@@ -0,0 +1,47 @@
+//! games and boards used by more than one test file, each takes the ones
+//! it needs. It is declared `pub mod common;` so the ones a file doesn't take
+//! aren't reported as unused
+
+use tetris_rust::game::{Field, FieldCell, GameState, PieceShape, GAME_HEIGHT, GAME_WIDTH};
+use tetris_rust::source::FixedSource;
+
+/// a game dealing only `shape`
+pub fn game_of(shape: PieceShape) -> GameState {
+    GameState::with_source(FixedSource::new(vec![shape]))
+}
+
+pub fn o_game() -> GameState {
+    game_of(PieceShape::O)
+}
+
+pub fn i_piece_game() -> GameState {
+    game_of(PieceShape::I)
+}
+
+pub fn t_piece_game() -> GameState {
+    game_of(PieceShape::T)
+}
+
+pub fn s_piece_game() -> GameState {
+    game_of(PieceShape::S)
+}
+
+/// an I piece game with the zone on
+pub fn zone_game() -> GameState {
+    let mut state = i_piece_game();
+    state.set_zone_enabled(true);
+    state
+}
+
+pub fn empty_field() -> Field {
+    [[FieldCell::Empty; GAME_WIDTH]; GAME_HEIGHT]
+}
+
+/// the cells of row `y` of the board
+pub fn row(state: &GameState, y: usize) -> [FieldCell; GAME_WIDTH] {
+    let mut row = [FieldCell::Empty; GAME_WIDTH];
+    for (x, cell) in row.iter_mut().enumerate() {
+        *cell = state.field_cell(y, x);
+    }
+    row
+}
@@ -0,0 +1,41 @@
+use tetris_rust::config::{Config, Mode, MAX_START_LEVEL};
+
+#[test]
+fn modes_are_saved_as_they_are_read() {
+    let modes = [Mode::Marathon, Mode::Cheese(9), Mode::Sprint(40), Mode::Ultra(120)];
+    for &mode in modes.iter() {
+        assert_eq!(mode.to_string().parse(), Ok(mode));
+    }
+    assert_eq!(Mode::Sprint(40).to_string(), "sprint 40");
+    assert_eq!("marathon 3".parse::<Mode>(), Err(()));
+    assert_eq!("sprint".parse::<Mode>(), Err(()));
+    assert_eq!("sprint forty".parse::<Mode>(), Err(()));
+    assert_eq!("survival 10".parse::<Mode>(), Err(()));
+}
+
+#[test]
+fn the_last_game_is_remembered() {
+    let config = Config::parse("last_mode = ultra 180\nlast_zone = true\n").unwrap();
+    assert_eq!(config.last_mode, Some(Mode::Ultra(180)));
+    assert!(config.last_zone);
+}
+
+#[test]
+fn a_last_game_that_cant_be_read_is_forgotten() {
+    let config = Config::parse("last_mode = survival 10\nlast_zone = maybe\ntheme = pastel\n").unwrap();
+    assert_eq!(config.last_mode, None);
+    assert!(!config.last_zone);
+    // the rest of the file is still read
+    assert_eq!(config.theme.name(), "pastel");
+}
+
+#[test]
+fn the_options_screen_settings_are_read_back() {
+    let config = Config::parse("mode = sprint 20\nstart_level = 7\n").unwrap();
+    assert_eq!(config.mode(), Mode::Sprint(20));
+    assert_eq!(config.game_config().start_level, 7);
+    assert_eq!(config.game_config().line_goal, Some(20));
+    assert!(Config::parse("start_level = 0\n").is_err());
+    assert!(Config::parse(&format!("start_level = {}\n", MAX_START_LEVEL + 1)).is_err());
+    assert!(Config::parse("mode = sprint\n").is_err());
+}
@@ -0,0 +1,45 @@
+use tetris_rust::game::{GameState, Point};
+
+#[test]
+fn contains_exactly_the_cells_of_each_piece() {
+    let mut seen = Vec::new();
+    for seed in 0..200 {
+        let mut state = GameState::with_seed(seed);
+        let shape = state.current_piece().shape();
+        if seen.contains(&shape) {
+            continue;
+        }
+        seen.push(shape);
+        for _ in 0..4 {
+            let piece = state.current_piece();
+            let cells = piece.cells(state.piece_set());
+            for &cell in &cells {
+                assert!(piece.contains(state.piece_set(), cell));
+            }
+            // every cell next to the piece that is not one of its blocks
+            for cell in &cells {
+                for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)].iter() {
+                    let next = Point { x: cell.x + dx, y: cell.y + dy };
+                    assert_eq!(piece.contains(state.piece_set(), next), cells.contains(&next));
+                }
+            }
+            state.rotate();
+        }
+    }
+    assert_eq!(seen.len(), 7);
+}
+
+#[test]
+fn contains_nothing_outside_the_piece_grid() {
+    let state = GameState::with_seed(0);
+    let piece = state.current_piece();
+    let p = piece.position();
+    for y in p.y - 3..p.y + 8 {
+        for x in p.x - 3..p.x + 8 {
+            let point = Point { x, y };
+            if piece.contains(state.piece_set(), point) {
+                assert!(piece.cells(state.piece_set()).contains(&point));
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+pub mod common;
+
+use common::{empty_field, i_piece_game};
+use tetris_rust::game::{Field, FieldCell, PieceShape, GAME_HEIGHT};
+
+/// a new game where every piece is the I, lying flat in the second row
+/// of its grid over columns 3 to 6
+/// an empty board with a single block in column 5, at row 12, with
+/// nothing below it
+fn overhang() -> Field {
+    let mut field = empty_field();
+    field[12][5] = FieldCell::Garbage;
+    field
+}
+
+#[test]
+fn drop_distance_on_an_empty_board_reaches_the_floor() {
+    let state = i_piece_game();
+    assert_eq!(state.current_piece().position().y, 0);
+    assert_eq!(state.drop_distance(), GAME_HEIGHT - 2);
+}
+
+#[test]
+fn drop_distance_stops_on_the_highest_column_under_the_piece() {
+    let mut state = i_piece_game();
+    state.set_field(overhang());
+    // columns 3, 4 and 6 are free down to the floor, 5 stops at the overhang
+    assert_eq!(state.drop_distance(), 10);
+    for _ in 0..3 {
+        state.move_left();
+    }
+    assert_eq!(state.drop_distance(), GAME_HEIGHT - 2);
+}
+
+#[test]
+fn hard_drop_lands_where_the_ghost_is() {
+    let mut state = i_piece_game();
+    state.set_field(overhang());
+    let ghost = state.ghost_piece();
+    assert_eq!(ghost.position().y, state.current_piece().position().y + 10);
+    state.move_bottom();
+    for cell in ghost.cells(state.piece_set()) {
+        assert_eq!(state.field_cell(cell.y as usize, cell.x as usize), FieldCell::Occupied(PieceShape::I));
+    }
+}
+
+#[test]
+fn drop_to_rest_never_locks() {
+    let mut state = i_piece_game();
+    state.set_field(overhang());
+    let ghost = state.ghost_piece();
+    state.drop_to_rest_no_lock();
+    // one row above the ghost, not on the stack yet
+    assert_eq!(state.current_piece().position().y, ghost.position().y - 1);
+    assert_eq!(state.current_piece().position().x, ghost.position().x);
+    assert_eq!(state.drop_distance(), 1);
+    let above = state.current_piece();
+    state.drop_to_rest_no_lock();
+    assert_eq!(state.current_piece(), above);
+    assert_eq!(state.pieces_placed(), 0);
+    assert!(state.take_events().is_empty());
+    assert_eq!(state.field_cell(11, 5), FieldCell::Empty);
+
+    // still free to move along the stack, from the row above it
+    for _ in 0..3 {
+        state.move_left();
+    }
+    assert_eq!(state.drop_distance(), GAME_HEIGHT - 2 - 10 + 1);
+}
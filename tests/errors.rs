@@ -0,0 +1,52 @@
+pub mod common;
+
+use common::game_of;
+use std::path::Path;
+use std::rc::Rc;
+use tetris_rust::game::{GameError, GameState, PieceProblem, PieceSet, PieceShape};
+
+const DOMINO: &str = "\
+#. .. .# ##
+#. ## .# ..
+";
+
+fn problem(content: &str) -> Result<PieceProblem, GameError> {
+    match PieceSet::parse(content) {
+        Err(GameError::InvalidPiece { problem, .. }) => Ok(problem),
+        Err(err) => Err(err),
+        Ok(_) => panic!("{:?} was parsed", content),
+    }
+}
+
+#[test]
+fn piece_set_errors() {
+    assert!(PieceSet::parse(DOMINO).is_ok());
+    assert_eq!(PieceSet::parse("\n\n").err(), Some(GameError::NoPieces));
+    assert_eq!(problem("#. .. .# ##\n#. ## .#\n"), Ok(PieceProblem::Rotations(3)));
+    assert_eq!(problem("#. .. .# #x\n#. ## .# ..\n"), Ok(PieceProblem::Character('x')));
+    assert_eq!(problem("#. .. .# #.\n#. ## .# ..\n"), Ok(PieceProblem::DifferentShapes));
+    assert_eq!(problem("#..... #..... #..... #.....\n"), Ok(PieceProblem::TooWide));
+    assert_eq!(problem(&"#. #. #. #.\n".repeat(6)), Ok(PieceProblem::TooTall));
+}
+
+#[test]
+fn piece_set_errors_have_the_line_of_the_piece() {
+    let content = format!("{}\n#. #. #. #x\n", DOMINO);
+    let err = PieceSet::parse(&content).unwrap_err();
+    assert_eq!(err, GameError::InvalidPiece { line: 4, problem: PieceProblem::Character('x') });
+    assert_eq!(err.to_string(), "line 4: invalid character 'x'");
+}
+
+#[test]
+fn missing_piece_set_file() {
+    let err = PieceSet::load(Path::new("/nonexistent/pieces")).unwrap_err();
+    assert!(matches!(err, GameError::Io(_)));
+}
+
+#[test]
+fn restoring_pieces_the_set_does_not_have() {
+    let standard = game_of(PieceShape::Z);
+    let mut dominoes = GameState::with_piece_set(Rc::new(PieceSet::parse(DOMINO).unwrap()));
+    let err = dominoes.restore(&standard.snapshot()).unwrap_err();
+    assert_eq!(err, GameError::UnknownPiece { piece: PieceShape::Z.0 + 1, pieces: 1 });
+}
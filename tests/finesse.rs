@@ -0,0 +1,14 @@
+use std::rc::Rc;
+
+use tetris_rust::finesse::FinesseTable;
+use tetris_rust::game::{PieceSet, RotationSystem};
+
+#[test]
+fn games_of_the_standard_pieces_share_the_table_of_their_rotation_system() {
+    let shapes = PieceSet::standard();
+    for &system in RotationSystem::ALL.iter() {
+        assert!(Rc::ptr_eq(&FinesseTable::shared(&shapes, system), &FinesseTable::shared(&shapes, system)));
+    }
+    let srs = FinesseTable::shared(&shapes, RotationSystem::Srs);
+    assert!(!Rc::ptr_eq(&srs, &FinesseTable::shared(&shapes, RotationSystem::Nintendo)));
+}
@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use tetris_rust::clock::{Clock, FrameLimiter, TestClock};
+
+const FRAME: Duration = Duration::from_millis(20);
+
+#[test]
+fn a_short_frame_sleeps_the_rest_of_its_time() {
+    let clock = TestClock::new();
+    let mut limiter = FrameLimiter::new(50);
+    assert_eq!(limiter.remaining(clock.now()), Duration::from_secs(0));
+    clock.advance(Duration::from_millis(5));
+    assert_eq!(limiter.remaining(clock.now()), FRAME - Duration::from_millis(5));
+}
+
+#[test]
+fn a_long_frame_does_not_shorten_the_next_one() {
+    let clock = TestClock::new();
+    let mut limiter = FrameLimiter::new(50);
+    limiter.remaining(clock.now());
+    clock.advance(Duration::from_millis(70));
+    assert_eq!(limiter.remaining(clock.now()), Duration::from_secs(0));
+    clock.advance(Duration::from_millis(1));
+    assert_eq!(limiter.remaining(clock.now()), FRAME - Duration::from_millis(1));
+}
+
+#[test]
+fn frames_stay_under_the_cap_however_fast_they_are() {
+    let clock = TestClock::new();
+    let mut limiter = FrameLimiter::new(60);
+    let mut frames = 0;
+    while clock.now() < Duration::from_secs(1) {
+        clock.advance(Duration::from_millis(1));
+        let sleep = limiter.remaining(clock.now());
+        clock.advance(sleep);
+        frames += 1;
+    }
+    assert!((59..=61).contains(&frames), "{} frames", frames);
+}
+
+#[test]
+fn no_limit_never_sleeps() {
+    let clock = TestClock::new();
+    let mut limiter = FrameLimiter::new(0);
+    for _ in 0..10 {
+        assert_eq!(limiter.remaining(clock.now()), Duration::from_secs(0));
+        clock.advance(Duration::from_micros(100));
+    }
+}
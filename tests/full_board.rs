@@ -0,0 +1,67 @@
+pub mod common;
+
+use common::o_game;
+use tetris_rust::game::{Field, FieldCell, GameOverReason, GAME_HEIGHT, GAME_WIDTH};
+
+const FULL: Field = [[FieldCell::Garbage; GAME_WIDTH]; GAME_HEIGHT];
+
+#[test]
+fn a_full_board_tops_out_instead_of_locking_the_piece_over_it() {
+    let mut state = o_game();
+    state.set_field(FULL);
+    state.move_down();
+
+    assert_eq!(state.game_over_reason(), Some(GameOverReason::BlockOut));
+    assert_eq!(state.topped_out_piece(), Some(state.current_piece()));
+    // nothing was locked, so the full rows weren't cleared or scored
+    assert_eq!(state.lines_cleared(), 0);
+    assert_eq!(state.pieces_placed(), 0);
+    assert_eq!(state.score, 0);
+    assert!((0..GAME_HEIGHT).all(|y| (0..GAME_WIDTH).all(|x| state.field_cell(y, x) == FieldCell::Garbage)));
+}
+
+#[test]
+fn gravity_and_hard_drop_top_out_on_a_full_board_too() {
+    let mut state = o_game();
+    state.set_field(FULL);
+    for _ in 0..100 {
+        state.clock_tick();
+    }
+    assert_eq!(state.game_over_reason(), Some(GameOverReason::BlockOut));
+
+    let mut state = o_game();
+    state.set_field(FULL);
+    state.move_bottom();
+    assert_eq!(state.game_over_reason(), Some(GameOverReason::BlockOut));
+    assert_eq!(state.lines_cleared(), 0);
+}
+
+#[test]
+fn a_board_full_but_for_a_column_tops_out_on_the_next_piece() {
+    let mut state = o_game();
+    let mut field = FULL;
+    for cell in state.current_piece().absolute_cells(state.piece_set()) {
+        field[cell.y as usize][cell.x as usize] = FieldCell::Empty;
+    }
+    for row in field.iter_mut() {
+        row[0] = FieldCell::Empty;
+    }
+    state.set_field(field);
+    state.move_bottom();
+
+    assert!(state.is_lost());
+    assert_eq!(state.lines_cleared(), 0);
+    assert_eq!(state.pieces_placed(), 1);
+}
+
+#[test]
+fn no_piece_locks_once_the_game_is_over() {
+    let mut state = o_game();
+    state.set_field(FULL);
+    state.move_down();
+    state.move_bottom();
+    state.move_down();
+
+    assert_eq!(state.pieces_placed(), 0);
+    assert_eq!(state.game_over_reason(), Some(GameOverReason::BlockOut));
+}
@@ -0,0 +1,87 @@
+use std::rc::Rc;
+use std::time::Duration;
+
+use tetris_rust::game::{GameConfig, GameState, LevelCap, LockDelayPolicy, PieceSet, RotationSystem};
+use tetris_rust::source::Randomizer;
+
+/// two shapes, a domino and a single block
+const PIECES: &str = "\
+#. .. .# ##
+#. ## .# ..
+
+#. #. #. #.
+";
+
+fn custom() -> GameConfig {
+    GameConfig {
+        piece_set: Rc::new(PieceSet::parse(PIECES).unwrap()),
+        seed: Some(11),
+        garbage_seed: Some(12),
+        randomizer: Randomizer::Bag,
+        rotation_system: RotationSystem::Sega,
+        lock_policy: LockDelayPolicy::StepReset,
+        entry_delay_ms: 100,
+        clear_animation_ms: 200,
+        collapse_animation_ms: 100,
+        hold_resets_orientation: false,
+        start_level: 3,
+        max_level: Some(5),
+        level_cap: LevelCap::Wrap,
+        line_goal: Some(40),
+        time_limit_ms: Some(120_000),
+        zone: true,
+        cascade: true,
+        hole_penalty: 10,
+        rainbow: true,
+        items: true,
+        next_hidden: true,
+        rewinds: 2,
+    }
+}
+
+#[test]
+fn a_game_takes_every_setting_of_its_config() {
+    let state = GameState::with_config(custom());
+    assert_eq!(state.piece_set().len(), 2);
+    assert!(state.current_piece().shape().0 < 2);
+    // the falling and the next piece emptied the bag of two shapes
+    assert!(state.remaining_bag().is_empty());
+    assert_eq!(state.rotation_system(), RotationSystem::Sega);
+    assert_eq!(state.level, 3);
+    assert_eq!(state.time_left(), Some(Duration::from_secs(120)));
+    assert!(state.is_zone_enabled());
+    assert!(state.is_cascade());
+    assert!(state.is_rainbow());
+    assert!(state.has_items());
+    assert_eq!(state.rewinds_left(), 2);
+    assert!(state.is_next_hidden());
+    assert_eq!(state.next_piece(), None);
+    assert!(!state.is_lost());
+}
+
+#[test]
+fn the_same_config_gives_the_same_game() {
+    let mut first = GameState::with_config(custom());
+    let mut second = GameState::with_config(custom());
+    for _ in 0..10 {
+        assert_eq!(first.current_piece().shape(), second.current_piece().shape());
+        assert_eq!(first.add_garbage(1), second.add_garbage(1));
+        first.move_bottom();
+        second.move_bottom();
+    }
+}
+
+#[test]
+fn the_default_config_is_a_plain_game() {
+    let state = GameState::with_config(GameConfig::default());
+    assert_eq!(state.piece_set().len(), 7);
+    assert_eq!(state.rotation_system(), RotationSystem::Srs);
+    assert_eq!(state.time_left(), None);
+    assert!(!state.is_zone_enabled());
+    assert!(!state.is_cascade());
+    assert!(!state.is_next_hidden());
+    assert!(state.remaining_bag().is_empty());
+
+    let seeded = GameState::with_config(GameConfig { seed: Some(4), ..GameConfig::default() });
+    assert_eq!(seeded.current_piece().shape(), GameState::with_seed(4).current_piece().shape());
+}
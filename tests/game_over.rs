@@ -0,0 +1,74 @@
+pub mod common;
+
+use common::{empty_field, i_piece_game};
+use tetris_rust::game::{Field, FieldCell, GameOverReason, GameState, GAME_HEIGHT, GAME_WIDTH, TICK_MS};
+
+/// a new game where every piece is the I, lying flat over columns 3 to 6
+/// a board filled up to row `top`, with a hole in column 0 of every row
+/// so nothing clears
+fn stack_up_to(top: usize) -> Field {
+    let mut field = empty_field();
+    for row in field[top..].iter_mut() {
+        *row = [FieldCell::Garbage; GAME_WIDTH];
+        row[0] = FieldCell::Empty;
+    }
+    field
+}
+
+#[test]
+fn new_game_is_not_over() {
+    let state = GameState::with_seed(0);
+    assert_eq!(state.game_over_reason(), None);
+    assert!(!state.is_lost());
+}
+
+#[test]
+fn piece_spawning_into_the_stack_is_a_block_out() {
+    let mut state = GameState::with_seed(0);
+    state.set_field(stack_up_to(0));
+    state.hold();
+    assert_eq!(state.game_over_reason(), Some(GameOverReason::BlockOut));
+    assert!(state.is_lost());
+}
+
+#[test]
+fn piece_locking_in_the_spawn_rows_is_a_lock_out() {
+    let mut state = GameState::with_seed(0);
+    state.set_field(stack_up_to(2));
+    state.move_bottom();
+    assert_eq!(state.game_over_reason(), Some(GameOverReason::LockOut));
+    assert!(state.topped_out_piece().is_some());
+}
+
+#[test]
+fn time_limit_runs_out() {
+    let mut state = GameState::with_seed(0);
+    state.set_time_limit(Some(1000));
+    for _ in 0..1000 / TICK_MS - 1 {
+        state.clock_tick();
+    }
+    assert!(!state.is_lost());
+    assert_eq!(state.time_left().unwrap().as_millis(), TICK_MS as u128);
+    state.clock_tick();
+    assert_eq!(state.game_over_reason(), Some(GameOverReason::TimeUp));
+
+    state.reset();
+    assert!(!state.is_lost());
+    assert_eq!(state.time_left().unwrap().as_millis(), 1000);
+}
+
+#[test]
+fn reaching_the_line_goal_completes_the_game() {
+    let mut state = i_piece_game();
+    state.set_line_goal(Some(1));
+    let mut field = empty_field();
+    field[GAME_HEIGHT - 1] = [FieldCell::Garbage; GAME_WIDTH];
+    for cell in field[GAME_HEIGHT - 1][3..7].iter_mut() {
+        *cell = FieldCell::Empty;
+    }
+    state.set_field(field);
+    state.move_bottom();
+    assert_eq!(state.lines_cleared(), 1);
+    assert_eq!(state.game_over_reason(), Some(GameOverReason::Completed));
+    assert!(state.is_lost());
+}
@@ -0,0 +1,97 @@
+use tetris_rust::game::{FieldCell, GameState, GAME_HEIGHT, GAME_WIDTH};
+
+fn garbage_in_row(state: &GameState, y: usize) -> usize {
+    (0..GAME_WIDTH).filter(|&x| state.field_cell(y, x) == FieldCell::Garbage).count()
+}
+
+#[test]
+fn garbage_fills_only_the_bottom_rows() {
+    let mut state = GameState::with_seed(0);
+    state.seed_initial_garbage(6, 0.5, 42);
+    for y in 0..GAME_HEIGHT - 6 {
+        assert_eq!(garbage_in_row(&state, y), 0, "row {}", y);
+    }
+    let filled: usize = (GAME_HEIGHT - 6..GAME_HEIGHT).map(|y| garbage_in_row(&state, y)).sum();
+    assert!(filled > 0 && filled < 6 * GAME_WIDTH);
+    assert!(!state.is_cheese_cleared());
+}
+
+#[test]
+fn full_density_still_leaves_a_hole_per_row() {
+    let mut state = GameState::with_seed(0);
+    state.seed_initial_garbage(5, 1.0, 7);
+    for y in GAME_HEIGHT - 5..GAME_HEIGHT {
+        assert_eq!(garbage_in_row(&state, y), GAME_WIDTH - 1, "row {}", y);
+    }
+    assert!((0..GAME_HEIGHT).all(|y| garbage_in_row(&state, y) < GAME_WIDTH));
+}
+
+#[test]
+fn the_spawn_rows_stay_free() {
+    for seed in 0..20 {
+        let mut state = GameState::with_seed(seed);
+        state.seed_initial_garbage(GAME_HEIGHT, 1.0, seed);
+        state.move_down();
+        assert!(!state.is_lost(), "seed {}", seed);
+        for y in 0..4 {
+            assert_eq!(garbage_in_row(&state, y), 0, "seed {} row {}", seed, y);
+        }
+    }
+}
+
+#[test]
+fn the_same_seed_gives_the_same_garbage() {
+    let mut first = GameState::with_seed(0);
+    let mut second = GameState::with_seed(1);
+    first.seed_initial_garbage(8, 0.3, 5);
+    second.seed_initial_garbage(8, 0.3, 5);
+    for y in 0..GAME_HEIGHT {
+        for x in 0..GAME_WIDTH {
+            assert_eq!(first.field_cell(y, x), second.field_cell(y, x));
+        }
+    }
+}
+
+#[test]
+fn same_garbage_seed_gives_the_same_holes() {
+    // different pieces, played differently, with the same garbage seed
+    let mut first = GameState::with_seeds(1, 9);
+    let mut second = GameState::with_seeds(2, 9);
+    let mut holes = (Vec::new(), Vec::new());
+    for _ in 0..6 {
+        holes.0.push(first.add_garbage(1));
+        first.move_bottom();
+        second.move_left();
+        second.move_bottom();
+        holes.1.push(second.add_garbage(1));
+    }
+    assert_eq!(holes.0, holes.1);
+    assert!(holes.0.iter().any(|&gap| gap != holes.0[0]));
+
+    let mut other = GameState::with_seeds(1, 10);
+    let other_holes: Vec<usize> = (0..6).map(|_| other.add_garbage(1)).collect();
+    assert_ne!(other_holes, holes.0);
+}
+
+#[test]
+fn added_garbage_pushes_the_stack_up() {
+    let mut state = GameState::with_seed(0);
+    state.set_cell(GAME_HEIGHT - 1, 0, FieldCell::Garbage).unwrap();
+    let gap = state.add_garbage(2);
+    assert_eq!(state.field_cell(GAME_HEIGHT - 3, 0), FieldCell::Garbage);
+    for y in GAME_HEIGHT - 2..GAME_HEIGHT {
+        assert_eq!(garbage_in_row(&state, y), GAME_WIDTH - 1);
+        assert_eq!(state.field_cell(y, gap), FieldCell::Empty);
+    }
+    assert!(!state.is_lost());
+}
+
+#[test]
+fn garbage_pushing_blocks_over_the_top_ends_the_game() {
+    let mut state = GameState::with_seed(0);
+    state.set_cell(GAME_HEIGHT - 1, 0, FieldCell::Garbage).unwrap();
+    state.add_garbage(GAME_HEIGHT - 2);
+    assert!(!state.is_lost());
+    state.add_garbage(2);
+    assert!(state.is_lost());
+}
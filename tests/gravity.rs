@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use tetris_rust::game::{gravity_delay_ms, Direction, GameState, MIN_GRAVITY_DELAY_MS, TICK_MS};
+
+#[test]
+fn delay_at_some_levels() {
+    assert_eq!(gravity_delay_ms(1), 700);
+    assert_eq!(gravity_delay_ms(5), 450);
+    assert_eq!(gravity_delay_ms(10), 300);
+    assert_eq!(gravity_delay_ms(20), 100);
+}
+
+#[test]
+fn delay_never_grows_with_the_level() {
+    for level in 1..100 {
+        assert!(gravity_delay_ms(level + 1) <= gravity_delay_ms(level), "level {}", level);
+    }
+}
+
+#[test]
+fn delay_stays_above_the_floor_in_whole_ticks() {
+    for level in 1..1000 {
+        let delay = gravity_delay_ms(level);
+        assert!(delay >= MIN_GRAVITY_DELAY_MS);
+        assert_eq!(delay % TICK_MS, 0);
+    }
+    assert_eq!(gravity_delay_ms(1000), MIN_GRAVITY_DELAY_MS);
+}
+
+#[test]
+fn time_to_next_drop_stays_in_range_at_level_20() {
+    let mut state = GameState::with_seed(0);
+    state.set_start_level(20);
+    let delay = gravity_delay_ms(20);
+    assert_eq!(delay, 2 * TICK_MS);
+    let start = state.current_piece().position().y;
+    while state.current_piece().position().y == start {
+        state.clock_tick();
+    }
+    while state.can_move(Direction::DOWN) {
+        let y = state.current_piece().position().y;
+        for tick in 1..=2 {
+            let left = state.time_to_next_drop();
+            assert!(left > Duration::from_millis(0) && left <= Duration::from_millis(delay as u64));
+            state.clock_tick();
+            let moved = state.current_piece().position().y == y + 1;
+            assert_eq!(moved, tick == 2);
+        }
+    }
+}
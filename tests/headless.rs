@@ -0,0 +1,23 @@
+use std::process::Command;
+
+fn run(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_tetris-rust")).args(args).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn runs_without_a_terminal() {
+    let output = run(&["--headless", "--steps", "50", "--seed", "1"]);
+    assert!(output.starts_with("steps: 50\n"), "{}", output);
+    assert!(output.contains("score: "));
+    assert!(output.contains("lines: "));
+}
+
+#[test]
+fn same_seed_prints_the_same_result() {
+    let args = ["--headless", "--seed", "42"];
+    let first = run(&args);
+    assert!(first.contains("game over: "), "{}", first);
+    assert_eq!(first, run(&args));
+}
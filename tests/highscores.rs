@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use tetris_rust::highscores::{HighScore, HighScores};
+
+/// an empty directory of its own for each test
+fn data_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tetris-rust-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn score(score: i32) -> HighScore {
+    HighScore { name: "ada".to_string(), score, lines: 0, mode: "marathon".to_string(), timestamp: 0 }
+}
+
+fn scores(table: &HighScores) -> Vec<i32> {
+    table.entries().iter().map(|entry| entry.score).collect()
+}
+
+#[test]
+fn the_old_best_score_is_converted() {
+    let dir = data_dir("migrate");
+    std::fs::write(dir.join("highscore"), "1234\n").unwrap();
+    let table = HighScores::load(Some(dir.clone())).unwrap();
+
+    assert_eq!(scores(&table), vec![1234]);
+    assert_eq!(table.entries()[0].name, "?");
+    assert!(!dir.join("highscore").exists());
+    // the next run reads it back from the new table
+    assert_eq!(scores(&HighScores::load(Some(dir)).unwrap()), vec![1234]);
+}
+
+#[test]
+fn the_table_keeps_the_ten_best_highest_first() {
+    let dir = data_dir("order");
+    let mut table = HighScores::load(Some(dir.clone())).unwrap();
+    for points in [50, 300, 10, 700, 20, 90, 80, 60, 40, 30, 200, 5] {
+        table.add(score(points));
+    }
+
+    assert_eq!(scores(&table), vec![700, 300, 200, 90, 80, 60, 50, 40, 30, 20]);
+    assert_eq!(table.best(), 700);
+    assert_eq!(scores(&HighScores::load(Some(dir)).unwrap()), scores(&table));
+}
+
+#[test]
+fn a_broken_table_is_an_error_and_left_alone() {
+    let dir = data_dir("broken");
+    let path = dir.join("highscores.json");
+    std::fs::write(&path, "[{\"name\": \"ada\", \"score\":").unwrap();
+
+    assert!(HighScores::load(Some(dir)).is_err());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "[{\"name\": \"ada\", \"score\":");
+}
+
+#[test]
+fn dates_are_days_in_utc() {
+    let at = |timestamp: u64| HighScore { timestamp, ..score(0) }.date();
+    assert_eq!(at(0), "1970-01-01");
+    assert_eq!(at(86_399), "1970-01-01");
+    assert_eq!(at(951_782_400), "2000-02-29");
+    assert_eq!(at(1_709_251_199), "2024-02-29");
+    assert_eq!(at(1_735_689_600), "2025-01-01");
+}
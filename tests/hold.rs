@@ -0,0 +1,66 @@
+pub mod common;
+
+use common::t_piece_game;
+use tetris_rust::game::{GameState, PieceRotation, PieceShape, Point};
+use tetris_rust::source::FixedSource;
+
+/// a T held after rotating it once and moving it, with an O coming next
+fn rotated_t_held(resets: bool) -> GameState {
+    let mut state = GameState::with_source(FixedSource::new(vec![PieceShape::T, PieceShape::O]));
+    state.set_hold_resets_orientation(resets);
+    assert!(state.rotate().is_some());
+    state.move_left();
+    state.move_down();
+    state.hold();
+    assert_eq!(state.held_shape(), Some(PieceShape::T));
+    assert_eq!(state.current_piece().shape(), PieceShape::O);
+    state
+}
+
+/// lock the falling piece and take the held one back
+fn swap_back(state: &mut GameState) {
+    state.move_bottom();
+    state.hold();
+}
+
+#[test]
+fn hold_resets_the_orientation_by_default() {
+    let spawn = t_piece_game().current_piece();
+    let mut state = rotated_t_held(true);
+    swap_back(&mut state);
+    let piece = state.current_piece();
+    assert_eq!(piece.shape(), PieceShape::T);
+    assert_eq!(piece.rotation(), PieceRotation::NORMAL);
+    assert_eq!(piece.position(), spawn.position());
+}
+
+#[test]
+fn hold_can_keep_the_rotation() {
+    let spawn = t_piece_game().current_piece();
+    let mut state = rotated_t_held(false);
+    swap_back(&mut state);
+    let piece = state.current_piece();
+    assert_eq!(piece.shape(), PieceShape::T);
+    assert_eq!(piece.rotation(), PieceRotation::RIGHT);
+    // the position still goes back to the spawn one
+    assert_eq!(piece.position(), spawn.position());
+}
+
+#[test]
+fn next_piece_from_hold_spawns_normally_either_way() {
+    for &resets in [true, false].iter() {
+        let state = rotated_t_held(resets);
+        assert_eq!(state.current_piece().rotation(), PieceRotation::NORMAL);
+        assert_eq!(state.current_piece().position().y, 0);
+    }
+}
+
+#[test]
+fn default_game_resets_the_orientation() {
+    let mut state = GameState::with_source(FixedSource::new(vec![PieceShape::T, PieceShape::O]));
+    assert!(state.rotate().is_some());
+    state.hold();
+    swap_back(&mut state);
+    assert_eq!(state.current_piece().rotation(), PieceRotation::NORMAL);
+    assert_eq!(state.current_piece().position(), Point { x: 3, y: 0 });
+}
@@ -0,0 +1,107 @@
+pub mod common;
+
+use common::i_piece_game;
+use tetris_rust::game::{FieldCell, GameEvent, GameState, ScoreBreakdown, GAME_HEIGHT, POINTS_PER_LEVEL};
+
+const PENALTY: i32 = 10;
+
+fn i_game() -> GameState {
+    let mut state = i_piece_game();
+    state.set_hole_penalty(PENALTY);
+    state
+}
+
+fn scored(state: &mut GameState) -> Vec<ScoreBreakdown> {
+    state.take_events()
+        .into_iter()
+        .filter_map(|event| match event {
+            GameEvent::Scored(breakdown) => Some(breakdown),
+            _ => None,
+        })
+        .collect()
+}
+
+/// a block on the floor under the first column of the flat I piece, so
+/// the piece rests on it with three holes under its other columns
+fn block_under_the_piece(state: &mut GameState) {
+    let x = state.ghost_piece().cells(state.piece_set()).iter().map(|cell| cell.x).min().unwrap();
+    state.set_cell(GAME_HEIGHT - 1, x as usize, FieldCell::Garbage).unwrap();
+}
+
+#[test]
+fn holes_are_empty_cells_under_a_block() {
+    let mut state = GameState::with_seed(0);
+    assert_eq!(state.count_holes(), 0);
+    state.set_cell(GAME_HEIGHT - 1, 0, FieldCell::Garbage).unwrap();
+    assert_eq!(state.count_holes(), 0);
+    state.set_cell(GAME_HEIGHT - 4, 0, FieldCell::Garbage).unwrap();
+    state.set_cell(GAME_HEIGHT - 2, 5, FieldCell::Garbage).unwrap();
+    assert_eq!(state.count_holes(), 3);
+}
+
+#[test]
+fn a_piece_on_a_flat_floor_loses_nothing() {
+    let mut state = i_game();
+    state.take_events();
+    state.move_bottom();
+    assert_eq!(state.count_holes(), 0);
+    assert!(scored(&mut state).is_empty());
+}
+
+#[test]
+fn each_hole_made_costs_the_penalty() {
+    let mut state = i_game();
+    block_under_the_piece(&mut state);
+    state.score = 100;
+    let mut free = state.clone();
+    free.set_hole_penalty(0);
+    state.take_events();
+    state.move_bottom();
+    free.move_bottom();
+    assert_eq!(state.count_holes(), 3);
+    let breakdown = scored(&mut state);
+    assert_eq!(breakdown.len(), 1);
+    assert_eq!(breakdown[0].holes, -3 * PENALTY);
+    assert_eq!(breakdown[0].total(), breakdown[0].base - 3 * PENALTY);
+    assert_eq!(state.score, free.score - 3 * PENALTY);
+
+    // the holes already there aren't charged again
+    state.move_bottom();
+    assert!(scored(&mut state).is_empty());
+}
+
+#[test]
+fn the_score_does_not_go_below_zero() {
+    let mut state = i_game();
+    block_under_the_piece(&mut state);
+    state.move_bottom();
+    assert!(state.score < 3 * PENALTY);
+    assert_eq!(state.score, 0);
+}
+
+#[test]
+fn the_penalty_does_not_take_the_level_down() {
+    let mut state = i_game();
+    state.score = POINTS_PER_LEVEL;
+    // work the level out from the score set above
+    state.set_start_level(1);
+    assert_eq!(state.level, 2);
+    block_under_the_piece(&mut state);
+    state.move_bottom();
+    assert!(state.score < POINTS_PER_LEVEL);
+    assert_eq!(state.level, 2);
+    assert_eq!(state.points_to_next_level(), 2 * POINTS_PER_LEVEL - state.score);
+    state.reset();
+    assert_eq!(state.level, 1);
+}
+
+#[test]
+fn no_penalty_when_it_is_off() {
+    let mut state = i_game();
+    state.set_hole_penalty(0);
+    block_under_the_piece(&mut state);
+    state.take_events();
+    state.move_bottom();
+    assert_eq!(state.count_holes(), 3);
+    assert!(scored(&mut state).is_empty());
+}
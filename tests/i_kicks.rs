@@ -0,0 +1,75 @@
+pub mod common;
+
+use common::i_piece_game;
+use tetris_rust::game::{Direction, GameState, PieceRotation, GAME_HEIGHT, GAME_WIDTH};
+
+/// a new game where every piece is the I
+/// the I piece turned `rotations` times clockwise, then pushed as far as
+/// possible in each of the directions
+fn i_piece_against(rotations: usize, directions: &[Direction]) -> GameState {
+    let mut state = i_piece_game();
+    for _ in 0..rotations {
+        assert!(state.rotate().is_some());
+    }
+    for &direction in directions {
+        for _ in 0..GAME_HEIGHT {
+            match direction {
+                Direction::LEFT => state.move_left(),
+                Direction::RIGHT => state.move_right(),
+                Direction::DOWN => {
+                    if state.can_move(Direction::DOWN) {
+                        state.move_down();
+                    }
+                },
+            }
+        }
+        assert!(!state.can_move(direction));
+    }
+    state
+}
+
+fn rotates_in_bounds(mut state: GameState) {
+    let before = state.current_piece().rotation();
+    let rotation = state.rotate();
+    assert!(rotation.is_some(), "the I piece can't rotate from {:?} at {:?}", before, state.current_piece().position());
+    assert_ne!(state.current_piece().rotation(), before);
+    for cell in state.current_piece().cells(state.piece_set()) {
+        assert!((0..GAME_WIDTH as i32).contains(&cell.x));
+        assert!((0..GAME_HEIGHT as i32).contains(&cell.y));
+    }
+    assert_eq!(state.pieces_placed(), 0);
+}
+
+#[test]
+fn i_rotates_on_the_floor() {
+    for rotations in 0..4 {
+        let state = i_piece_against(rotations, &[Direction::DOWN]);
+        rotates_in_bounds(state);
+    }
+}
+
+#[test]
+fn i_rotates_against_the_walls() {
+    for rotations in 0..4 {
+        rotates_in_bounds(i_piece_against(rotations, &[Direction::LEFT]));
+        rotates_in_bounds(i_piece_against(rotations, &[Direction::RIGHT]));
+    }
+}
+
+#[test]
+fn i_rotates_in_the_corners() {
+    for rotations in 0..4 {
+        rotates_in_bounds(i_piece_against(rotations, &[Direction::LEFT, Direction::DOWN]));
+        rotates_in_bounds(i_piece_against(rotations, &[Direction::RIGHT, Direction::DOWN]));
+    }
+}
+
+#[test]
+fn horizontal_i_on_the_floor_kicks_up_to_vertical() {
+    let mut state = i_piece_against(0, &[Direction::DOWN]);
+    let floor = state.current_piece().position().y;
+    let rotation = state.rotate().unwrap();
+    assert_eq!(state.current_piece().rotation(), PieceRotation::RIGHT);
+    assert!(rotation.kick.y < 0, "the kick {:?} doesn't go up", rotation.kick);
+    assert!(state.current_piece().position().y < floor);
+}
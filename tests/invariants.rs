@@ -0,0 +1,62 @@
+#![cfg(debug_assertions)]
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tetris_rust::game::{Action, FieldCell, GameConfig, GameState, GAME_HEIGHT, GAME_WIDTH};
+
+// The moves and ticks check the invariants themselves in debug builds,
+// the tests only have to play. Release builds don't have the checks
+
+#[test]
+fn games_with_every_rule_on_keep_the_invariants() {
+    for seed in 0..10 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut state = GameState::with_config(GameConfig {
+            seed: Some(seed),
+            zone: true,
+            cascade: true,
+            items: true,
+            rewinds: 1,
+            max_level: Some(3),
+            ..GameConfig::default()
+        });
+        for _ in 0..1000 {
+            if rng.gen() {
+                state.clock_tick();
+            } else {
+                state.apply(Action::ALL[rng.gen_range(0, Action::ALL.len())]);
+            }
+            if state.is_lost() && !state.can_rewind() {
+                state.reset();
+            }
+            state.assert_invariants();
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "negative score")]
+fn a_broken_game_fails_on_the_next_move() {
+    let mut state = GameState::new();
+    state.score = -1;
+    state.move_left();
+}
+
+#[test]
+fn a_row_filled_with_set_cell_waits_for_the_lock() {
+    let mut state = GameState::with_seed(0);
+    for x in 0..GAME_WIDTH {
+        state.set_cell(GAME_HEIGHT - 1, x, FieldCell::Garbage).unwrap();
+    }
+    state.move_left();
+    state.move_bottom();
+    assert_eq!(state.lines_cleared(), 1);
+}
+
+#[test]
+#[should_panic(expected = "where the score gives")]
+fn a_level_the_score_doesnt_give_fails_on_the_next_move() {
+    let mut state = GameState::new();
+    state.level = 5;
+    state.move_left();
+}
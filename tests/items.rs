@@ -0,0 +1,111 @@
+pub mod common;
+
+use common::{empty_field, i_piece_game};
+use tetris_rust::game::{Field, FieldCell, GameConfig, GameState, Item, PieceShape, GAME_HEIGHT, GAME_WIDTH};
+
+const BOTTOM: usize = GAME_HEIGHT - 1;
+
+/// a board with `row` full but for the four cells of a flat I piece
+/// from column `gap`, an item of `item` at column `x` in it, and garbage
+/// in the other cells given
+fn board(row: usize, gap: usize, x: usize, item: Item, cells: &[(usize, usize)]) -> Field {
+    let mut field = empty_field();
+    field[row] = [FieldCell::Garbage; GAME_WIDTH];
+    field[row][gap..gap + 4].copy_from_slice(&[FieldCell::Empty; 4]);
+    field[row][x] = FieldCell::Item(PieceShape::T, item);
+    for &(y, x) in cells {
+        field[y][x] = FieldCell::Garbage;
+    }
+    field
+}
+
+/// a game of I pieces on `field`
+fn game_on(field: Field) -> GameState {
+    let mut state = i_piece_game();
+    state.set_field(field);
+    state
+}
+
+/// drop the I piece at the left or right wall, where the gap of `board` is
+fn drop_to_side(state: &mut GameState, left: bool) {
+    for _ in 0..GAME_WIDTH {
+        if left {
+            state.move_left();
+        } else {
+            state.move_right();
+        }
+    }
+    state.move_bottom();
+}
+
+fn occupied(state: &GameState, y: usize) -> Vec<usize> {
+    (0..GAME_WIDTH).filter(|&x| state.field_cell(y, x) != FieldCell::Empty).collect()
+}
+
+#[test]
+fn clear_bottom_also_removes_the_bottom_row() {
+    let mut state = game_on(board(BOTTOM - 2, 0, 4, Item::ClearBottom, &[(BOTTOM - 1, 1), (BOTTOM, 0), (BOTTOM, 7)]));
+
+    drop_to_side(&mut state, true);
+    // only the full line counts
+    assert_eq!(state.lines_cleared(), 1);
+    assert_eq!(occupied(&state, BOTTOM), vec![1]);
+    assert!(occupied(&state, BOTTOM - 1).is_empty());
+}
+
+#[test]
+fn clear_column_empties_its_column() {
+    let mut state = game_on(board(BOTTOM, GAME_WIDTH - 4, 3, Item::ClearColumn, &[(BOTTOM - 1, 3), (BOTTOM - 1, 4), (BOTTOM - 4, 3)]));
+
+    drop_to_side(&mut state, false);
+    assert_eq!(state.lines_cleared(), 1);
+    assert_eq!(occupied(&state, BOTTOM), vec![4]);
+    assert!((0..GAME_HEIGHT).all(|y| state.field_cell(y, 3) == FieldCell::Empty));
+}
+
+#[test]
+fn bomb_blows_away_the_blocks_around_it() {
+    let around = [(BOTTOM, 4), (BOTTOM, 5), (BOTTOM, 6), (BOTTOM - 2, 4), (BOTTOM - 2, 5)];
+    let mut cells = around.to_vec();
+    cells.extend(&[(BOTTOM, 8), (BOTTOM - 2, 0), (BOTTOM - 3, 5)]);
+    let mut state = game_on(board(BOTTOM - 1, GAME_WIDTH - 4, 5, Item::Bomb, &cells));
+
+    drop_to_side(&mut state, false);
+    assert_eq!(state.lines_cleared(), 1);
+    assert_eq!(occupied(&state, BOTTOM), vec![8]);
+    assert_eq!(occupied(&state, BOTTOM - 1), vec![0]);
+    assert_eq!(occupied(&state, BOTTOM - 2), vec![5]);
+}
+
+#[test]
+fn an_item_in_a_row_that_is_not_full_does_nothing() {
+    let mut field = board(BOTTOM, GAME_WIDTH - 4, 0, Item::Bomb, &[]);
+    field[BOTTOM][0] = FieldCell::Empty;
+    field[BOTTOM][5] = FieldCell::Item(PieceShape::T, Item::ClearColumn);
+    let mut state = game_on(field);
+
+    // the I piece fills the gap but for the first column
+    drop_to_side(&mut state, false);
+    assert_eq!(state.lines_cleared(), 0);
+    assert_eq!(state.field_cell(BOTTOM, 5), FieldCell::Item(PieceShape::T, Item::ClearColumn));
+    assert_eq!(occupied(&state, BOTTOM).len(), GAME_WIDTH - 1);
+}
+
+#[test]
+fn items_only_show_up_in_items_mode() {
+    let has_items = |items: bool| {
+        let mut state = GameState::with_config(GameConfig { seed: Some(5), items, ..GameConfig::default() });
+        assert_eq!(state.has_items(), items);
+        let mut found = false;
+        for _ in 0..30 {
+            if state.is_lost() {
+                state.reset();
+            }
+            state.move_bottom();
+            found |= (0..GAME_HEIGHT).any(|y| (0..GAME_WIDTH).any(|x| matches!(state.field_cell(y, x), FieldCell::Item(..))));
+        }
+        found
+    };
+    assert!(has_items(true));
+    assert!(!has_items(false));
+}
@@ -0,0 +1,26 @@
+use tetris_rust::layout::{center_x, clip};
+
+#[test]
+fn text_is_centered_in_the_space() {
+    assert_eq!(center_x(80, 20), 30);
+    assert_eq!(center_x(22, 7), 7);
+    // the extra column of an odd gap goes on the right
+    assert_eq!(center_x(10, 5), 2);
+}
+
+#[test]
+fn text_wider_than_the_space_starts_at_its_edge() {
+    assert_eq!(center_x(20, 20), 0);
+    assert_eq!(center_x(30, 50), 0);
+    assert_eq!(center_x(0, 7), 0);
+}
+
+#[test]
+fn clip_keeps_what_fits() {
+    assert_eq!(clip(" PAUSE ", 80), " PAUSE ");
+    assert_eq!(clip(" PAUSE ", 7), " PAUSE ");
+    assert_eq!(clip(" PAUSE ", 4), " PAU");
+    assert_eq!(clip(" PAUSE ", 0), "");
+    assert_eq!(clip(" PAUSE ", -3), "");
+    assert_eq!(clip("▲▼ up", 2), "▲▼");
+}
@@ -0,0 +1,107 @@
+pub mod common;
+
+use common::t_piece_game;
+use tetris_rust::game::{Direction, Field, FieldCell, GameState, GAME_HEIGHT, GAME_WIDTH};
+
+fn moves_like(state: &mut GameState, direction: Direction) -> bool {
+    let expected = state.can_move(direction);
+    let before = state.current_piece().position();
+    match direction {
+        Direction::LEFT => state.move_left(),
+        Direction::RIGHT => state.move_right(),
+        Direction::DOWN => state.move_down(),
+    }
+    (state.current_piece().position() != before) == expected
+}
+
+#[test]
+fn can_move_agrees_with_moves_against_the_walls() {
+    for seed in 0..20 {
+        let mut state = GameState::with_seed(seed);
+        for _ in 0..GAME_WIDTH {
+            assert!(moves_like(&mut state, Direction::LEFT));
+        }
+        assert!(!state.can_move(Direction::LEFT));
+        for _ in 0..GAME_WIDTH {
+            assert!(moves_like(&mut state, Direction::RIGHT));
+        }
+        assert!(!state.can_move(Direction::RIGHT));
+    }
+}
+
+#[test]
+fn can_move_down_is_false_on_the_floor() {
+    let mut state = GameState::with_seed(1);
+    let mut steps = 0;
+    while state.can_move(Direction::DOWN) {
+        assert!(moves_like(&mut state, Direction::DOWN));
+        steps += 1;
+        assert!(steps < GAME_HEIGHT);
+    }
+    assert_eq!(state.pieces_placed(), 0);
+}
+
+#[test]
+fn can_rotate_agrees_with_rotate_near_walls() {
+    for seed in 0..20 {
+        let mut state = GameState::with_seed(seed);
+        for _ in 0..GAME_WIDTH {
+            state.move_left();
+        }
+        for _ in 0..4 {
+            let expected = state.can_rotate();
+            assert_eq!(state.rotate().is_some(), expected);
+        }
+        for _ in 0..GAME_WIDTH {
+            state.move_right();
+        }
+        for _ in 0..4 {
+            let expected = state.can_rotate();
+            assert_eq!(state.rotate().is_some(), expected);
+        }
+    }
+}
+
+#[test]
+fn can_rotate_is_false_when_boxed_in() {
+    let mut state = t_piece_game();
+    // every cell but those of the falling piece is taken, no kick can fit
+    let mut field: Field = [[FieldCell::Garbage; GAME_WIDTH]; GAME_HEIGHT];
+    for cell in state.current_piece().absolute_cells(state.piece_set()) {
+        field[cell.y as usize][cell.x as usize] = FieldCell::Empty;
+    }
+    state.set_field(field);
+    let before = state.current_piece();
+    assert!(!state.can_rotate());
+    assert!(!state.can_rotate_ccw());
+    assert!(state.rotate().is_none());
+    assert_eq!(state.current_piece(), before);
+}
+
+#[test]
+fn can_hold_only_once_per_piece() {
+    let mut state = GameState::with_seed(3);
+    assert!(state.can_hold());
+    state.hold();
+    assert_eq!(state.hold_count(), 1);
+    assert!(!state.can_hold());
+    state.hold();
+    assert_eq!(state.hold_count(), 1);
+    state.move_bottom();
+    assert!(state.can_hold());
+}
+
+#[test]
+fn hold_is_available_again_after_a_lock() {
+    let mut state = GameState::with_seed(3);
+    state.set_entry_delay(200);
+    assert!(state.hold_available());
+    state.hold();
+    assert!(!state.hold_available());
+    state.move_left();
+    assert!(!state.hold_available());
+    state.move_bottom();
+    // during the entry delay hold can't be used yet, but it isn't spent
+    assert!(!state.can_hold());
+    assert!(state.hold_available());
+}
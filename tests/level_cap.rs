@@ -0,0 +1,84 @@
+pub mod common;
+
+use common::{empty_field, i_piece_game};
+use tetris_rust::game::{Field, FieldCell, GameState, LevelCap, GAME_HEIGHT, GAME_WIDTH, POINTS_PER_LEVEL};
+
+/// a board with its bottom row complete but under the falling I piece
+/// and a block over it, so clearing it isn't a perfect clear
+fn bottom_row_under_the_piece(state: &GameState) -> Field {
+    let mut field = empty_field();
+    field[GAME_HEIGHT - 1] = [FieldCell::Garbage; GAME_WIDTH];
+    for cell in state.current_piece().absolute_cells(state.piece_set()) {
+        field[GAME_HEIGHT - 1][cell.x as usize] = FieldCell::Empty;
+    }
+    field[GAME_HEIGHT - 2][0] = FieldCell::Garbage;
+    field
+}
+
+/// drop the I piece to clear a single line, worth 40 points, with the
+/// score set to reach `score` with it
+fn score_a_line(state: &mut GameState, score: i32) {
+    state.score = score - 40;
+    state.set_field(bottom_row_under_the_piece(state));
+    state.move_bottom();
+}
+
+#[test]
+fn level_never_goes_past_the_maximum() {
+    let mut state = i_piece_game();
+    state.set_max_level(Some(15), LevelCap::Stop);
+    for score in (0..100).map(|i| i * 1000 + 100) {
+        score_a_line(&mut state, score);
+        assert!(state.level <= 15, "score {}", state.score);
+        assert_eq!(state.is_max_level(), state.score >= 14 * POINTS_PER_LEVEL, "score {}", state.score);
+    }
+    assert_eq!(state.level, 15);
+}
+
+#[test]
+fn level_wraps_around_the_maximum() {
+    let mut state = i_piece_game();
+    state.set_max_level(Some(10), LevelCap::Wrap);
+    score_a_line(&mut state, 9 * POINTS_PER_LEVEL + 100);
+    assert_eq!(state.level, 10);
+    score_a_line(&mut state, 10 * POINTS_PER_LEVEL + 100);
+    assert_eq!(state.level, 1);
+    assert!(state.is_max_level());
+    score_a_line(&mut state, 1000 * POINTS_PER_LEVEL + 100);
+    assert!((1..=10).contains(&state.level));
+}
+
+#[test]
+fn without_a_maximum_the_level_follows_the_score() {
+    let mut state = i_piece_game();
+    score_a_line(&mut state, 50 * POINTS_PER_LEVEL + 100);
+    assert_eq!(state.level, 51);
+    assert!(!state.is_max_level());
+}
+
+#[test]
+fn the_level_counts_from_the_start_level() {
+    let mut state = i_piece_game();
+    state.set_start_level(5);
+    assert_eq!(state.level, 5);
+    assert_eq!(state.points_to_next_level(), POINTS_PER_LEVEL);
+    score_a_line(&mut state, POINTS_PER_LEVEL + 100);
+    assert_eq!(state.level, 6);
+    assert_eq!(state.points_to_next_level(), POINTS_PER_LEVEL - 100);
+    state.reset();
+    assert_eq!(state.level, 5);
+}
+
+#[test]
+fn gravity_is_a_row_per_tick_at_the_maximum() {
+    let mut state = GameState::with_seed(0);
+    state.set_max_level(Some(1), LevelCap::Stop);
+    let start = state.current_piece().position().y;
+    while state.current_piece().position().y == start {
+        state.clock_tick();
+    }
+    for row in 1..10 {
+        state.clock_tick();
+        assert_eq!(state.current_piece().position().y, start + 1 + row);
+    }
+}
@@ -0,0 +1,133 @@
+pub mod common;
+
+use common::{empty_field, i_piece_game, row};
+use tetris_rust::game::{ClearPhase, Field, FieldCell, GameState, PieceShape, GAME_HEIGHT, GAME_WIDTH};
+
+/// a board where rows 5 to 21 have one block each, in a column that
+/// tells them apart, except `full` rows that are complete
+fn marked_rows(full: &[usize]) -> Field {
+    let mut field = empty_field();
+    for y in 5..GAME_HEIGHT {
+        if full.contains(&y) {
+            field[y] = [FieldCell::Garbage; GAME_WIDTH];
+        } else {
+            field[y][y % GAME_WIDTH] = FieldCell::Occupied(PieceShape(y % 7));
+        }
+    }
+    field
+}
+
+#[test]
+fn separated_full_rows_collapse_together() {
+    let mut state = i_piece_game();
+    // the I piece dropped on the left, on the block of row 11, completes
+    // row 10
+    let mut field = marked_rows(&[10, 18]);
+    field[10][..4].copy_from_slice(&[FieldCell::Empty; 4]);
+    state.set_field(field);
+    for _ in 0..GAME_WIDTH {
+        state.move_left();
+    }
+    state.move_bottom();
+    assert_eq!(state.lines_cleared(), 2);
+
+    let before = marked_rows(&[]);
+    for y in 0..GAME_HEIGHT {
+        // rows below 18 stay, rows between the two move down by one and
+        // rows above 10 by two
+        let expected = match y {
+            19.. => before[y],
+            12..=18 => before[y - 1],
+            7..=11 => before[y - 2],
+            _ => [FieldCell::Empty; GAME_WIDTH],
+        };
+        assert_eq!(row(&state, y), expected, "row {}", y);
+    }
+}
+
+#[test]
+fn rows_without_full_lines_stay() {
+    let mut state = i_piece_game();
+    state.set_field(marked_rows(&[]));
+    // the rows stay as they were, with the I piece locked on them
+    let mut after = marked_rows(&[]);
+    for cell in state.ghost_piece().absolute_cells(state.piece_set()) {
+        after[cell.y as usize][cell.x as usize] = FieldCell::Occupied(PieceShape::I);
+    }
+    state.move_bottom();
+    assert_eq!(state.lines_cleared(), 0);
+    for (y, expected) in after.iter().enumerate() {
+        assert_eq!(row(&state, y), *expected, "row {}", y);
+    }
+}
+
+/// a game of I pieces where hard dropping the first one clears the
+/// bottom row
+fn one_line_to_clear() -> GameState {
+    let mut state = i_piece_game();
+    let mut field = empty_field();
+    field[GAME_HEIGHT - 1] = [FieldCell::Garbage; GAME_WIDTH];
+    field[GAME_HEIGHT - 2][0] = FieldCell::Garbage;
+    for cell in state.ghost_piece().absolute_cells(state.piece_set()) {
+        field[cell.y as usize][cell.x as usize] = FieldCell::Empty;
+    }
+    state.set_field(field);
+    state
+}
+
+#[test]
+fn clear_animation_stops_the_game_for_its_length() {
+    let mut state = one_line_to_clear();
+    state.set_clear_animation(100, 150);
+    state.move_bottom();
+    assert_eq!(state.lines_cleared(), 1);
+    let animation = state.clear_animation().expect("the clear is animated").clone();
+    assert_eq!(animation.phase, ClearPhase::Flash);
+    assert_eq!(animation.rows, vec![GAME_HEIGHT - 1]);
+    assert!(!animation.field[GAME_HEIGHT - 1].contains(&FieldCell::Empty));
+    // the board already has the line removed
+    assert_eq!(state.field_cell(GAME_HEIGHT - 1, 0), FieldCell::Garbage);
+    assert_eq!(state.field_cell(GAME_HEIGHT - 2, 0), FieldCell::Empty);
+
+    // 250ms are 5 ticks, the new piece can't move and doesn't fall
+    let piece = state.current_piece();
+    for tick in 0..5 {
+        assert!(state.is_entering(), "tick {}", tick);
+        state.move_left();
+        state.move_down();
+        assert_eq!(state.current_piece(), piece, "tick {}", tick);
+        let phase = state.clear_animation().map(|animation| animation.phase);
+        let expected = if tick < 2 { ClearPhase::Flash } else { ClearPhase::Collapse };
+        assert_eq!(phase, Some(expected), "tick {}", tick);
+        state.clock_tick();
+    }
+    assert!(!state.is_entering());
+    assert!(state.clear_animation().is_none());
+    state.move_left();
+    assert_ne!(state.current_piece(), piece);
+}
+
+#[test]
+fn clear_animation_comes_before_the_entry_delay() {
+    let mut state = one_line_to_clear();
+    state.set_entry_delay(100);
+    state.set_clear_animation(0, 100);
+    state.move_bottom();
+    assert_eq!(state.clear_animation().map(|animation| animation.phase), Some(ClearPhase::Collapse));
+    state.clock_tick();
+    state.clock_tick();
+    assert!(state.clear_animation().is_none());
+    assert!(state.is_entering());
+    state.clock_tick();
+    state.clock_tick();
+    assert!(!state.is_entering());
+}
+
+#[test]
+fn no_clear_animation_by_default() {
+    let mut state = one_line_to_clear();
+    state.move_bottom();
+    assert_eq!(state.lines_cleared(), 1);
+    assert!(state.clear_animation().is_none());
+    assert!(!state.is_entering());
+}
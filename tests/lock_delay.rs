@@ -0,0 +1,93 @@
+pub mod common;
+
+use common::{empty_field, o_game};
+use tetris_rust::game::{Direction, Field, FieldCell, GameState, LockDelayPolicy, GAME_HEIGHT, LOCK_DELAY_MS, TICK_MS};
+
+const LOCK_TICKS: i32 = LOCK_DELAY_MS / TICK_MS;
+
+/// a new game with the first piece resting on the floor
+fn grounded(policy: LockDelayPolicy) -> GameState {
+    let mut state = GameState::with_seed(2);
+    state.set_lock_delay_policy(policy);
+    while state.can_move(Direction::DOWN) {
+        state.move_down();
+    }
+    assert_eq!(state.pieces_placed(), 0);
+    state
+}
+
+/// tick until the piece locks, moving it sideways every `move_every`
+/// ticks, and return the number of ticks
+fn ticks_to_lock(state: &mut GameState, move_every: i32, limit: i32) -> Option<i32> {
+    for tick in 1..=limit {
+        state.clock_tick();
+        if state.pieces_placed() > 0 {
+            return Some(tick);
+        }
+        if tick % move_every == 0 {
+            if tick / move_every % 2 == 0 {
+                state.move_left();
+            } else {
+                state.move_right();
+            }
+        }
+    }
+    None
+}
+
+#[test]
+fn without_moves_every_policy_locks_after_the_delay() {
+    let policies = [LockDelayPolicy::Infinite, LockDelayPolicy::StepReset, LockDelayPolicy::MoveReset(15)];
+    for &policy in policies.iter() {
+        let mut state = grounded(policy);
+        assert_eq!(ticks_to_lock(&mut state, i32::MAX, 1000), Some(LOCK_TICKS), "{:?}", policy);
+    }
+}
+
+#[test]
+fn infinite_keeps_the_piece_up_while_it_moves() {
+    let mut state = grounded(LockDelayPolicy::Infinite);
+    assert_eq!(ticks_to_lock(&mut state, LOCK_TICKS - 1, 1000), None);
+}
+
+#[test]
+fn step_reset_ignores_sideways_moves() {
+    let mut state = grounded(LockDelayPolicy::StepReset);
+    assert_eq!(ticks_to_lock(&mut state, 2, 1000), Some(LOCK_TICKS));
+}
+
+#[test]
+fn move_reset_stops_resetting_after_the_cap() {
+    let resets = 15;
+    let mut state = grounded(LockDelayPolicy::MoveReset(resets));
+    let move_every = LOCK_TICKS - 1;
+    // every move restarts the delay until the cap, then it runs out
+    let expected = resets as i32 * move_every + LOCK_TICKS;
+    assert_eq!(ticks_to_lock(&mut state, move_every, 1000), Some(expected));
+}
+
+#[test]
+fn moving_down_restarts_the_delay() {
+    // the O lands on a ledge two columns wide, with the floor a row lower
+    // right of it
+    let mut state = o_game();
+    state.set_lock_delay_policy(LockDelayPolicy::StepReset);
+    let mut field: Field = empty_field();
+    for cell in field[GAME_HEIGHT - 1][..6].iter_mut() {
+        *cell = FieldCell::Garbage;
+    }
+    state.set_field(field);
+    while state.can_move(Direction::DOWN) {
+        state.move_down();
+    }
+    for _ in 0..LOCK_TICKS - 2 {
+        state.clock_tick();
+    }
+    // off the ledge and one row down onto the floor, a full delay again
+    state.move_right();
+    state.move_right();
+    assert!(state.can_move(Direction::DOWN));
+    state.move_down();
+    assert!(!state.can_move(Direction::DOWN));
+    assert_eq!(ticks_to_lock(&mut state, i32::MAX, 1000), Some(LOCK_TICKS));
+}
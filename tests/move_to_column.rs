@@ -0,0 +1,40 @@
+pub mod common;
+
+use common::o_game;
+use tetris_rust::game::{FieldCell, GameState, GAME_HEIGHT, GAME_WIDTH};
+
+fn columns(state: &GameState) -> (i32, i32) {
+    let cells: Vec<i32> = state.current_piece().absolute_cells(state.piece_set()).map(|cell| cell.x).collect();
+    (*cells.iter().min().unwrap(), *cells.iter().max().unwrap())
+}
+
+#[test]
+fn the_piece_moves_to_the_column_asked() {
+    for column in 0..GAME_WIDTH as i32 - 1 {
+        let mut state = o_game();
+        let start = columns(&state).0;
+
+        assert_eq!(state.move_to_column(column), column - start);
+        assert_eq!(columns(&state).0, column);
+    }
+}
+
+#[test]
+fn the_piece_stops_at_the_wall() {
+    let mut state = o_game();
+    state.move_to_column(GAME_WIDTH as i32 - 1);
+
+    assert_eq!(columns(&state).1, GAME_WIDTH as i32 - 1);
+}
+
+#[test]
+fn the_piece_stops_next_to_a_block() {
+    let mut state = o_game();
+    let (left, _) = columns(&state);
+    for y in 0..GAME_HEIGHT {
+        state.set_cell(y, 1, FieldCell::Garbage).unwrap();
+    }
+
+    assert_eq!(state.move_to_column(0), 2 - left);
+    assert_eq!(columns(&state).0, 2);
+}
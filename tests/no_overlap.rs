@@ -0,0 +1,46 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tetris_rust::game::{Action, FieldCell, GameState, RotationSystem, GAME_HEIGHT, GAME_WIDTH};
+
+/// the falling piece is inside the board and only over empty cells
+fn assert_piece_fits(state: &GameState, seed: u64, step: usize) {
+    for cell in state.current_piece().cells(state.piece_set()) {
+        assert!(
+            (0..GAME_HEIGHT as i32).contains(&cell.y) && (0..GAME_WIDTH as i32).contains(&cell.x),
+            "seed {} step {}: cell {:?} out of the board", seed, step, cell
+        );
+        assert_eq!(
+            state.field_cell(cell.y as usize, cell.x as usize), FieldCell::Empty,
+            "seed {} step {}: cell {:?} overlaps the stack", seed, step, cell
+        );
+    }
+}
+
+#[test]
+fn falling_piece_never_overlaps_the_stack() {
+    for seed in 0..40 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut state = GameState::with_seed(seed);
+        state.set_rotation_system(RotationSystem::ALL[seed as usize % RotationSystem::ALL.len()]);
+        state.set_entry_delay(if seed % 2 == 0 { 0 } else { 100 });
+        for step in 0..2000 {
+            // ticks as often as inputs, so gravity and lock delay get a turn
+            if rng.gen() {
+                state.clock_tick();
+            } else {
+                // hard drops less often, to let the pieces move around the stack
+                let action = Action::ALL[rng.gen_range(0, Action::ALL.len())];
+                if action == Action::HardDrop && rng.gen_range(0, 4) > 0 {
+                    continue;
+                }
+                state.apply(action);
+            }
+            if state.is_lost() {
+                // a topped out piece is left where it overlaps
+                state.reset();
+                continue;
+            }
+            assert_piece_fits(&state, seed, step);
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use tetris_rust::game::GameState;
+
+#[test]
+fn paused_clock_ticks_do_nothing() {
+    for seed in 0..10 {
+        let mut state = GameState::with_seed(seed);
+        state.set_paused(true);
+        let piece = state.current_piece();
+        for _ in 0..10_000 {
+            state.clock_tick();
+        }
+        assert_eq!(state.current_piece(), piece);
+        assert_eq!(state.score, 0);
+        assert_eq!(state.pieces_placed(), 0);
+        assert!(!state.is_lost());
+    }
+}
+
+#[test]
+fn clock_runs_again_after_unpausing() {
+    let mut state = GameState::with_seed(1);
+    state.set_paused(true);
+    state.clock_tick();
+    state.set_paused(false);
+    let start = state.current_piece().position();
+    // less than the piece takes to land and lock, after which the next
+    // one would be back where it started
+    for _ in 0..100 {
+        state.clock_tick();
+    }
+    assert_ne!(state.current_piece().position(), start);
+}
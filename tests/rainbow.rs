@@ -0,0 +1,76 @@
+pub mod common;
+
+use common::o_game;
+use std::collections::HashSet;
+use tetris_rust::game::{FieldCell, GameConfig, GameState, PieceShape};
+use tetris_rust::source::FixedSource;
+
+/// drop the falling piece to the far left or right, returns the cells it
+/// locked as
+fn drop_to_side(state: &mut GameState, left: bool) -> Vec<FieldCell> {
+    for _ in 0..5 {
+        if left { state.move_left() } else { state.move_right() }
+    }
+    let cells: Vec<_> = state.ghost_piece().absolute_cells(state.piece_set()).collect();
+    state.move_bottom();
+    cells.iter().map(|cell| state.field_cell(cell.y as usize, cell.x as usize)).collect()
+}
+
+#[test]
+fn pieces_take_the_color_of_their_shape() {
+    let mut state = o_game();
+    let piece = state.current_piece();
+    assert_eq!(piece.color_index(), PieceShape::O.color_index());
+    assert!(drop_to_side(&mut state, true).iter().all(|&cell| cell == FieldCell::Occupied(PieceShape::O)));
+}
+
+#[test]
+fn rainbow_pieces_keep_their_own_color_when_locked() {
+    let mut state = o_game();
+    state.set_rainbow(true);
+    // skip the two pieces drawn before rainbow was turned on
+    drop_to_side(&mut state, true);
+    drop_to_side(&mut state, false);
+    let mut colors = HashSet::new();
+    for i in 0..8 {
+        let color = state.current_piece().color_index();
+        for cell in drop_to_side(&mut state, i % 2 == 0) {
+            match cell {
+                FieldCell::Painted(PieceShape::O, index) => assert_eq!(index as usize, color),
+                cell => panic!("expected a painted O, got {:?}", cell),
+            }
+        }
+        colors.insert(color);
+    }
+    assert!(colors.len() > 1);
+}
+
+#[test]
+fn held_pieces_keep_their_color() {
+    let mut state = GameState::with_source(FixedSource::new(vec![PieceShape::T, PieceShape::L]));
+    state.set_rainbow(true);
+    drop_to_side(&mut state, true);
+    drop_to_side(&mut state, false);
+    let piece = state.current_piece();
+    state.hold();
+    let held = state.held_piece().expect("a piece is held");
+    assert_eq!(held.shape(), piece.shape());
+    assert_eq!(held.color_index(), piece.color_index());
+}
+
+#[test]
+fn seeded_rainbow_games_paint_the_same_colors_from_the_first_piece() {
+    let config = GameConfig { seed: Some(5), rainbow: true, ..GameConfig::default() };
+    let mut first = GameState::with_config(config.clone());
+    let mut second = GameState::with_config(config);
+    let shape = first.current_piece().shape();
+    for cell in drop_to_side(&mut first, true) {
+        assert!(matches!(cell, FieldCell::Painted(painted, _) if painted == shape), "got {:?}", cell);
+    }
+    drop_to_side(&mut second, true);
+    for i in 0..8 {
+        assert_eq!(first.current_piece().color_index(), second.current_piece().color_index());
+        drop_to_side(&mut first, i % 2 == 0);
+        drop_to_side(&mut second, i % 2 == 0);
+    }
+}
@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use tetris_rust::clock::{Clock, RepeatGuard, TestClock};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn rapid_inputs_within_the_cooldown_act_once() {
+    let clock = TestClock::new();
+    let mut guard = RepeatGuard::new(150);
+    let mut allowed = 0;
+    for _ in 0..5 {
+        allowed += guard.allow(clock.now()) as i32;
+        clock.advance(ms(30));
+    }
+    assert_eq!(allowed, 1);
+}
+
+#[test]
+fn a_key_held_down_acts_again_only_after_it_is_let_go() {
+    let clock = TestClock::new();
+    let mut guard = RepeatGuard::new(150);
+    assert!(guard.allow(clock.now()));
+    // the repeats keep the cooldown going long after it would have ended
+    for _ in 0..20 {
+        clock.advance(ms(100));
+        assert!(!guard.allow(clock.now()));
+    }
+    clock.advance(ms(150));
+    assert!(guard.allow(clock.now()));
+}
+
+#[test]
+fn a_cooldown_of_zero_lets_every_input_through() {
+    let mut guard = RepeatGuard::new(0);
+    assert!((0..5).all(|_| guard.allow(ms(0))));
+}
@@ -0,0 +1,191 @@
+use tetris_rust::ai::best_move;
+use tetris_rust::game::{Action, GameError, GameState, ReplayProblem, TICK_MS};
+use tetris_rust::replay::{Replay, ReplayPlayer, Step};
+
+/// a game placing `pieces` pieces where `best_move` puts them, with some
+/// ticks after each one, and the states after each step
+fn recorded_game(pieces: usize) -> (Replay, Vec<GameState>) {
+    let mut replay = Replay::seeded(3);
+    let mut state = replay.start().clone();
+    let mut states = vec![state.clone()];
+    let mut play = |state: &mut GameState, step: Step| {
+        step.run(state);
+        replay.record(step);
+        states.push(state.clone());
+    };
+    for _ in 0..pieces {
+        let target = best_move(&state).expect("there is room for the piece");
+        for _ in 0..target.rotations {
+            play(&mut state, Step::Action(Action::RotateCw));
+        }
+        while state.piece_column() != target.column {
+            let side = if state.piece_column() > target.column { Action::MoveLeft } else { Action::MoveRight };
+            play(&mut state, Step::Action(side));
+        }
+        play(&mut state, Step::Action(Action::HardDrop));
+        for _ in 0..5 {
+            play(&mut state, Step::Tick);
+        }
+    }
+    assert!(!state.is_lost());
+    (replay, states)
+}
+
+fn same_game(a: &GameState, b: &GameState) -> bool {
+    a.snapshot() == b.snapshot() && a.pieces_placed() == b.pieces_placed()
+}
+
+#[test]
+fn playing_forward_goes_through_the_same_states() {
+    let (replay, states) = recorded_game(30);
+    let mut player = ReplayPlayer::new(replay);
+    assert!(same_game(player.state(), &states[0]));
+    for expected in &states[1..] {
+        assert!(player.step_forward());
+        assert!(same_game(player.state(), expected));
+    }
+    assert!(player.is_at_end());
+    assert!(!player.step_forward());
+}
+
+#[test]
+fn seeking_back_matches_playing_forward() {
+    let (replay, states) = recorded_game(30);
+    let mut player = ReplayPlayer::new(replay);
+    player.seek(player.len());
+    for position in (0..states.len()).rev().step_by(7) {
+        player.seek(position);
+        assert_eq!(player.position(), position);
+        assert!(same_game(player.state(), &states[position]), "position {}", position);
+    }
+}
+
+#[test]
+fn stepping_by_piece() {
+    let (replay, _) = recorded_game(25);
+    let mut player = ReplayPlayer::new(replay);
+    assert_eq!(player.piece(), 0);
+    player.next_piece();
+    assert_eq!(player.piece(), 1);
+    assert_eq!(player.state().pieces_placed(), 1);
+    for _ in 0..15 {
+        player.next_piece();
+    }
+    assert_eq!(player.piece(), 16);
+    player.previous_piece();
+    assert_eq!(player.piece(), 15);
+    assert_eq!(player.state().pieces_placed(), 15);
+    player.seek(0);
+    player.previous_piece();
+    assert_eq!(player.position(), 0);
+}
+
+#[test]
+fn jumping_to_line_clears() {
+    let (replay, _) = recorded_game(60);
+    let mut player = ReplayPlayer::new(replay);
+    let mut lines = 0;
+    while player.next_clear() {
+        assert!(player.state().lines_cleared() > lines);
+        lines = player.state().lines_cleared();
+    }
+    assert!(lines > 0);
+    player.seek(player.len());
+    assert_eq!(player.state().lines_cleared(), lines);
+}
+
+#[test]
+fn a_share_string_plays_the_same_game() {
+    let (replay, states) = recorded_game(20);
+    let text = replay.to_share_string().expect("the game started from a seed");
+    assert!(text.starts_with("tr1:3:"));
+    assert!(!text.contains(char::is_whitespace));
+
+    let read = Replay::from_share_string(&text).unwrap();
+    assert_eq!(read.seed(), Some(3));
+    assert_eq!(read.steps(), replay.steps());
+    let mut player = ReplayPlayer::new(read);
+    player.seek(player.len());
+    assert!(same_game(player.state(), states.last().unwrap()));
+}
+
+#[test]
+fn share_strings_of_ticks_alone() {
+    let mut replay = Replay::seeded(0xbeef);
+    assert_eq!(replay.to_share_string().unwrap(), "tr1:beef:");
+    for _ in 0..12 {
+        replay.record(Step::Tick);
+    }
+    replay.record(Step::Action(Action::MoveLeft));
+    replay.record(Step::Action(Action::HardDrop));
+    replay.record(Step::Tick);
+    assert_eq!(replay.to_share_string().unwrap(), "tr1:beef:12ae1");
+    assert_eq!(Replay::from_share_string("tr1:beef:12ae1").unwrap().steps(), replay.steps());
+}
+
+#[test]
+fn long_waits_are_split_to_be_read_back() {
+    let mut replay = Replay::seeded(7);
+    for _ in 0..200_000 {
+        replay.record(Step::Tick);
+    }
+    replay.record(Step::Action(Action::Hold));
+    for _ in 0..144_000 {
+        replay.record(Step::Tick);
+    }
+    let text = replay.to_share_string().unwrap();
+    assert_eq!(text, "tr1:7:72000.72000.56000h72000.72000");
+    assert_eq!(Replay::from_share_string(&text).unwrap().steps(), replay.steps());
+}
+
+#[test]
+fn rules_changed_while_playing_play_back_the_same() {
+    let mut replay = Replay::seeded(5);
+    let mut state = replay.start().clone();
+    let steps = [Step::Action(Action::HardDrop), Step::EntryDelay(300), Step::Action(Action::HardDrop), Step::NextHidden(true)]
+        .iter()
+        .copied()
+        .chain(std::iter::repeat_n(Step::Tick, 4))
+        .chain(Some(Step::Action(Action::HardDrop)));
+    for step in steps {
+        step.run(&mut state);
+        replay.record(step);
+    }
+    // the last drop came during the longer entry delay and did nothing
+    assert!(state.is_entering());
+    assert_eq!(state.pieces_placed(), 2);
+    assert!(state.is_next_hidden());
+
+    let mut player = ReplayPlayer::new(replay.clone());
+    player.seek(player.len());
+    assert!(same_game(player.state(), &state));
+    assert!(player.state().is_next_hidden());
+    // the share string would play it with the default rules
+    assert_eq!(replay.to_share_string(), None);
+}
+
+#[test]
+fn only_games_from_a_seed_can_be_shared() {
+    let replay = Replay::new(&GameState::with_seed(3));
+    assert_eq!(replay.to_share_string(), None);
+}
+
+#[test]
+fn malformed_share_strings_are_rejected() {
+    let problem = |s: &str| match Replay::from_share_string(s) {
+        Err(GameError::InvalidReplay(problem)) => problem,
+        other => panic!("{:?} read as {:?}", s, other.map(|replay| replay.len())),
+    };
+    assert_eq!(problem(""), ReplayProblem::Format);
+    assert_eq!(problem("tr2:3:a"), ReplayProblem::Format);
+    assert_eq!(problem("tr1:3"), ReplayProblem::Format);
+    assert_eq!(problem("tr1:xyz:a"), ReplayProblem::Seed);
+    assert_eq!(problem("tr1::a"), ReplayProblem::Seed);
+    assert_eq!(problem("tr1:3:5az"), ReplayProblem::Character('z'));
+    assert_eq!(problem("tr1:3:a b"), ReplayProblem::Character(' '));
+    assert_eq!(problem("tr1:3:4000000000a"), ReplayProblem::Ticks);
+    // hours of waits, each run short enough, add up to too many steps
+    let hour = format!("{}.", 60 * 60 * 1000 / TICK_MS);
+    assert!(Replay::from_share_string(&format!("tr1:3:{}a", hour.repeat(2))).is_ok());
+    assert_eq!(problem(&format!("tr1:3:{}a", hour.repeat(11))), ReplayProblem::Steps);
+}
@@ -0,0 +1,53 @@
+use tetris_rust::game::{FieldCell, GameEvent, GameState, GAME_HEIGHT, GAME_WIDTH};
+
+/// everything observable about a game except the pieces
+fn assert_like_new(state: &GameState) {
+    let new = GameState::new();
+    assert_eq!(state.score, new.score);
+    assert_eq!(state.level, new.level);
+    assert_eq!(state.lines_cleared(), new.lines_cleared());
+    assert_eq!(state.pieces_placed(), new.pieces_placed());
+    assert_eq!(state.hold_count(), new.hold_count());
+    assert_eq!(state.held_shape(), new.held_shape());
+    assert_eq!(state.finesse_faults(), new.finesse_faults());
+    assert_eq!(state.combo(), new.combo());
+    assert_eq!(state.is_lost(), new.is_lost());
+    assert_eq!(state.is_paused(), new.is_paused());
+    assert_eq!(state.topped_out_piece(), None);
+    assert_eq!(state.cheese_left(), 0);
+    assert_eq!(state.current_piece().position(), new.current_piece().position());
+    assert_eq!(state.current_piece().rotation(), new.current_piece().rotation());
+    for y in 0..GAME_HEIGHT {
+        for x in 0..GAME_WIDTH {
+            assert_eq!(state.field_cell(y, x), FieldCell::Empty);
+        }
+    }
+}
+
+#[test]
+fn reset_after_losing_is_like_a_new_game() {
+    let mut state = GameState::with_seed(4);
+    state.fill_cheese(5, 4);
+    state.hold();
+    while !state.is_lost() {
+        state.move_bottom();
+    }
+    state.set_paused(true);
+    state.reset();
+    assert_like_new(&state);
+    // only the first piece of the new game
+    let shape = state.current_piece().shape();
+    assert_eq!(state.take_events(), vec![GameEvent::PieceSpawned { shape, topped_out: false }]);
+}
+
+#[test]
+fn reset_keeps_drawing_from_the_same_sequence() {
+    let mut state = GameState::with_seed(9);
+    let mut fresh = GameState::with_seed(9);
+    state.reset();
+    // the reset game continues the sequence of the first one, which
+    // started with two pieces
+    fresh.move_bottom();
+    fresh.move_bottom();
+    assert_eq!(state.current_piece().shape(), fresh.current_piece().shape());
+}
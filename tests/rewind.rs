@@ -0,0 +1,99 @@
+pub mod common;
+
+use common::{i_piece_game, o_game};
+use tetris_rust::game::{Action, FieldCell, GameOverReason, GameState, GAME_HEIGHT, GAME_WIDTH, REWIND_PIECES};
+
+/// a game of O pieces with `rewinds` and garbage under the spawn rows,
+/// so a few hard drops top it out
+fn rewind_game(rewinds: u32) -> GameState {
+    let mut state = o_game();
+    state.set_rewinds(rewinds);
+    for y in 8..GAME_HEIGHT {
+        for x in (0..GAME_WIDTH).filter(|&x| x != 0) {
+            state.set_cell(y, x, FieldCell::Garbage).unwrap();
+        }
+    }
+    state
+}
+
+fn drop_until_lost(state: &mut GameState) -> i32 {
+    let mut drops = 0;
+    while !state.is_lost() {
+        state.move_bottom();
+        drops += 1;
+    }
+    drops
+}
+
+#[test]
+fn a_rewind_goes_back_a_few_pieces_and_uses_one_up() {
+    let mut state = rewind_game(2);
+    let drops = drop_until_lost(&mut state);
+    assert!(drops as usize >= REWIND_PIECES);
+    assert!(state.can_rewind());
+    let pieces = state.pieces_placed();
+
+    assert!(state.rewind());
+    assert!(!state.is_lost());
+    assert_eq!(state.rewinds_left(), 1);
+    // the board is back to where it was before the last pieces locked
+    let before = rewind_game(2);
+    let mut redone = before.clone();
+    for _ in 0..drops as usize - REWIND_PIECES {
+        redone.move_bottom();
+    }
+    assert_eq!(state.field_snapshot(true), redone.field_snapshot(true));
+    assert_eq!(state.pieces_placed(), pieces);
+}
+
+#[test]
+fn the_game_ends_once_the_rewinds_are_used_up() {
+    let mut state = rewind_game(2);
+    for left in (0..2).rev() {
+        drop_until_lost(&mut state);
+        assert!(state.rewind());
+        assert_eq!(state.rewinds_left(), left);
+    }
+    drop_until_lost(&mut state);
+
+    assert!(!state.can_rewind());
+    assert!(!state.rewind());
+    assert!(state.is_lost());
+    assert!(matches!(state.game_over_reason(), Some(GameOverReason::BlockOut) | Some(GameOverReason::LockOut)));
+}
+
+#[test]
+fn without_rewinds_a_top_out_ends_the_game() {
+    let mut state = rewind_game(0);
+    drop_until_lost(&mut state);
+    assert!(!state.can_rewind());
+    state.apply(Action::Rewind);
+    assert!(state.is_lost());
+}
+
+#[test]
+fn reset_gives_the_rewinds_back() {
+    let mut state = rewind_game(1);
+    drop_until_lost(&mut state);
+    state.apply(Action::Rewind);
+    assert_eq!(state.rewinds_left(), 0);
+    state.reset();
+    assert_eq!(state.rewinds(), 1);
+    assert_eq!(state.rewinds_left(), 1);
+}
+
+#[test]
+fn a_game_that_ends_otherwise_cannot_be_rewound() {
+    let mut state = i_piece_game();
+    state.set_rewinds(3);
+    state.set_line_goal(Some(1));
+    for x in 4..GAME_WIDTH {
+        state.set_cell(GAME_HEIGHT - 1, x, FieldCell::Garbage).unwrap();
+    }
+    while state.piece_column() > 0 && !state.is_lost() {
+        state.move_left();
+    }
+    state.move_bottom();
+    assert_eq!(state.game_over_reason(), Some(GameOverReason::Completed));
+    assert!(!state.can_rewind());
+}
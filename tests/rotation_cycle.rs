@@ -0,0 +1,74 @@
+pub mod common;
+
+use common::game_of;
+use tetris_rust::game::{GameState, Piece, PieceShape, Point};
+
+const SHAPES: [PieceShape; 7] = [
+    PieceShape::I,
+    PieceShape::O,
+    PieceShape::L,
+    PieceShape::J,
+    PieceShape::T,
+    PieceShape::S,
+    PieceShape::Z,
+];
+
+fn sorted_cells(state: &GameState, piece: &Piece) -> Vec<(i32, i32)> {
+    let mut cells: Vec<(i32, i32)> = piece.cells(state.piece_set()).iter().map(|&Point { x, y }| (y, x)).collect();
+    cells.sort_unstable();
+    cells
+}
+
+/// the piece in its four rotations, starting from the spawn one
+fn rotations(state: &GameState) -> Vec<Piece> {
+    let mut pieces = vec![state.current_piece()];
+    for _ in 0..3 {
+        let last = *pieces.last().unwrap();
+        pieces.push(last.rotated_right());
+    }
+    pieces
+}
+
+#[test]
+fn four_right_turns_come_back_to_the_same_cells() {
+    for &shape in SHAPES.iter() {
+        let state = game_of(shape);
+        for start in rotations(&state) {
+            let turned = (0..4).fold(start, |piece, _| piece.rotated_right());
+            assert_eq!(turned.rotation(), start.rotation(), "{:?}", shape);
+            assert_eq!(sorted_cells(&state, &turned), sorted_cells(&state, &start), "{:?} from {:?}", shape, start.rotation());
+        }
+    }
+}
+
+#[test]
+fn a_left_turn_undoes_a_right_turn() {
+    for &shape in SHAPES.iter() {
+        let state = game_of(shape);
+        for start in rotations(&state) {
+            for back in [start.rotated_right().rotated_left(), start.rotated_left().rotated_right()].iter() {
+                assert_eq!(back.rotation(), start.rotation(), "{:?}", shape);
+                assert_eq!(back.position(), start.position());
+                assert_eq!(sorted_cells(&state, back), sorted_cells(&state, &start));
+            }
+        }
+    }
+}
+
+#[test]
+fn the_o_looks_the_same_in_every_rotation() {
+    let state = game_of(PieceShape::O);
+    let pieces = rotations(&state);
+    for piece in &pieces[1..] {
+        assert_eq!(sorted_cells(&state, piece), sorted_cells(&state, &pieces[0]), "{:?}", piece.rotation());
+    }
+}
+
+#[test]
+fn the_other_shapes_change_when_turned() {
+    for &shape in SHAPES.iter().filter(|&&shape| shape != PieceShape::O) {
+        let state = game_of(shape);
+        let pieces = rotations(&state);
+        assert_ne!(sorted_cells(&state, &pieces[0]), sorted_cells(&state, &pieces[1]), "{:?}", shape);
+    }
+}
@@ -0,0 +1,57 @@
+pub mod common;
+
+use common::{empty_field, s_piece_game};
+use tetris_rust::game::{Direction, Field, FieldCell, GameState, Point, GAME_WIDTH};
+
+/// rows 18 to 21 filled except for a well in columns 4 and 5
+fn narrow_well() -> Field {
+    let mut field = empty_field();
+    for row in field[18..].iter_mut() {
+        *row = [FieldCell::Garbage; GAME_WIDTH];
+        row[4] = FieldCell::Empty;
+        row[5] = FieldCell::Empty;
+    }
+    field
+}
+
+/// a vertical S at the bottom of the well
+fn s_piece_in_the_well() -> GameState {
+    let mut state = s_piece_game();
+    state.set_field(narrow_well());
+    assert!(state.rotate_cw().is_some());
+    while state.can_move(Direction::DOWN) {
+        state.move_down();
+    }
+    assert_eq!(state.current_piece().position(), Point { x: 3, y: 19 });
+    state
+}
+
+#[test]
+fn rotate_is_clockwise() {
+    let mut cw = s_piece_game();
+    let mut rotate = s_piece_game();
+    cw.rotate_cw();
+    rotate.rotate();
+    assert_eq!(cw.current_piece(), rotate.current_piece());
+}
+
+#[test]
+fn the_two_directions_undo_each_other() {
+    let mut state = s_piece_game();
+    let start = state.current_piece();
+    assert!(state.rotate_ccw().is_some());
+    assert_ne!(state.current_piece().rotation(), start.rotation());
+    assert!(state.rotate_cw().is_some());
+    assert_eq!(state.current_piece(), start);
+}
+
+#[test]
+fn counterclockwise_kicks_out_of_a_well_where_clockwise_fails() {
+    let mut state = s_piece_in_the_well();
+    assert!(!state.can_rotate());
+    assert!(state.rotate_cw().is_none());
+    assert!(state.can_rotate_ccw());
+    let rotation = state.rotate_ccw().unwrap();
+    assert_eq!(rotation.kick, Point { x: 1, y: -2 });
+    assert_eq!(state.current_piece().position(), Point { x: 4, y: 17 });
+}
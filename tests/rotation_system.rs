@@ -0,0 +1,138 @@
+pub mod common;
+
+use common::{empty_field, game_of};
+use tetris_rust::game::{
+    Direction, Field, FieldCell, GameState, PieceRotation, PieceShape, Point, RotationSystem, Spin, GAME_WIDTH,
+};
+use tetris_rust::source::FixedSource;
+
+/// a new game where every piece is `shape`, using `system`
+fn game_with(shape: PieceShape, system: RotationSystem) -> GameState {
+    let mut state = game_of(shape);
+    state.set_rotation_system(system);
+    state
+}
+
+/// a T-spin triple slot in column 3 under an overhang at row 17. The T
+/// pointing up slides under the overhang and only gets in the slot with
+/// the last SRS kick, one column left and two rows down
+fn triple_slot() -> Field {
+    let mut field = empty_field();
+    for row in field[19..].iter_mut() {
+        *row = [FieldCell::Garbage; GAME_WIDTH];
+    }
+    field[17][3] = FieldCell::Garbage;
+    field[19][3] = FieldCell::Empty;
+    field[20][3] = FieldCell::Empty;
+    field[20][4] = FieldCell::Empty;
+    field[21][3] = FieldCell::Empty;
+    field
+}
+
+/// the T pointing up, resting on row 19 right of the overhang and
+/// then slid left under it
+fn t_under_overhang(system: RotationSystem) -> GameState {
+    let mut state = game_with(PieceShape::T, system);
+    state.set_field(triple_slot());
+    while state.current_piece().rotation() != PieceRotation::NORMAL {
+        assert!(state.rotate().is_some());
+    }
+    state.move_right();
+    state.move_right();
+    while state.can_move(Direction::DOWN) {
+        state.move_down();
+    }
+    state.move_left();
+    state.move_left();
+    assert_eq!(state.current_piece().position(), Point { x: 3, y: 17 });
+    state
+}
+
+#[test]
+fn srs_kicks_the_t_into_the_triple() {
+    let mut state = t_under_overhang(RotationSystem::Srs);
+    let rotation = state.rotate().unwrap();
+    assert_eq!(rotation.kick, Point { x: -1, y: 2 });
+    assert_eq!(rotation.spin, Spin::Full);
+    state.move_bottom();
+    assert_eq!(state.lines_cleared(), 3);
+}
+
+#[test]
+fn no_kicks_leave_the_t_stuck() {
+    let mut state = t_under_overhang(RotationSystem::Nintendo);
+    let before = state.current_piece();
+    assert!(state.rotate().is_none());
+    assert_eq!(state.current_piece(), before);
+}
+
+#[test]
+fn older_systems_spawn_the_t_flat_side_up() {
+    assert_eq!(game_with(PieceShape::T, RotationSystem::Srs).current_piece().rotation(), PieceRotation::NORMAL);
+    assert_eq!(game_with(PieceShape::T, RotationSystem::Sega).current_piece().rotation(), PieceRotation::REVERSE);
+    assert_eq!(game_with(PieceShape::T, RotationSystem::Nintendo).current_piece().rotation(), PieceRotation::REVERSE);
+}
+
+fn columns(state: &GameState) -> Vec<i32> {
+    let mut columns: Vec<i32> = state.current_piece().absolute_cells(state.piece_set()).map(|cell| cell.x).collect();
+    columns.dedup();
+    columns
+}
+
+/// the I standing against a wall, the right one if `right`
+fn i_against_the_wall(system: RotationSystem, right: bool) -> GameState {
+    let mut state = game_with(PieceShape::I, system);
+    state.move_down();
+    state.move_down();
+    assert!(state.rotate().is_some());
+    let direction = if right { Direction::RIGHT } else { Direction::LEFT };
+    while state.can_move(direction) {
+        if right { state.move_right() } else { state.move_left() }
+    }
+    assert_eq!(columns(&state), vec![if right { GAME_WIDTH as i32 - 1 } else { 0 }]);
+    state
+}
+
+#[test]
+fn nintendo_rotations_against_a_wall_fail() {
+    for &right in &[true, false] {
+        let mut state = i_against_the_wall(RotationSystem::Nintendo, right);
+        let before = state.current_piece();
+        assert!(!state.can_rotate());
+        assert!(state.rotate().is_none());
+        assert!(state.rotate_ccw().is_none());
+        assert_eq!(state.current_piece(), before);
+
+        // the same spot is left with a kick in SRS
+        let mut state = i_against_the_wall(RotationSystem::Srs, right);
+        assert_ne!(state.rotate().unwrap().kick, Point { x: 0, y: 0 });
+    }
+}
+
+#[test]
+fn nintendo_turns_the_i_s_and_z_between_two_orientations() {
+    for &shape in &[PieceShape::I, PieceShape::S, PieceShape::Z] {
+        let mut state = GameState::with_source(FixedSource::new(vec![shape]));
+        state.set_rotation_system(RotationSystem::Nintendo);
+        state.move_down();
+        state.move_down();
+        let lying = state.current_piece();
+        assert_eq!(lying.rotation(), PieceRotation::REVERSE);
+        state.rotate().unwrap();
+        let standing = state.current_piece();
+        assert_eq!(standing.rotation(), PieceRotation::RIGHT);
+        state.rotate().unwrap();
+        assert_eq!(state.current_piece(), lying);
+        state.rotate_ccw().unwrap();
+        assert_eq!(state.current_piece(), standing);
+    }
+}
+
+#[test]
+fn nintendo_spawns_the_i_on_the_row_of_the_flat_t() {
+    let rows = |state: &GameState| state.current_piece().absolute_cells(state.piece_set()).map(|cell| cell.y).max();
+    let i = game_with(PieceShape::I, RotationSystem::Nintendo);
+    assert_eq!(i.current_piece().rotation(), PieceRotation::REVERSE);
+    assert_eq!(rows(&i), rows(&game_with(PieceShape::T, RotationSystem::Nintendo)));
+    assert_eq!(game_with(PieceShape::I, RotationSystem::Sega).current_piece().rotation(), PieceRotation::NORMAL);
+}
@@ -0,0 +1,65 @@
+pub mod common;
+
+use common::{i_piece_game, o_game};
+use tetris_rust::game::{FieldCell, GameState, Point, GAME_HEIGHT, GAME_WIDTH};
+
+/// a game of I pieces with the bottom row full but under the flat I, so
+/// dropping it clears the row
+fn i_game_over_a_gap() -> GameState {
+    let mut state = i_piece_game();
+    let columns: Vec<usize> = state.ghost_piece().absolute_cells(state.piece_set()).map(|cell| cell.x as usize).collect();
+    for x in (0..GAME_WIDTH).filter(|x| !columns.contains(x)) {
+        state.set_cell(GAME_HEIGHT - 1, x, FieldCell::Garbage).unwrap();
+    }
+    state.set_cell(GAME_HEIGHT - 2, 0, FieldCell::Garbage).unwrap();
+    state
+}
+
+#[test]
+fn a_simulated_lock_ends_like_a_real_one() {
+    let state = i_game_over_a_gap();
+    let simulated = state.simulate_lock(state.ghost_piece()).unwrap();
+    let mut played = state.clone();
+    played.move_bottom();
+
+    assert_eq!(simulated.field_snapshot(true), played.field_snapshot(true));
+    assert_eq!(simulated.lines_cleared(), 1);
+    assert_eq!(simulated.lines_cleared(), played.lines_cleared());
+    assert_eq!(simulated.score, played.score);
+    assert_eq!(simulated.pieces_placed(), played.pieces_placed());
+    // the falling piece is still the one that was placed
+    assert_eq!(simulated.current_piece(), state.ghost_piece());
+}
+
+#[test]
+fn simulating_a_lock_leaves_the_game_alone() {
+    let state = i_game_over_a_gap();
+    let before = state.snapshot();
+    let piece = state.current_piece();
+    state.simulate_lock(state.ghost_piece()).unwrap();
+
+    assert_eq!(state.snapshot(), before);
+    assert_eq!(state.current_piece(), piece);
+    assert_eq!(state.lines_cleared(), 0);
+    assert_eq!(state.pieces_placed(), 0);
+}
+
+#[test]
+fn a_lock_in_the_spawn_rows_does_not_end_the_simulated_game() {
+    let state = o_game();
+    let simulated = state.simulate_lock(state.current_piece()).unwrap();
+
+    assert!(!simulated.is_lost());
+    assert_eq!(simulated.pieces_placed(), 1);
+}
+
+#[test]
+fn a_piece_that_does_not_fit_is_not_simulated() {
+    let state = i_game_over_a_gap();
+    let piece = state.current_piece();
+    let off_the_board = piece.with_position(Point { x: -(GAME_WIDTH as i32), ..piece.position() });
+    assert!(state.simulate_lock(off_the_board).is_none());
+    let ghost = state.ghost_piece();
+    let in_the_stack = ghost.with_position(Point { y: ghost.position().y + 1, ..ghost.position() });
+    assert!(state.simulate_lock(in_the_stack).is_none());
+}
@@ -0,0 +1,68 @@
+use tetris_rust::game::{GameState, PieceShape, GAME_HEIGHT, GAME_WIDTH, POINTS_PER_LEVEL};
+use tetris_rust::source::FixedSource;
+
+// enough steps to fill every cell of the board one piece at a time
+const MAX_STEPS: usize = GAME_WIDTH * GAME_HEIGHT * GAME_HEIGHT;
+
+#[test]
+fn gravity_alone_tops_out() {
+    for seed in 0..20 {
+        let mut state = GameState::with_seed(seed);
+        let (mut score, mut lines, mut pieces) = (0, 0, 0);
+        let mut steps = 0;
+        while !state.is_lost() {
+            assert!(steps < MAX_STEPS, "seed {}: no top out after {} steps", seed, steps);
+            state.move_down();
+            steps += 1;
+
+            assert!(state.score >= score);
+            assert!(state.lines_cleared() >= lines);
+            assert!(state.pieces_placed() == pieces || state.pieces_placed() == pieces + 1);
+            assert_eq!(state.level, 1 + state.score / POINTS_PER_LEVEL);
+            assert!(state.points_to_next_level() > 0);
+            assert!(state.points_to_next_level() <= POINTS_PER_LEVEL);
+            score = state.score;
+            lines = state.lines_cleared();
+            pieces = state.pieces_placed();
+        }
+        assert!(pieces > 0);
+        assert!(state.topped_out_piece().is_some());
+    }
+}
+
+#[test]
+fn same_seed_plays_the_same_game() {
+    let mut first = GameState::with_seed(7);
+    let mut second = GameState::with_seed(7);
+    while !first.is_lost() {
+        first.move_down();
+        second.move_down();
+        assert_eq!(first.current_piece(), second.current_piece());
+    }
+    assert!(second.is_lost());
+    assert_eq!(first.pieces_placed(), second.pieces_placed());
+}
+
+#[test]
+fn scripted_source_plays_the_pieces_in_order() {
+    let script = vec![PieceShape::T, PieceShape::I, PieceShape::O, PieceShape::J];
+    let mut state = GameState::with_source(FixedSource::new(script.clone()));
+    let mut played = Vec::new();
+    while played.len() < script.len() * 2 {
+        played.push(state.current_piece().shape());
+        state.move_bottom();
+    }
+    let expected: Vec<PieceShape> = script.iter().cycle().take(script.len() * 2).copied().collect();
+    assert_eq!(played, expected);
+}
+
+#[test]
+fn cloned_game_replays_the_same_pieces() {
+    let mut first = GameState::with_seed(3);
+    let mut second = first.clone();
+    while !first.is_lost() {
+        first.move_bottom();
+        second.move_bottom();
+        assert_eq!(first.current_piece(), second.current_piece());
+    }
+}
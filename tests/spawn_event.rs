@@ -0,0 +1,127 @@
+pub mod common;
+
+use common::{empty_field, i_piece_game, o_game};
+use std::cell::RefCell;
+use std::rc::Rc;
+use tetris_rust::game::{Field, FieldCell, GameEvent, GameState, PieceShape, GAME_HEIGHT, GAME_WIDTH};
+use tetris_rust::source::FixedSource;
+
+fn spawns(events: &[GameEvent]) -> Vec<(PieceShape, bool)> {
+    events.iter()
+        .filter_map(|event| match *event {
+            GameEvent::PieceSpawned { shape, topped_out } => Some((shape, topped_out)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn each_new_piece_is_an_event_after_the_lock() {
+    let mut state = GameState::with_source(FixedSource::new(vec![PieceShape::I, PieceShape::T]));
+    state.move_bottom();
+    let events = state.take_events();
+
+    assert!(matches!(events[0], GameEvent::PieceLocked { .. }));
+    assert_eq!(spawns(&events), vec![(PieceShape::T, false)]);
+    assert_eq!(events.last(), Some(&GameEvent::PieceSpawned { shape: PieceShape::T, topped_out: false }));
+}
+
+#[test]
+fn the_lock_event_has_the_board_before_the_clear() {
+    let mut state = i_piece_game();
+    let columns: Vec<usize> = state.ghost_piece().absolute_cells(state.piece_set()).map(|cell| cell.x as usize).collect();
+    for x in (0..GAME_WIDTH).filter(|x| !columns.contains(x)) {
+        state.set_cell(GAME_HEIGHT - 1, x, FieldCell::Garbage).unwrap();
+    }
+    state.move_bottom();
+
+    assert_eq!(state.lines_cleared(), 1);
+    match &state.take_events()[0] {
+        GameEvent::PieceLocked { cells, field } => {
+            assert!(cells.iter().all(|cell| cell.y as usize == GAME_HEIGHT - 1));
+            // the row is still full, with the piece in it
+            assert!(field[GAME_HEIGHT - 1].iter().all(|&cell| cell != FieldCell::Empty));
+            for cell in cells {
+                assert_eq!(field[cell.y as usize][cell.x as usize], FieldCell::Occupied(PieceShape::I));
+            }
+        },
+        event => panic!("expected the lock first, got {:?}", event),
+    }
+}
+
+#[test]
+fn a_spawn_over_the_stack_says_it_topped_out() {
+    let mut state = o_game();
+    let mut field: Field = empty_field();
+    // a column under the spawn, high enough for the next O to land on it
+    for row in field[3..].iter_mut() {
+        row[4] = FieldCell::Garbage;
+        row[5] = FieldCell::Garbage;
+    }
+    for cell in state.current_piece().absolute_cells(state.piece_set()) {
+        field[cell.y as usize][cell.x as usize] = FieldCell::Empty;
+    }
+    state.set_field(field);
+    state.move_bottom();
+
+    assert!(state.is_lost());
+    assert_eq!(spawns(&state.take_events()), vec![(PieceShape::O, true)]);
+}
+
+#[test]
+fn the_hook_hears_of_the_spawn_before_the_piece_can_move() {
+    let mut state = GameState::with_source(FixedSource::new(vec![PieceShape::I, PieceShape::S]));
+    state.set_entry_delay(200);
+    let heard = Rc::new(RefCell::new(Vec::new()));
+    let log = heard.clone();
+    state.set_spawn_hook(Some(Rc::new(move |event: &GameEvent| log.borrow_mut().push(event.clone()))));
+    state.move_bottom();
+
+    assert!(state.is_entering());
+    assert_eq!(*heard.borrow(), vec![GameEvent::PieceSpawned { shape: PieceShape::S, topped_out: false }]);
+    // the same event goes to the event stream
+    assert_eq!(spawns(&state.take_events()), vec![(PieceShape::S, false)]);
+
+    state.set_spawn_hook(None);
+    state.set_entry_delay(0);
+    for _ in 0..10 {
+        state.clock_tick();
+    }
+    state.move_bottom();
+    assert_eq!(heard.borrow().len(), 1);
+}
+
+#[test]
+fn a_hold_with_nothing_held_spawns_the_next_piece() {
+    let mut state = GameState::with_source(FixedSource::new(vec![PieceShape::T, PieceShape::L, PieceShape::J]));
+    let heard = Rc::new(RefCell::new(Vec::new()));
+    let log = heard.clone();
+    state.set_spawn_hook(Some(Rc::new(move |event: &GameEvent| log.borrow_mut().push(event.clone()))));
+    state.hold();
+
+    assert_eq!(*heard.borrow(), vec![GameEvent::PieceSpawned { shape: PieceShape::L, topped_out: false }]);
+    assert_eq!(spawns(&state.take_events()), vec![(PieceShape::L, false)]);
+
+    // the held T coming back isn't a new piece
+    state.move_bottom();
+    state.take_events();
+    heard.borrow_mut().clear();
+    state.hold();
+    assert_eq!(state.current_piece().shape(), PieceShape::T);
+    assert!(heard.borrow().is_empty());
+    assert!(spawns(&state.take_events()).is_empty());
+}
+
+#[test]
+fn a_reset_spawns_the_first_piece() {
+    let mut state = GameState::with_source(FixedSource::new(vec![PieceShape::Z, PieceShape::O]));
+    assert!(state.take_events().is_empty());
+    let heard = Rc::new(RefCell::new(Vec::new()));
+    let log = heard.clone();
+    state.set_spawn_hook(Some(Rc::new(move |event: &GameEvent| log.borrow_mut().push(event.clone()))));
+    state.reset();
+
+    let shape = state.current_piece().shape();
+    assert_eq!(*heard.borrow(), vec![GameEvent::PieceSpawned { shape, topped_out: false }]);
+    assert_eq!(spawns(&state.take_events()), vec![(shape, false)]);
+}
@@ -0,0 +1,121 @@
+use tetris_rust::game::{FieldCell, GameConfig, GameState, PieceShape, GAME_HEIGHT, GAME_WIDTH};
+use tetris_rust::theme::{available_color, color_slot, half_block, Theme, PIECE_COLORS};
+
+/// the color of each cell of the board, with the falling piece, in `theme`
+fn rendered(state: &GameState, theme: Theme) -> Vec<Option<i16>> {
+    (0..GAME_HEIGHT)
+        .flat_map(|y| (0..GAME_WIDTH).map(move |x| theme.cell_color(state.get(y, x))))
+        .collect()
+}
+
+#[test]
+fn each_theme_has_its_colors() {
+    // mono tells the pieces apart from the garbage only
+    let mono = Theme::Mono.colors();
+    assert!(mono[..7].iter().all(|&color| color == mono[0]));
+    assert_ne!(mono[7], mono[0]);
+    // the garbage is never the color of a piece, except in mono
+    for theme in [Theme::Classic, Theme::Pastel].iter() {
+        let colors = theme.colors();
+        assert!(!colors[..7].contains(&colors[7]), "{:?}", theme);
+    }
+}
+
+#[test]
+fn missing_colors_become_white() {
+    assert_eq!(available_color(3, 8), 3);
+    assert_eq!(available_color(7, 8), 7);
+    assert_eq!(available_color(203, 8), 7);
+    assert_eq!(available_color(203, 256), 203);
+    assert_eq!(available_color(8, 16), 8);
+    // without colors everything is white
+    assert_eq!(available_color(1, 0), 7);
+    // on 8 colors the classic theme only loses the orange of the L
+    for (i, &color) in Theme::Classic.colors().iter().enumerate() {
+        assert_eq!(available_color(color, 8) == color, i != PieceShape::L.color_index(), "color {}", i);
+    }
+}
+
+#[test]
+fn cycling_all_themes_comes_back_to_the_start() {
+    for &start in Theme::ALL.iter() {
+        let mut theme = start;
+        for _ in 0..Theme::ALL.len() {
+            theme = theme.next();
+        }
+        assert_eq!(theme, start);
+        assert_eq!(theme.colors(), start.colors());
+    }
+}
+
+#[test]
+fn cycling_through_all_themes_and_back_renders_the_board_the_same() {
+    let mut state = GameState::with_config(GameConfig { seed: Some(4), rainbow: true, ..GameConfig::default() });
+    state.add_garbage(2);
+    for i in 0..6 {
+        for _ in 0..i {
+            state.move_right();
+        }
+        state.move_bottom();
+    }
+    let before = rendered(&state, Theme::default());
+    let mut theme = Theme::default();
+    for _ in 0..Theme::ALL.len() {
+        theme = theme.next();
+        if theme != Theme::default() {
+            assert_ne!(rendered(&state, theme), before, "{:?}", theme);
+        }
+    }
+    assert_eq!(rendered(&state, theme), before);
+}
+
+#[test]
+fn cells_take_the_color_of_their_piece_or_of_the_garbage() {
+    assert_eq!(color_slot(FieldCell::Empty), None);
+    assert_eq!(color_slot(FieldCell::Occupied(PieceShape::T)), Some(PieceShape::T.color_index()));
+    assert_eq!(color_slot(FieldCell::Painted(PieceShape::T, 2)), Some(2));
+    assert_eq!(color_slot(FieldCell::Garbage), Some(PIECE_COLORS));
+    let classic = Theme::Classic.colors();
+    assert_eq!(Theme::Classic.cell_color(FieldCell::Garbage), Some(classic[PIECE_COLORS]));
+}
+
+#[test]
+fn cycling_visits_every_theme() {
+    let mut theme = Theme::default();
+    let mut seen = Vec::new();
+    for _ in 0..Theme::ALL.len() {
+        seen.push(theme);
+        theme = theme.next();
+    }
+    for t in Theme::ALL.iter() {
+        assert!(seen.contains(t));
+    }
+}
+
+#[test]
+fn theme_names_parse_back() {
+    for &theme in Theme::ALL.iter() {
+        assert_eq!(theme.name().parse(), Ok(theme));
+    }
+    assert_eq!("neon".parse::<Theme>(), Err(()));
+}
+
+#[test]
+fn every_standard_piece_has_its_own_color() {
+    let shapes = [PieceShape::I, PieceShape::O, PieceShape::L, PieceShape::J, PieceShape::T, PieceShape::S, PieceShape::Z];
+    let colors: Vec<usize> = shapes.iter().map(|shape| shape.color_index()).collect();
+    assert_eq!(colors, (0..PIECE_COLORS).collect::<Vec<_>>());
+    // custom pieces after the seventh start again from the first color
+    assert_eq!(PieceShape(7).color_index(), PieceShape::I.color_index());
+}
+
+#[test]
+fn stacked_cells_keep_their_colors() {
+    assert_eq!(half_block::<i16>(None, None), (' ', None, None));
+    assert_eq!(half_block(Some(1), None), ('▀', Some(1), None));
+    assert_eq!(half_block(None, Some(2)), ('▄', Some(2), None));
+    assert_eq!(half_block(Some(3), Some(3)), ('▀', Some(3), Some(3)));
+    // the upper color is in front, the lower one behind
+    assert_eq!(half_block(Some(4), Some(8)), ('▀', Some(4), Some(8)));
+    assert_eq!(half_block(Some(8), Some(4)), ('▀', Some(8), Some(4)));
+}
@@ -0,0 +1,58 @@
+use std::rc::Rc;
+use std::time::Duration;
+
+use tetris_rust::clock::TestClock;
+use tetris_rust::game::GameState;
+
+fn game_with_clock() -> (GameState, TestClock) {
+    let clock = TestClock::new();
+    let mut game = GameState::with_seed(1);
+    game.set_clock(Rc::new(clock.clone()));
+    (game, clock)
+}
+
+#[test]
+fn timer_counts_the_time_played() {
+    let (game, clock) = game_with_clock();
+    assert_eq!(game.elapsed(), Duration::from_secs(0));
+    clock.advance(Duration::from_secs(75));
+    assert_eq!(game.elapsed(), Duration::from_secs(75));
+}
+
+#[test]
+fn paused_time_is_not_counted() {
+    let (mut game, clock) = game_with_clock();
+    clock.advance(Duration::from_secs(10));
+    game.set_paused(true);
+    clock.advance(Duration::from_secs(30));
+    assert_eq!(game.elapsed(), Duration::from_secs(10));
+    game.set_paused(false);
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(game.elapsed(), Duration::from_secs(15));
+    game.set_paused(true);
+    game.set_paused(true);
+    clock.advance(Duration::from_secs(20));
+    game.set_paused(false);
+    assert_eq!(game.elapsed(), Duration::from_secs(15));
+}
+
+#[test]
+fn reset_restarts_the_timer() {
+    let (mut game, clock) = game_with_clock();
+    clock.advance(Duration::from_secs(10));
+    game.set_paused(true);
+    clock.advance(Duration::from_secs(10));
+    game.reset();
+    clock.advance(Duration::from_secs(3));
+    assert_eq!(game.elapsed(), Duration::from_secs(3));
+}
+
+#[test]
+fn pieces_per_second_uses_the_game_clock() {
+    let (mut game, clock) = game_with_clock();
+    assert_eq!(game.pieces_per_second(), 0.0);
+    game.move_bottom();
+    game.move_bottom();
+    clock.advance(Duration::from_secs(4));
+    assert_eq!(game.pieces_per_second(), 0.5);
+}
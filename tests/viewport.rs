@@ -0,0 +1,49 @@
+use tetris_rust::game::GAME_HEIGHT;
+use tetris_rust::viewport::Viewport;
+
+#[test]
+fn board_smaller_than_the_terminal_never_scrolls() {
+    let mut viewport = Viewport::new(GAME_HEIGHT, 40);
+    assert_eq!(viewport.rows(), GAME_HEIGHT);
+    for (first, last) in [(0, 1), (10, 12), (20, 21), (-2, 0)].iter() {
+        viewport.follow(*first, *last);
+        assert_eq!(viewport.top(), 0);
+        assert!(!viewport.hides_above() && !viewport.hides_below());
+    }
+    for y in 0..GAME_HEIGHT as i32 {
+        assert_eq!(viewport.row(y), Some(y));
+    }
+}
+
+#[test]
+fn board_taller_than_the_terminal_follows_the_piece() {
+    let mut viewport = Viewport::new(GAME_HEIGHT, 10);
+    assert_eq!(viewport.rows(), 10);
+    assert_eq!(viewport.top(), GAME_HEIGHT - 10);
+
+    viewport.follow(0, 1);
+    assert_eq!(viewport.top(), 0);
+    assert!(viewport.hides_below() && !viewport.hides_above());
+    assert_eq!(viewport.row(0), Some(0));
+    assert_eq!(viewport.row(15), None);
+
+    // a piece falling one row at a time stays in view with its margin
+    for y in 0..GAME_HEIGHT as i32 - 1 {
+        viewport.follow(y, y + 1);
+        let (first, last) = (viewport.row(y), viewport.row(y + 1));
+        assert!(first.is_some() && last.is_some(), "row {} out of view at top {}", y, viewport.top());
+        assert!(last.unwrap() < 10 - 2 || !viewport.hides_below());
+    }
+    assert_eq!(viewport.top(), GAME_HEIGHT - 10);
+    assert!(viewport.hides_above() && !viewport.hides_below());
+    assert_eq!(viewport.row(GAME_HEIGHT as i32 - 1), Some(9));
+    assert_eq!(viewport.row(GAME_HEIGHT as i32), None);
+}
+
+#[test]
+fn piece_taller_than_the_view_shows_its_bottom() {
+    let mut viewport = Viewport::new(GAME_HEIGHT, 3);
+    viewport.follow(5, 9);
+    assert_eq!(viewport.row(9), Some(2));
+    assert_eq!(viewport.row(5), None);
+}
@@ -0,0 +1,122 @@
+pub mod common;
+
+use common::{empty_field, row, zone_game};
+use tetris_rust::game::{Field, FieldCell, GameEvent, GameState, GAME_HEIGHT, GAME_WIDTH, TICK_MS, ZONE_CHARGE_LINES, ZONE_MS};
+
+type Row = [FieldCell; GAME_WIDTH];
+
+/// a garbage row with holes where the flat I piece lands, or with blocks
+/// only there if `inverted`
+fn i_row(state: &GameState, inverted: bool) -> Row {
+    let columns: Vec<usize> = state.ghost_piece().cells(state.piece_set()).iter().map(|cell| cell.x as usize).collect();
+    let mut row = [FieldCell::Empty; GAME_WIDTH];
+    for (x, cell) in row.iter_mut().enumerate() {
+        if columns.contains(&x) == inverted {
+            *cell = FieldCell::Garbage;
+        }
+    }
+    row
+}
+
+fn field_with(rows: &[(usize, Row)]) -> Field {
+    let mut field = empty_field();
+    for &(y, row) in rows {
+        field[y] = row;
+    }
+    field
+}
+
+fn is_full(row: &Row) -> bool {
+    !row.contains(&FieldCell::Empty)
+}
+
+/// clear single lines until the meter is full
+fn charge(state: &mut GameState) {
+    for _ in 0..ZONE_CHARGE_LINES {
+        let gap = i_row(state, false);
+        state.set_field(field_with(&[(GAME_HEIGHT - 1, gap)]));
+        state.move_bottom();
+    }
+}
+
+fn run_zone_out(state: &mut GameState) {
+    for _ in 0..ZONE_MS / TICK_MS {
+        state.clock_tick();
+    }
+}
+
+#[test]
+fn cleared_lines_charge_the_meter() {
+    let mut state = zone_game();
+    assert_eq!(state.zone_charge(), 0);
+    assert!(!state.start_zone());
+    charge(&mut state);
+    assert_eq!(state.zone_charge(), ZONE_CHARGE_LINES);
+    assert!(state.start_zone());
+    assert!(state.is_zone_active());
+    assert_eq!(state.zone_charge(), 0);
+    assert!(!state.start_zone());
+}
+
+#[test]
+fn the_zone_needs_the_mode() {
+    let mut state = zone_game();
+    state.set_zone_enabled(false);
+    charge(&mut state);
+    assert_eq!(state.zone_charge(), 0);
+    assert!(!state.start_zone());
+}
+
+#[test]
+fn gravity_stops_during_the_zone() {
+    let mut state = zone_game();
+    charge(&mut state);
+    state.set_field(field_with(&[]));
+    assert!(state.start_zone());
+    let piece = state.current_piece();
+    for _ in 0..ZONE_MS / TICK_MS - 1 {
+        state.clock_tick();
+    }
+    assert_eq!(state.current_piece(), piece);
+    state.clock_tick();
+    assert!(!state.is_zone_active());
+}
+
+#[test]
+fn lines_pile_at_the_bottom_and_clear_at_the_end() {
+    let mut state = zone_game();
+    charge(&mut state);
+    let (lines, score) = (state.lines_cleared(), state.score);
+    let gap = i_row(&state, false);
+    let marker = i_row(&state, true);
+    // the I completes row 20 and rests on the marker row under it
+    state.set_field(field_with(&[(GAME_HEIGHT - 2, gap), (GAME_HEIGHT - 1, marker)]));
+    assert!(state.start_zone());
+    state.move_bottom();
+    assert_eq!(state.zone_lines(), 1);
+    assert_eq!(state.lines_cleared(), lines);
+    assert!(is_full(&row(&state, GAME_HEIGHT - 1)));
+    assert_eq!(row(&state, GAME_HEIGHT - 2), marker);
+
+    // a second line piles over the first one
+    let mut field = field_with(&[(GAME_HEIGHT - 3, gap), (GAME_HEIGHT - 2, marker)]);
+    field[GAME_HEIGHT - 1] = row(&state, GAME_HEIGHT - 1);
+    state.set_field(field);
+    state.move_bottom();
+    assert_eq!(state.zone_lines(), 2);
+    assert!(is_full(&row(&state, GAME_HEIGHT - 2)));
+    assert_eq!(row(&state, GAME_HEIGHT - 3), marker);
+    state.take_events();
+
+    run_zone_out(&mut state);
+    assert_eq!(state.zone_lines(), 0);
+    assert_eq!(state.lines_cleared(), lines + 2);
+    assert_eq!(state.score, score + 400);
+    assert_eq!(row(&state, GAME_HEIGHT - 1), marker);
+    assert_eq!(row(&state, GAME_HEIGHT - 2), [FieldCell::Empty; GAME_WIDTH]);
+    let zone_bonus = state.take_events().into_iter().find_map(|event| match event {
+        GameEvent::Scored(breakdown) => Some(breakdown.zone),
+        _ => None,
+    });
+    assert_eq!(zone_bonus, Some(200));
+}